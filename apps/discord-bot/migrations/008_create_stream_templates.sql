@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS stream_templates (
+    guild_id BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    content TEXT NOT NULL DEFAULT '',
+    title TEXT NOT NULL DEFAULT '',
+    description TEXT NOT NULL DEFAULT '',
+    image_url TEXT NOT NULL DEFAULT '',
+    thumbnail_url TEXT NOT NULL DEFAULT '',
+    show_fields BOOLEAN NOT NULL DEFAULT TRUE,
+    PRIMARY KEY (guild_id, kind)
+);
@@ -0,0 +1,7 @@
+ALTER TABLE stream_sessions
+    ADD COLUMN IF NOT EXISTS twitch_stream_id TEXT,
+    ADD COLUMN IF NOT EXISTS viewer_sample_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS viewer_sample_total BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS titles TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS games TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS vod_url TEXT;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS stream_alert_templates (
+    guild_id BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    template TEXT NOT NULL,
+    PRIMARY KEY (guild_id, kind)
+);
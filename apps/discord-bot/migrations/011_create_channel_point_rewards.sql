@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS channel_point_rewards (
+    reward_id TEXT PRIMARY KEY NOT NULL,
+    guild_id BIGINT NOT NULL,
+    action TEXT NOT NULL,
+    role_id BIGINT,
+    duration_secs INTEGER,
+    channel_id BIGINT,
+    amount INTEGER,
+    message TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_channel_point_rewards_guild ON channel_point_rewards (guild_id);
+
+CREATE TABLE IF NOT EXISTS temporary_roles (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    role_id BIGINT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (guild_id, user_id, role_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_temporary_roles_expiry ON temporary_roles (expires_at);
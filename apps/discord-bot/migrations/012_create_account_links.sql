@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS account_links (
+    discord_user_id BIGINT NOT NULL,
+    platform TEXT NOT NULL,
+    platform_user_id TEXT NOT NULL,
+    platform_login TEXT NOT NULL,
+    linked_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (discord_user_id, platform),
+    UNIQUE (platform, platform_user_id)
+);
@@ -0,0 +1,34 @@
+CREATE TABLE IF NOT EXISTS clip_submissions (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    clip_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    url TEXT NOT NULL,
+    creator_name TEXT NOT NULL,
+    thumbnail_url TEXT NOT NULL,
+    submitted_by BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    message_id BIGINT,
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (guild_id, clip_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_clip_submissions_submitted ON clip_submissions (guild_id, submitted_at);
+
+CREATE TABLE IF NOT EXISTS clip_votes (
+    submission_id BIGINT NOT NULL REFERENCES clip_submissions (id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL,
+    value SMALLINT NOT NULL CHECK (value IN (-1, 1)),
+    voted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (submission_id, user_id)
+);
+
+-- Archived weekly leaderboards; rank 1 is the Clip of the Week.
+CREATE TABLE IF NOT EXISTS clip_weekly_results (
+    guild_id BIGINT NOT NULL,
+    week_ending TIMESTAMPTZ NOT NULL,
+    rank INTEGER NOT NULL,
+    submission_id BIGINT NOT NULL REFERENCES clip_submissions (id) ON DELETE CASCADE,
+    score INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, week_ending, rank)
+);
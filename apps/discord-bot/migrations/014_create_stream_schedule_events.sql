@@ -0,0 +1,10 @@
+-- Discord Scheduled Events mirrored from Twitch schedule segments.
+CREATE TABLE IF NOT EXISTS stream_schedule_events (
+    guild_id BIGINT NOT NULL,
+    segment_id TEXT NOT NULL,
+    event_id BIGINT NOT NULL,
+    title TEXT NOT NULL,
+    start_time TIMESTAMPTZ NOT NULL,
+    end_time TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (guild_id, segment_id)
+);
@@ -0,0 +1,11 @@
+-- Twitch OAuth tokens, kept across restarts because refresh tokens rotate.
+CREATE TABLE IF NOT EXISTS twitch_tokens (
+    kind TEXT PRIMARY KEY,
+    access_token TEXT NOT NULL,
+    refresh_token TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    login TEXT,
+    user_id TEXT,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,2 @@
+-- Default repository for the GitHub commands, set with /defaultrepo.
+ALTER TABLE guild_config ADD COLUMN IF NOT EXISTS github_repo TEXT;
@@ -0,0 +1,7 @@
+-- Channels where `#123` and `owner/repo#45` expand into GitHub embeds.
+CREATE TABLE IF NOT EXISTS github_autolink_channels (
+    channel_id BIGINT PRIMARY KEY,
+    guild_id BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_github_autolink_channels_guild ON github_autolink_channels (guild_id);
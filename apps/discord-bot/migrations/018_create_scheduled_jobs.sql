@@ -0,0 +1,20 @@
+-- Cron-style and one-shot jobs run by the scheduler.
+-- Recurring jobs have a unique `key` and a `cron` expression; one-shot jobs have neither.
+CREATE TABLE IF NOT EXISTS scheduled_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    key TEXT UNIQUE,
+    kind TEXT NOT NULL,
+    guild_id BIGINT,
+    payload JSONB NOT NULL DEFAULT 'null',
+    cron TEXT,
+    run_at TIMESTAMPTZ NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- Set while a run is in progress; an expired lease means the run was interrupted.
+    locked_until TIMESTAMPTZ,
+    last_run_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_jobs_run_at ON scheduled_jobs (run_at);
+CREATE INDEX IF NOT EXISTS idx_scheduled_jobs_guild ON scheduled_jobs (guild_id);
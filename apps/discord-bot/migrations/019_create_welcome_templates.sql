@@ -0,0 +1,12 @@
+-- Per-guild welcome and leave messages. Guilds without a row use the built-in defaults.
+CREATE TABLE IF NOT EXISTS welcome_templates (
+    guild_id BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Falls back to WELCOME_CHANNEL_ID when unset.
+    channel_id BIGINT,
+    embed BOOLEAN NOT NULL DEFAULT TRUE,
+    title TEXT NOT NULL DEFAULT '',
+    message TEXT NOT NULL,
+    PRIMARY KEY (guild_id, kind)
+);
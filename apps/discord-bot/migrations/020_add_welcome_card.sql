@@ -0,0 +1,2 @@
+-- Attach a generated image card to welcome messages.
+ALTER TABLE welcome_templates ADD COLUMN IF NOT EXISTS card BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,21 @@
+-- Join verification gate: new members must verify in `channel_id` before getting the auto-roles.
+CREATE TABLE IF NOT EXISTS verification_config (
+    guild_id BIGINT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    channel_id BIGINT NOT NULL,
+    mode TEXT NOT NULL DEFAULT 'button',
+    timeout_minutes INT NOT NULL DEFAULT 10
+);
+
+-- Members waiting at the gate. Removed when they verify, leave or time out.
+CREATE TABLE IF NOT EXISTS pending_verifications (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    message_id BIGINT,
+    -- The captcha answer currently shown to the member
+    challenge TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1,29 @@
+-- Raid detection settings. Guilds without a row use the defaults.
+CREATE TABLE IF NOT EXISTS raid_config (
+    guild_id BIGINT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    join_threshold INT NOT NULL DEFAULT 10,
+    window_seconds INT NOT NULL DEFAULT 30,
+    account_age_days INT NOT NULL DEFAULT 7,
+    -- Falls back to LOG_CHANNEL_ID when unset.
+    alert_channel_id BIGINT,
+    alert_role_id BIGINT,
+    -- Channels whose @everyone overwrite denies sending during a lockdown.
+    lock_channel_ids BIGINT[] NOT NULL DEFAULT '{}'
+);
+
+-- An active lockdown and what it changed, so it can be undone after a restart.
+CREATE TABLE IF NOT EXISTS raid_lockdowns (
+    guild_id BIGINT PRIMARY KEY,
+    reason TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    previous_verification_level SMALLINT,
+    -- [{channel_id, allow, deny}] with allow/deny null when @everyone had no overwrite
+    locked_channels JSONB NOT NULL DEFAULT '[]',
+    -- Members who joined as part of the raid or during the lockdown
+    user_ids BIGINT[] NOT NULL DEFAULT '{}',
+    -- Members who joined during the lockdown and are waiting to be let in
+    held_user_ids BIGINT[] NOT NULL DEFAULT '{}',
+    alert_channel_id BIGINT,
+    alert_message_id BIGINT
+);
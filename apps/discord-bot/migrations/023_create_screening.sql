@@ -0,0 +1,37 @@
+-- Join screening settings. Guilds without a row don't screen.
+CREATE TABLE IF NOT EXISTS screening_config (
+    guild_id BIGINT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Accounts younger than this are screened; 0 turns the age check off
+    min_account_age_days INT NOT NULL DEFAULT 7,
+    -- Also screen accounts that never set an avatar
+    default_avatar BOOLEAN NOT NULL DEFAULT FALSE,
+    -- 'flag', 'quarantine' or 'kick'
+    action TEXT NOT NULL DEFAULT 'flag',
+    quarantine_role_id BIGINT,
+    -- Falls back to LOG_CHANNEL_ID when unset.
+    summary_channel_id BIGINT
+);
+
+-- Members who skip screening.
+CREATE TABLE IF NOT EXISTS screening_allowlist (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    added_by BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (guild_id, user_id)
+);
+
+-- Accounts caught by screening, for the daily summary. Pruned after 30 days.
+CREATE TABLE IF NOT EXISTS screened_accounts (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    user_name TEXT NOT NULL,
+    reasons TEXT NOT NULL,
+    -- What was actually done, which can fall back to 'flag' when the action fails
+    action TEXT NOT NULL,
+    screened_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_screened_accounts_screened_at ON screened_accounts (screened_at);
@@ -0,0 +1,18 @@
+-- Role persistence settings. Guilds without a row persist roles with nothing excluded.
+CREATE TABLE IF NOT EXISTS role_persistence_config (
+    guild_id BIGINT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    excluded_role_ids BIGINT[] NOT NULL DEFAULT '{}'
+);
+
+-- What a member had when they left, restored if they rejoin.
+CREATE TABLE IF NOT EXISTS persisted_members (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    role_ids BIGINT[] NOT NULL DEFAULT '{}',
+    nickname TEXT,
+    -- End of a timeout that was still running when they left
+    muted_until TIMESTAMPTZ,
+    left_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (guild_id, user_id)
+);
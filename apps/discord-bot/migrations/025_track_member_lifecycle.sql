@@ -0,0 +1,16 @@
+-- Member lifecycle: when someone was first seen, how often they've joined, and whether they left.
+ALTER TABLE members ADD COLUMN IF NOT EXISTS first_seen TIMESTAMPTZ;
+ALTER TABLE members ADD COLUMN IF NOT EXISTS join_count INTEGER NOT NULL DEFAULT 0;
+-- Set while the member is gone, cleared when they rejoin
+ALTER TABLE members ADD COLUMN IF NOT EXISTS left_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS member_history (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    -- 'join' or 'leave'
+    event TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_member_history_user ON member_history (guild_id, user_id);
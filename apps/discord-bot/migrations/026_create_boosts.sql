@@ -0,0 +1,25 @@
+-- Boost thank-you and perk settings. Guilds without a row use the defaults.
+CREATE TABLE IF NOT EXISTS boost_config (
+    guild_id BIGINT PRIMARY KEY,
+    thanks_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Falls back to WELCOME_CHANNEL_ID when unset.
+    thanks_channel_id BIGINT,
+    -- NULL uses the built-in message
+    thanks_message TEXT,
+    color_roles BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+-- Everyone who has boosted, for perks and the top boosters list.
+CREATE TABLE IF NOT EXISTS boosters (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    -- Start of the current boost, NULL while not boosting
+    boosting_since TIMESTAMPTZ,
+    -- Times they've started boosting
+    boost_count INTEGER NOT NULL DEFAULT 0,
+    -- Length of past boosts, not counting the current one
+    total_seconds BIGINT NOT NULL DEFAULT 0,
+    -- Personal color role set with /boostrole color
+    color_role_id BIGINT,
+    PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1,14 @@
+-- Locked voice channels whose names show a live stat, e.g. "Members: 1,234".
+CREATE TABLE IF NOT EXISTS stat_channels (
+    channel_id BIGINT PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    -- 'members', 'live' or 'followers'
+    kind TEXT NOT NULL,
+    -- Channel name with {value} in place of the stat
+    template TEXT NOT NULL,
+    -- Name last set, so unchanged stats don't spend the rename rate limit
+    last_name TEXT,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_stat_channels_guild ON stat_channels (guild_id);
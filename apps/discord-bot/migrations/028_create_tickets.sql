@@ -0,0 +1,34 @@
+-- Support ticket settings, saved when the panel is posted.
+CREATE TABLE IF NOT EXISTS ticket_config (
+    guild_id BIGINT PRIMARY KEY,
+    -- Channel the panel is in; tickets are private threads under it
+    channel_id BIGINT NOT NULL,
+    staff_role_id BIGINT NOT NULL,
+    -- Where transcripts go. Falls back to LOG_CHANNEL_ID when unset.
+    log_channel_id BIGINT,
+    -- Open tickets with no messages for this long are closed; 0 never closes them.
+    -- At most a week, when Discord auto-archives the thread anyway.
+    inactivity_hours INTEGER NOT NULL DEFAULT 48
+);
+
+CREATE TABLE IF NOT EXISTS tickets (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    -- The ticket's private thread
+    channel_id BIGINT NOT NULL UNIQUE,
+    user_id BIGINT NOT NULL,
+    -- 'open' or 'closed'
+    status TEXT NOT NULL DEFAULT 'open',
+    claimed_by BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_activity_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    closed_at TIMESTAMPTZ,
+    -- NULL when closed automatically
+    closed_by BIGINT,
+    close_reason TEXT,
+    transcript_message_id BIGINT
+);
+
+-- One open ticket per member
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tickets_open ON tickets (guild_id, user_id) WHERE status = 'open';
+CREATE INDEX IF NOT EXISTS idx_tickets_activity ON tickets (last_activity_at) WHERE status = 'open';
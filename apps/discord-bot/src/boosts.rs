@@ -0,0 +1,442 @@
+use crate::utils::{embeds, templates};
+use crate::Data;
+use chrono::{DateTime, Duration, Utc};
+use serenity::all::{
+    ChannelId, Colour, Context, CreateEmbed, CreateMessage, EditRole, GuildId,
+    GuildMemberUpdateEvent, Member, Mentionable, Permissions, RoleId, User, UserId,
+};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+pub const DEFAULT_THANKS: &str =
+    "💖 Thank you {user} for boosting **{server}**! We're now at **{boosts}** boosts.";
+
+/// Boosts that began longer ago than this are recorded without a thank-you:
+/// the bot was offline when they started, or is seeing an existing booster
+/// for the first time.
+const ANNOUNCE_WITHIN: Duration = Duration::minutes(10);
+
+/// Boosters shown by `/server`.
+pub const TOP_BOOSTERS: i64 = 5;
+
+/// A guild's boost thank-you and perk settings.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BoostConfig {
+    pub thanks_enabled: bool,
+    /// Where thank-yous go; `None` uses the welcome channel.
+    pub thanks_channel_id: Option<i64>,
+    /// `None` uses [`DEFAULT_THANKS`].
+    pub thanks_message: Option<String>,
+    /// Whether boosters can set a color role with `/boostrole color`.
+    pub color_roles: bool,
+}
+
+impl Default for BoostConfig {
+    fn default() -> Self {
+        Self {
+            thanks_enabled: true,
+            thanks_channel_id: None,
+            thanks_message: None,
+            color_roles: true,
+        }
+    }
+}
+
+impl BoostConfig {
+    /// Load the guild's settings, falling back to the defaults.
+    pub async fn load(db: &PgPool, guild_id: GuildId) -> Result<Self, sqlx::Error> {
+        let row = sqlx::query_as::<_, Self>(
+            "SELECT thanks_enabled, thanks_channel_id, thanks_message, color_roles \
+             FROM boost_config WHERE guild_id = $1",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.unwrap_or_default())
+    }
+
+    pub async fn save(&self, db: &PgPool, guild_id: GuildId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO boost_config (guild_id, thanks_enabled, thanks_channel_id, \
+                thanks_message, color_roles) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (guild_id) DO UPDATE SET \
+                thanks_enabled = EXCLUDED.thanks_enabled, \
+                thanks_channel_id = EXCLUDED.thanks_channel_id, \
+                thanks_message = EXCLUDED.thanks_message, color_roles = EXCLUDED.color_roles",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(self.thanks_enabled)
+        .bind(self.thanks_channel_id)
+        .bind(&self.thanks_message)
+        .bind(self.color_roles)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The thank-you channel, else `fallback`.
+    pub fn thanks_channel(&self, fallback: Option<ChannelId>) -> Option<ChannelId> {
+        self.thanks_channel_id
+            .map(|id| ChannelId::new(id as u64))
+            .or(fallback)
+    }
+
+    pub fn thanks_message(&self) -> &str {
+        self.thanks_message.as_deref().unwrap_or(DEFAULT_THANKS)
+    }
+}
+
+/// A current booster, for `/server`.
+#[derive(Debug, sqlx::FromRow)]
+pub struct TopBooster {
+    pub user_id: i64,
+    pub boosting_since: DateTime<Utc>,
+    pub boost_count: i32,
+}
+
+/// Current boosters who've boosted longest in total, past boosts included.
+pub async fn top_boosters(db: &PgPool, guild_id: GuildId) -> Result<Vec<TopBooster>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT user_id, boosting_since, boost_count FROM boosters \
+         WHERE guild_id = $1 AND boosting_since IS NOT NULL \
+         ORDER BY total_seconds + EXTRACT(EPOCH FROM now() - boosting_since) DESC \
+         LIMIT $2",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(TOP_BOOSTERS)
+    .fetch_all(db)
+    .await
+}
+
+/// Notice boosts starting or stopping from member updates.
+pub async fn handle_member_update(ctx: &Context, event: &GuildMemberUpdateEvent, data: &Data) {
+    let (guild_id, user) = (event.guild_id, &event.user);
+    if user.bot {
+        return;
+    }
+
+    match event.premium_since {
+        Some(since) => {
+            let since =
+                DateTime::from_timestamp(since.unix_timestamp(), 0).unwrap_or_else(Utc::now);
+            match record_start(&data.db, guild_id, user.id, since).await {
+                // Recorded, but long after it began; don't thank them for an old boost.
+                Ok(true) if Utc::now() - since > ANNOUNCE_WITHIN => {
+                    info!(user = %user.name, "Recorded existing booster");
+                }
+                Ok(true) => boost_started(ctx, guild_id, user, data).await,
+                Ok(false) => {}
+                Err(e) => error!(error = %e, "Failed to record boost"),
+            }
+        }
+        None => boost_ended(ctx, guild_id, user, data, "Stopped boosting").await,
+    }
+}
+
+/// End a departing member's boost; leaving the server removes it.
+pub async fn handle_leave(ctx: &Context, guild_id: GuildId, user: &User, data: &Data) {
+    boost_ended(ctx, guild_id, user, data, "Left the server").await;
+}
+
+/// Record a boost. Returns `false` if they were already boosting.
+async fn record_start(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    since: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO boosters (guild_id, user_id, boosting_since, boost_count) \
+         VALUES ($1, $2, $3, 1) \
+         ON CONFLICT (guild_id, user_id) DO UPDATE SET \
+            boosting_since = EXCLUDED.boosting_since, boost_count = boosters.boost_count + 1 \
+         WHERE boosters.boosting_since IS NULL",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .bind(since)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[derive(sqlx::FromRow)]
+struct EndedBoost {
+    boosting_since: DateTime<Utc>,
+    color_role_id: Option<i64>,
+}
+
+/// Record the end of a boost, returning it if they were boosting.
+async fn record_end(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<EndedBoost>, sqlx::Error> {
+    sqlx::query_as(
+        "WITH ended AS ( \
+            SELECT boosting_since, color_role_id FROM boosters \
+            WHERE guild_id = $1 AND user_id = $2 AND boosting_since IS NOT NULL FOR UPDATE \
+         ) \
+         UPDATE boosters SET \
+            total_seconds = total_seconds \
+                + EXTRACT(EPOCH FROM now() - ended.boosting_since)::BIGINT, \
+            boosting_since = NULL, color_role_id = NULL \
+         FROM ended WHERE guild_id = $1 AND user_id = $2 \
+         RETURNING ended.boosting_since, ended.color_role_id",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .fetch_optional(db)
+    .await
+}
+
+async fn boost_started(ctx: &Context, guild_id: GuildId, user: &User, data: &Data) {
+    info!(user = %user.name, guild_id = %guild_id, "Member started boosting");
+
+    let config = match BoostConfig::load(&data.db, guild_id).await {
+        Ok(config) => config,
+        Err(e) => {
+            error!(error = %e, "Failed to load boost settings");
+            BoostConfig::default()
+        }
+    };
+
+    if config.thanks_enabled {
+        if let Some(channel_id) = config.thanks_channel(data.config.welcome_channel_id) {
+            let message = thanks_message(ctx, guild_id, user, &config);
+            if let Err(e) = channel_id.send_message(&ctx.http, message).await {
+                error!(error = %e, "Failed to send boost thank-you");
+            }
+        }
+    }
+
+    log(
+        ctx,
+        data,
+        embeds::success_embed()
+            .title("Member Boosted")
+            .field("User", format!("{} ({})", user.mention(), user.name), false)
+            .thumbnail(
+                user.avatar_url()
+                    .unwrap_or_else(|| user.default_avatar_url()),
+            ),
+    )
+    .await;
+}
+
+async fn boost_ended(ctx: &Context, guild_id: GuildId, user: &User, data: &Data, why: &str) {
+    let ended = match record_end(&data.db, guild_id, user.id).await {
+        Ok(Some(ended)) => ended,
+        Ok(None) => return,
+        Err(e) => {
+            error!(error = %e, "Failed to record end of boost");
+            return;
+        }
+    };
+    info!(user = %user.name, guild_id = %guild_id, why, "Member stopped boosting");
+
+    // The color role is a booster perk, so it goes with the boost.
+    if let Some(role_id) = ended.color_role_id {
+        let role_id = RoleId::new(role_id as u64);
+        if let Err(e) = guild_id.delete_role(&ctx.http, role_id).await {
+            warn!(role_id = %role_id, error = %e, "Failed to delete booster color role");
+        }
+    }
+
+    let since = ended.boosting_since.timestamp();
+    log(
+        ctx,
+        data,
+        embeds::warning_embed()
+            .title("Boost Ended")
+            .field("User", format!("{} ({})", user.mention(), user.name), false)
+            .field("Reason", why, true)
+            .field(
+                "Boosted Since",
+                format!("<t:{since}:D> (<t:{since}:R>)"),
+                true,
+            ),
+    )
+    .await;
+}
+
+/// Render the thank-you for `user`.
+pub fn thanks_message(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    config: &BoostConfig,
+) -> CreateMessage {
+    let (server, boosts) = ctx
+        .cache
+        .guild(guild_id)
+        .map(|g| {
+            (
+                g.name.clone(),
+                g.premium_subscription_count.unwrap_or(0).to_string(),
+            )
+        })
+        .unwrap_or_else(|| ("the server".to_string(), "?".to_string()));
+
+    let text = templates::render(config.thanks_message(), |key| match key {
+        "user" => Some(user.mention().to_string()),
+        "username" => Some(user.display_name().to_string()),
+        "server" => Some(server.clone()),
+        "boosts" => Some(boosts.clone()),
+        _ => None,
+    });
+
+    let mut embed = embeds::crimson_embed()
+        .title("New Boost!")
+        .description(text)
+        .thumbnail(
+            user.avatar_url()
+                .unwrap_or_else(|| user.default_avatar_url()),
+        );
+    if config.color_roles {
+        embed = embed.field(
+            "Booster Perk",
+            "Pick your own name color with `/boostrole color`.",
+            false,
+        );
+    }
+
+    CreateMessage::new()
+        .content(user.mention().to_string())
+        .embed(embed)
+}
+
+/// Give a booster a role in `colour`, creating it the first time.
+///
+/// New roles go just below the bot's highest role so the color shows over
+/// any other colored roles the booster has.
+pub async fn set_color_role(
+    ctx: &Context,
+    db: &PgPool,
+    member: &Member,
+    colour: Colour,
+) -> Result<RoleId, String> {
+    let guild_id = member.guild_id;
+    let Some(since) = member.premium_since else {
+        return Err("Only server boosters can set a color role.".into());
+    };
+
+    // Boosters from before the bot was tracking aren't recorded yet.
+    let since = DateTime::from_timestamp(since.unix_timestamp(), 0).unwrap_or_else(Utc::now);
+    record_start(db, guild_id, member.user.id, since)
+        .await
+        .map_err(|e| format!("Failed to record boost: {e}"))?;
+
+    let existing: Option<i64> = sqlx::query_scalar(
+        "SELECT color_role_id FROM boosters WHERE guild_id = $1 AND user_id = $2",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| format!("Failed to load color role: {e}"))?
+    .flatten();
+
+    if let Some(role_id) = existing {
+        let role_id = RoleId::new(role_id as u64);
+        let edited = guild_id
+            .edit_role(&ctx.http, role_id, EditRole::new().colour(colour))
+            .await;
+        match edited {
+            Ok(_) => {
+                if !member.roles.contains(&role_id) {
+                    member
+                        .add_role(&ctx.http, role_id)
+                        .await
+                        .map_err(|e| format!("Failed to give you your color role: {e}"))?;
+                }
+                return Ok(role_id);
+            }
+            // Deleted by hand; make a new one.
+            Err(e) => warn!(role_id = %role_id, error = %e, "Failed to edit color role"),
+        }
+    }
+
+    let mut role = EditRole::new()
+        .name(format!("{} ✦", member.display_name()))
+        .colour(colour)
+        .permissions(Permissions::empty())
+        .audit_log_reason("Booster color role");
+    if let Some(position) = below_bot(ctx, guild_id) {
+        role = role.position(position);
+    }
+    let role = guild_id
+        .create_role(&ctx.http, role)
+        .await
+        .map_err(|e| format!("Failed to create your color role: {e}"))?;
+
+    sqlx::query("UPDATE boosters SET color_role_id = $3 WHERE guild_id = $1 AND user_id = $2")
+        .bind(guild_id.get() as i64)
+        .bind(member.user.id.get() as i64)
+        .bind(role.id.get() as i64)
+        .execute(db)
+        .await
+        .map_err(|e| format!("Failed to save color role: {e}"))?;
+
+    member
+        .add_role(&ctx.http, role.id)
+        .await
+        .map_err(|e| format!("Failed to give you your color role: {e}"))?;
+    Ok(role.id)
+}
+
+/// Delete a booster's color role. Returns `false` if they didn't have one.
+pub async fn remove_color_role(
+    ctx: &Context,
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<bool, String> {
+    let role_id: Option<i64> = sqlx::query_scalar(
+        "WITH removed AS ( \
+            SELECT color_role_id FROM boosters \
+            WHERE guild_id = $1 AND user_id = $2 AND color_role_id IS NOT NULL FOR UPDATE \
+         ) \
+         UPDATE boosters SET color_role_id = NULL \
+         FROM removed WHERE guild_id = $1 AND user_id = $2 \
+         RETURNING removed.color_role_id",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| format!("Failed to remove color role: {e}"))?;
+
+    let Some(role_id) = role_id else {
+        return Ok(false);
+    };
+    let role_id = RoleId::new(role_id as u64);
+    if let Err(e) = guild_id.delete_role(&ctx.http, role_id).await {
+        warn!(role_id = %role_id, error = %e, "Failed to delete booster color role");
+    }
+    Ok(true)
+}
+
+/// The position just below the bot's highest role, if the guild is cached.
+fn below_bot(ctx: &Context, guild_id: GuildId) -> Option<u16> {
+    let guild = ctx.cache.guild(guild_id)?;
+    let bot = guild.members.get(&ctx.cache.current_user().id)?;
+    let top = guild.member_highest_role(bot)?;
+    Some(top.position.saturating_sub(1).max(1))
+}
+
+async fn log(ctx: &Context, data: &Data, embed: CreateEmbed) {
+    let Some(channel_id) = data.config.log_channel_id else {
+        return;
+    };
+    if let Err(e) = channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+    {
+        error!(error = %e, "Failed to send boost log");
+    }
+}
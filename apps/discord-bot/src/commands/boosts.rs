@@ -0,0 +1,173 @@
+use crate::boosts::{self, BoostConfig};
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::{Colour, GuildChannel, Mentionable};
+
+type Error = crate::error::Error;
+
+/// Configure the boost thank-you and booster perks.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("boosts_settings", "boosts_test"),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn boosts(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Change the thank-you message and booster perks.
+#[poise::command(slash_command, rename = "settings")]
+pub async fn boosts_settings(
+    ctx: Context<'_>,
+    #[description = "Thank members when they boost"] thanks: Option<bool>,
+    #[description = "Channel for thank-yous (default: the welcome channel)"]
+    #[channel_types("Text")]
+    channel: Option<GuildChannel>,
+    #[description = "Thank-you text: {user}, {username}, {server}, {boosts}. \"default\" to reset"]
+    message: Option<String>,
+    #[description = "Let boosters pick a color role with /boostrole"] color_roles: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let mut config = BoostConfig::load(db, guild_id).await?;
+    if let Some(thanks) = thanks {
+        config.thanks_enabled = thanks;
+    }
+    if let Some(channel) = channel {
+        config.thanks_channel_id = Some(channel.id.get() as i64);
+    }
+    if let Some(message) = message {
+        config.thanks_message = (!message.eq_ignore_ascii_case("default")).then_some(message);
+    }
+    if let Some(color_roles) = color_roles {
+        config.color_roles = color_roles;
+    }
+    config.save(db, guild_id).await?;
+
+    let channel = config
+        .thanks_channel(ctx.data().config.welcome_channel_id)
+        .map(|c| c.mention().to_string())
+        .unwrap_or_else(|| "None".into());
+    let embed = embeds::success_embed()
+        .title("Boost Settings Updated")
+        .field(
+            "Thank-You",
+            if config.thanks_enabled { "On" } else { "Off" },
+            true,
+        )
+        .field("Channel", channel, true)
+        .field(
+            "Color Roles",
+            if config.color_roles { "On" } else { "Off" },
+            true,
+        )
+        .field("Message", config.thanks_message(), false);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Preview the thank-you message with yourself as the booster.
+#[poise::command(slash_command, rename = "test")]
+pub async fn boosts_test(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    let config = BoostConfig::load(&ctx.data().db, guild_id).await?;
+    let message = boosts::thanks_message(ctx.serenity_context(), guild_id, ctx.author(), &config);
+    ctx.channel_id().send_message(ctx.http(), message).await?;
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content("Sent a preview of the thank-you message.")
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Booster perks.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("boostrole_color", "boostrole_remove"),
+    subcommand_required
+)]
+pub async fn boostrole(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set your name color (boosters only).
+#[poise::command(slash_command, rename = "color")]
+pub async fn boostrole_color(
+    ctx: Context<'_>,
+    #[description = "Hex color, e.g. #dc143c"] color: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let data = ctx.data();
+
+    let colour = parse_colour(&color).ok_or_else(|| {
+        Error::Command(format!(
+            "`{color}` isn't a hex color. Use something like `#dc143c`."
+        ))
+    })?;
+    if !BoostConfig::load(&data.db, guild_id).await?.color_roles {
+        return Err(Error::Command(
+            "Booster color roles are turned off here.".into(),
+        ));
+    }
+
+    ctx.defer_ephemeral().await?;
+    let member = guild_id.member(ctx.http(), ctx.author().id).await?;
+    let role_id = boosts::set_color_role(ctx.serenity_context(), &data.db, &member, colour)
+        .await
+        .map_err(Error::Command)?;
+
+    let embed = embeds::crimson_embed()
+        .colour(colour)
+        .title("Color Updated")
+        .description(format!(
+            "Your name now shows in {} — thanks for boosting!",
+            role_id.mention()
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Remove your color role.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn boostrole_remove(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    let removed = boosts::remove_color_role(
+        ctx.serenity_context(),
+        &ctx.data().db,
+        guild_id,
+        ctx.author().id,
+    )
+    .await
+    .map_err(Error::Command)?;
+    if !removed {
+        return Err(Error::Command("You don't have a color role.".into()));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Color Removed")
+        .description("Your color role has been deleted.");
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Parse `#rrggbb` or `rrggbb`.
+fn parse_colour(input: &str) -> Option<Colour> {
+    let hex = input.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().map(Colour::new)
+}
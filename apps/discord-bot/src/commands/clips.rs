@@ -0,0 +1,82 @@
+use crate::integrations::twitch::clips;
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::CreateMessage;
+
+type Error = crate::error::Error;
+
+/// Submit a Twitch clip to the clips channel for voting.
+#[poise::command(slash_command, guild_only)]
+pub async fn clip(
+    ctx: Context<'_>,
+    #[description = "Twitch clip URL"] url: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let twitch = ctx
+        .data()
+        .config
+        .twitch
+        .as_ref()
+        .ok_or_else(|| Error::Command("Twitch integration is not configured.".into()))?;
+    let channel_id = twitch
+        .clips_channel_id
+        .ok_or_else(|| Error::Command("No clips channel is configured.".into()))?;
+
+    let clip_id = clips::parse_clip_id(&url)
+        .ok_or_else(|| Error::Command("That doesn't look like a Twitch clip URL.".into()))?;
+
+    ctx.defer_ephemeral().await?;
+
+    let db = &ctx.data().db;
+    if let Some(existing) = clips::find_submission(db, guild_id, &clip_id).await? {
+        let location = existing
+            .message_link()
+            .map(|link| format!(" — [see it here]({link})"))
+            .unwrap_or_default();
+        return Err(Error::Command(format!(
+            "That clip has already been submitted{location}."
+        )));
+    }
+
+    let clip = ctx
+        .data()
+        .twitch_api
+        .get_clip(&clip_id)
+        .await
+        .map_err(Error::Command)?
+        .ok_or_else(|| Error::Command("Twitch couldn't find that clip.".into()))?;
+
+    if clip.broadcaster_id.as_str() != twitch.channel_id {
+        return Err(Error::Command(format!(
+            "Only clips from **{}**'s channel can be submitted.",
+            twitch.username
+        )));
+    }
+
+    let submission_id =
+        clips::create_submission(db, guild_id, channel_id, &clip, ctx.author().id).await?;
+
+    let message = CreateMessage::new()
+        .embed(clips::clip_embed(&clip, ctx.author().id))
+        .components(clips::vote_buttons(submission_id, 0, 0));
+
+    let posted = match channel_id.send_message(ctx.http(), message).await {
+        Ok(posted) => posted,
+        Err(e) => {
+            clips::delete_submission(db, submission_id).await?;
+            return Err(e.into());
+        }
+    };
+    clips::set_message(db, submission_id, posted.id).await?;
+
+    let embed = embeds::success_embed()
+        .title("Clip Submitted")
+        .description(format!(
+            "**{}** is up for voting in {}.",
+            clip.title,
+            posted.link()
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::boosts;
+use crate::integrations::twitch::schedule;
 use crate::utils::embeds;
 use crate::Context;
 
@@ -69,13 +71,23 @@ pub async fn socials(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-/// Show the current streaming schedule.
+/// Show the upcoming streaming schedule.
 #[poise::command(slash_command, prefix_command)]
 pub async fn schedule(ctx: Context<'_>) -> Result<(), Error> {
-    let embed = embeds::twitch_embed()
-        .title("Streaming Schedule")
-        .description("Check back soon for the updated schedule!")
-        .field("Status", "Schedule coming soon", false);
+    let data = ctx.data();
+    let fallback = || schedule::fallback_embed(data.config.schedule_text.as_deref());
+
+    let embed = match data.config.twitch {
+        Some(ref twitch) => match data.twitch_api.get_schedule().await {
+            Ok(s) if !s.segments.is_empty() => schedule::schedule_embed(&s, &twitch.channel_url()),
+            Ok(_) => fallback(),
+            Err(e) => {
+                tracing::warn!(error = %e, "Twitch schedule unavailable, using configured text");
+                fallback()
+            }
+        },
+        None => fallback(),
+    };
 
     ctx.send(poise::CreateReply::default().embed(embed)).await?;
     Ok(())
@@ -94,8 +106,13 @@ pub async fn server(ctx: Context<'_>) -> Result<(), Error> {
         )
     });
 
+    let top_boosters = match ctx.guild_id() {
+        Some(guild_id) => boosts::top_boosters(&ctx.data().db, guild_id).await?,
+        None => Vec::new(),
+    };
+
     let embed = if let Some((name, member_count, boost_tier, boost_count, created_at)) = guild {
-        embeds::crimson_embed()
+        let mut embed = embeds::crimson_embed()
             .title(format!("{name} Server Info"))
             .field("Members", member_count.to_string(), true)
             .field("Boost Tier", format!("{boost_tier:?}"), true)
@@ -104,7 +121,27 @@ pub async fn server(ctx: Context<'_>) -> Result<(), Error> {
                 "Created",
                 format!("<t:{}:R>", created_at.unix_timestamp()),
                 true,
-            )
+            );
+        if !top_boosters.is_empty() {
+            let lines: Vec<String> = top_boosters
+                .iter()
+                .enumerate()
+                .map(|(i, booster)| {
+                    let mut line = format!(
+                        "**{}.** <@{}> — since <t:{}:D>",
+                        i + 1,
+                        booster.user_id,
+                        booster.boosting_since.timestamp()
+                    );
+                    if booster.boost_count > 1 {
+                        line.push_str(&format!(" · boosted {} times", booster.boost_count));
+                    }
+                    line
+                })
+                .collect();
+            embed = embed.field("Top Boosters", lines.join("\n"), false);
+        }
+        embed
     } else {
         embeds::error_embed()
             .title("Error")
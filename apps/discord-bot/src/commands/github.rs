@@ -0,0 +1,383 @@
+use crate::integrations::github::api::{self, PullRequest, Repository};
+use crate::integrations::github::defaults;
+use crate::integrations::github::pages::{self, truncate, Listing};
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::{CreateEmbed, CreateEmbedAuthor, GuildChannel, Mentionable};
+
+type Error = crate::error::Error;
+
+/// Longest pull request description excerpt shown by `/pr`.
+const MAX_PR_BODY_CHARS: usize = 400;
+
+/// Discord's limit on embed titles.
+const MAX_TITLE_CHARS: usize = 256;
+
+/// Show stats for a GitHub repository.
+#[poise::command(slash_command)]
+pub async fn repo(
+    ctx: Context<'_>,
+    #[description = "Repository as owner/name (defaults to the server's repository)"]
+    repository: Option<String>,
+) -> Result<(), Error> {
+    let repo = resolve_repo(ctx, repository).await?;
+    ctx.defer().await?;
+
+    let repository = ctx
+        .data()
+        .github_api
+        .repository(&repo)
+        .await
+        .map_err(Error::Command)?;
+
+    ctx.send(poise::CreateReply::default().embed(repo_embed(&repository)))
+        .await?;
+    Ok(())
+}
+
+/// List open issues in a GitHub repository.
+#[poise::command(slash_command)]
+pub async fn issues(
+    ctx: Context<'_>,
+    #[description = "Repository as owner/name (defaults to the server's repository)"]
+    repository: Option<String>,
+) -> Result<(), Error> {
+    let repo = resolve_repo(ctx, repository).await?;
+    ctx.defer().await?;
+
+    let (embed, components) =
+        pages::render(&ctx.data().github_api, Listing::Issues, &repo, None, 1)
+            .await
+            .map_err(Error::Command)?;
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embed)
+            .components(components),
+    )
+    .await?;
+    Ok(())
+}
+
+/// List recent commits in a GitHub repository.
+#[poise::command(slash_command)]
+pub async fn commits(
+    ctx: Context<'_>,
+    #[description = "Repository as owner/name (defaults to the server's repository)"]
+    repository: Option<String>,
+    #[description = "Branch (defaults to the repository's default branch)"] branch: Option<String>,
+) -> Result<(), Error> {
+    let repo = resolve_repo(ctx, repository).await?;
+    ctx.defer().await?;
+
+    let branch = branch.as_deref().map(str::trim).filter(|b| !b.is_empty());
+    let (embed, components) =
+        pages::render(&ctx.data().github_api, Listing::Commits, &repo, branch, 1)
+            .await
+            .map_err(Error::Command)?;
+
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embed)
+            .components(components),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Show a pull request from a GitHub repository.
+#[poise::command(slash_command)]
+pub async fn pr(
+    ctx: Context<'_>,
+    #[description = "Pull request number"]
+    #[min = 1]
+    number: u64,
+    #[description = "Repository as owner/name (defaults to the server's repository)"]
+    repository: Option<String>,
+) -> Result<(), Error> {
+    let repo = resolve_repo(ctx, repository).await?;
+    ctx.defer().await?;
+
+    let pr = ctx
+        .data()
+        .github_api
+        .pull_request(&repo, number)
+        .await
+        .map_err(Error::Command)?;
+
+    ctx.send(poise::CreateReply::default().embed(pr_embed(&repo, &pr)))
+        .await?;
+    Ok(())
+}
+
+/// Set the repository the GitHub commands use in this server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn defaultrepo(
+    ctx: Context<'_>,
+    #[description = "Repository as owner/name (leave empty to clear)"] repository: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let repo = repository.as_deref().map(normalize_repo).transpose()?;
+
+    defaults::set_default_repo(&ctx.data().db, guild_id, repo.as_deref()).await?;
+
+    let description = match repo {
+        Some(ref repo) => {
+            format!("GitHub commands now default to [{repo}](https://github.com/{repo}).")
+        }
+        None => match ctx.data().config.github.default_repo {
+            Some(ref fallback) => format!("Cleared. GitHub commands fall back to **{fallback}**."),
+            None => "Cleared. GitHub commands now need a repository.".to_string(),
+        },
+    };
+    let embed = embeds::success_embed()
+        .title("Default Repository")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// The repository to query: the argument, the guild default, then the configured one.
+async fn resolve_repo(ctx: Context<'_>, repository: Option<String>) -> Result<String, Error> {
+    if let Some(repo) = repository {
+        return normalize_repo(&repo);
+    }
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Some(repo) = defaults::default_repo(&ctx.data().db, guild_id).await? {
+            return Ok(repo);
+        }
+    }
+
+    ctx.data()
+        .config
+        .github
+        .default_repo
+        .clone()
+        .ok_or_else(|| {
+            Error::Command(
+                "No repository given and this server has no default. Pass one as `owner/name`."
+                    .into(),
+            )
+        })
+}
+
+/// Accept `owner/name` or a github.com URL.
+fn normalize_repo(input: &str) -> Result<String, Error> {
+    let trimmed = input
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.")
+        .trim_start_matches("github.com/")
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+
+    if api::is_valid_repo(trimmed) {
+        Ok(trimmed.to_string())
+    } else {
+        Err(Error::Command(format!(
+            "`{}` isn't a repository. Use the `owner/name` form.",
+            input.trim()
+        )))
+    }
+}
+
+fn repo_embed(repo: &Repository) -> CreateEmbed {
+    let mut embed = embeds::github_embed()
+        .author(
+            CreateEmbedAuthor::new(&repo.owner.login)
+                .url(&repo.owner.html_url)
+                .icon_url(&repo.owner.avatar_url),
+        )
+        .title(&repo.full_name)
+        .url(&repo.html_url)
+        .thumbnail(&repo.owner.avatar_url)
+        .field("Stars", repo.stargazers_count.to_string(), true)
+        .field("Forks", repo.forks_count.to_string(), true)
+        .field(
+            "Open Issues & PRs",
+            repo.open_issues_count.to_string(),
+            true,
+        );
+
+    if let Some(ref description) = repo.description {
+        embed = embed.description(description);
+    }
+    if let Some(watchers) = repo.subscribers_count {
+        embed = embed.field("Watchers", watchers.to_string(), true);
+    }
+    if let Some(ref language) = repo.language {
+        embed = embed.field("Language", language, true);
+    }
+    embed = embed.field("Default Branch", format!("`{}`", repo.default_branch), true);
+    if let Some(pushed_at) = repo.pushed_at {
+        embed = embed.field(
+            "Last Push",
+            format!("<t:{}:R>", pushed_at.timestamp()),
+            true,
+        );
+    }
+    if let Some(ref license) = repo.license {
+        embed = embed.field("License", &license.name, true);
+    }
+    if !repo.topics.is_empty() {
+        let topics: Vec<String> = repo.topics.iter().map(|t| format!("`{t}`")).collect();
+        embed = embed.field("Topics", topics.join(" "), false);
+    }
+
+    embed
+}
+
+fn pr_embed(repo: &str, pr: &PullRequest) -> CreateEmbed {
+    let status = if pr.merged {
+        "🟣 Merged"
+    } else if pr.state == "closed" {
+        "🔴 Closed"
+    } else if pr.draft {
+        "⚪ Draft"
+    } else {
+        "🟢 Open"
+    };
+
+    let mut embed = embeds::github_embed()
+        .author(
+            CreateEmbedAuthor::new(&pr.user.login)
+                .url(&pr.user.html_url)
+                .icon_url(&pr.user.avatar_url),
+        )
+        .title(truncate(
+            &format!("[{repo}] #{} {}", pr.number, pr.title),
+            MAX_TITLE_CHARS,
+        ))
+        .url(&pr.html_url)
+        .field("Status", status, true)
+        .field(
+            "Branches",
+            format!("`{}` ← `{}`", pr.base.name, pr.head.name),
+            true,
+        )
+        .field(
+            "Changes",
+            format!(
+                "{} commits · {} files · +{} −{}",
+                pr.commits, pr.changed_files, pr.additions, pr.deletions
+            ),
+            false,
+        )
+        .field(
+            "Comments",
+            (pr.comments + pr.review_comments).to_string(),
+            true,
+        )
+        .field(
+            "Opened",
+            format!("<t:{}:R>", pr.created_at.timestamp()),
+            true,
+        );
+
+    if let Some(merged_at) = pr.merged_at {
+        embed = embed.field("Merged", format!("<t:{}:R>", merged_at.timestamp()), true);
+    }
+    if let Some(body) = pr.body.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+        embed = embed.description(truncate(body, MAX_PR_BODY_CHARS));
+    }
+
+    embed
+}
+
+/// Expand `#123` and `owner/repo#45` mentions into GitHub embeds.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("autolink_enable", "autolink_disable", "autolink_list"),
+    subcommand_required,
+    required_permissions = "MANAGE_CHANNELS",
+    default_member_permissions = "MANAGE_CHANNELS"
+)]
+pub async fn autolink(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Turn on auto-linking in a channel.
+#[poise::command(slash_command, rename = "enable")]
+pub async fn autolink_enable(
+    ctx: Context<'_>,
+    #[description = "Channel (defaults to this one)"]
+    #[channel_types("Text")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    set_autolink(ctx, channel, true).await
+}
+
+/// Turn off auto-linking in a channel.
+#[poise::command(slash_command, rename = "disable")]
+pub async fn autolink_disable(
+    ctx: Context<'_>,
+    #[description = "Channel (defaults to this one)"]
+    #[channel_types("Text")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    set_autolink(ctx, channel, false).await
+}
+
+/// List channels with auto-linking on.
+#[poise::command(slash_command, rename = "list")]
+pub async fn autolink_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let data = ctx.data();
+    let channels = data.autolinker.guild_channels(&data.db, guild_id).await?;
+
+    let description = if channels.is_empty() {
+        "Auto-linking is off everywhere. Turn it on with `/autolink enable`.".to_string()
+    } else {
+        channels
+            .iter()
+            .map(|c| c.mention().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let embed = embeds::github_embed()
+        .title("Auto-link Channels")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+async fn set_autolink(
+    ctx: Context<'_>,
+    channel: Option<GuildChannel>,
+    enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let channel_id = channel.map(|c| c.id).unwrap_or_else(|| ctx.channel_id());
+    let data = ctx.data();
+
+    let changed = data
+        .autolinker
+        .set_enabled(&data.db, guild_id, channel_id, enabled)
+        .await?;
+
+    let description = match (enabled, changed) {
+        (true, true) => format!(
+            "GitHub references in {} will now be expanded.",
+            channel_id.mention()
+        ),
+        (true, false) => format!("Auto-linking was already on in {}.", channel_id.mention()),
+        (false, true) => format!("Auto-linking is now off in {}.", channel_id.mention()),
+        (false, false) => format!("Auto-linking wasn't on in {}.", channel_id.mention()),
+    };
+    let embed = embeds::success_embed()
+        .title("Auto-link")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
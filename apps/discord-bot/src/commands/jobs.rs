@@ -0,0 +1,84 @@
+use crate::scheduler::Job;
+use crate::utils::embeds;
+use crate::Context;
+
+type Error = crate::error::Error;
+
+/// Most jobs shown by `/jobs list`.
+const MAX_LISTED: usize = 20;
+
+/// View and cancel scheduled jobs.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("jobs_list", "jobs_cancel"),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn jobs(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// List pending scheduled jobs.
+#[poise::command(slash_command, rename = "list")]
+pub async fn jobs_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let jobs = ctx.data().scheduler.list(guild_id).await?;
+
+    let description = if jobs.is_empty() {
+        "No jobs are scheduled.".to_string()
+    } else {
+        let mut lines: Vec<String> = jobs.iter().take(MAX_LISTED).map(job_line).collect();
+        if jobs.len() > MAX_LISTED {
+            lines.push(format!("…and {} more", jobs.len() - MAX_LISTED));
+        }
+        lines.join("\n")
+    };
+
+    let embed = embeds::crimson_embed()
+        .title("Scheduled Jobs")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Cancel a scheduled job.
+#[poise::command(slash_command, rename = "cancel")]
+pub async fn jobs_cancel(
+    ctx: Context<'_>,
+    #[description = "Job ID from /jobs list"] id: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    if !ctx.data().scheduler.cancel(guild_id, id).await? {
+        return Err(Error::Command(format!("There's no job with ID {id}.")));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Job Cancelled")
+        .description(format!(
+            "Job `{id}` was cancelled. Recurring jobs are recreated the next time the bot starts."
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+fn job_line(job: &Job) -> String {
+    let schedule = match job.cron {
+        Some(ref cron) => format!(" · `{cron}`"),
+        None => String::new(),
+    };
+    let failures = match job.last_error {
+        Some(_) if job.attempts > 0 => format!(" · ⚠️ {} failed attempt(s)", job.attempts),
+        _ => String::new(),
+    };
+    format!(
+        "`{}` **{}** · <t:{}:R>{schedule}{failures}",
+        job.id,
+        job.kind,
+        job.run_at.timestamp()
+    )
+}
@@ -0,0 +1,171 @@
+use crate::integrations::twitch::links;
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::{Mentionable, User};
+use std::time::Duration;
+use twitch_api::twitch_oauth2::{tokens::DeviceUserTokenBuilder, TwitchToken};
+
+type Error = crate::error::Error;
+
+/// Interaction tokens expire after 15 minutes, so stop waiting a little before that.
+const MAX_LINK_WAIT: Duration = Duration::from_secs(14 * 60);
+
+/// Link your Discord account to another platform.
+#[poise::command(slash_command, subcommands("link_twitch"), subcommand_required)]
+pub async fn link(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Link your Twitch account by authorising the bot on twitch.tv.
+#[poise::command(slash_command, rename = "twitch")]
+pub async fn link_twitch(ctx: Context<'_>) -> Result<(), Error> {
+    let twitch = ctx
+        .data()
+        .config
+        .twitch
+        .as_ref()
+        .ok_or_else(|| Error::Command("Twitch integration is not configured.".into()))?;
+
+    ctx.defer_ephemeral().await?;
+
+    // The device code grant needs no redirect URL: the member enters a short
+    // code on twitch.tv/activate while we poll for the result.
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| Error::Command(format!("Failed to start Twitch linking: {e}")))?;
+    let mut builder = DeviceUserTokenBuilder::new(twitch.client_id.clone(), vec![]);
+
+    let code = builder
+        .start(&client)
+        .await
+        .map_err(|e| Error::Command(format!("Failed to start Twitch linking: {e}")))?
+        .clone();
+
+    let wait = Duration::from_secs(code.expires_in).min(MAX_LINK_WAIT);
+    let expires_at = chrono::Utc::now().timestamp() + wait.as_secs() as i64;
+
+    let embed = embeds::twitch_embed()
+        .title("Link your Twitch account")
+        .description(format!(
+            "1. Open **[twitch.tv/activate]({})**\n\
+             2. Enter the code **`{}`** and authorise the app\n\n\
+             This request expires <t:{expires_at}:R>.",
+            code.verification_uri, code.user_code
+        ));
+    let handle = ctx
+        .send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    let deadline = tokio::time::Instant::now() + wait;
+    let interval = Duration::from_secs(code.interval.max(1));
+    let token = loop {
+        tokio::time::sleep(interval).await;
+
+        match builder.try_finish(&client).await {
+            Ok(token) => break token,
+            Err(e) if e.is_pending() => {
+                if tokio::time::Instant::now() >= deadline {
+                    let embed = embeds::warning_embed()
+                        .title("Link Expired")
+                        .description("The code wasn't used in time. Run `/link twitch` again.");
+                    handle
+                        .edit(ctx, poise::CreateReply::default().embed(embed))
+                        .await?;
+                    return Ok(());
+                }
+            }
+            Err(e) => {
+                return Err(Error::Command(format!("Twitch authorisation failed: {e}")));
+            }
+        }
+    };
+
+    let twitch_user_id = token.user_id.to_string();
+    let twitch_login = token.login.to_string();
+
+    // We only needed the token to learn who the member is on Twitch.
+    let _ = token.revoke_token(&client).await;
+
+    let db = &ctx.data().db;
+    if let Some(existing) = links::find_by_twitch(db, &twitch_user_id).await? {
+        if existing.discord_user_id != ctx.author().id.get() as i64 {
+            return Err(Error::Command(format!(
+                "The Twitch account **{twitch_login}** is already linked to another member."
+            )));
+        }
+    }
+
+    links::save(db, ctx.author().id, &twitch_user_id, &twitch_login).await?;
+
+    let embed = embeds::success_embed()
+        .title("Twitch Linked")
+        .description(format!(
+            "Your account is now linked to [{twitch_login}](https://twitch.tv/{twitch_login})."
+        ));
+    handle
+        .edit(ctx, poise::CreateReply::default().embed(embed))
+        .await?;
+
+    tracing::info!(user = %ctx.author().name, twitch_login, "Twitch account linked");
+    Ok(())
+}
+
+/// Remove a linked account.
+#[poise::command(slash_command, subcommands("unlink_twitch"), subcommand_required)]
+pub async fn unlink(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Unlink your Twitch account.
+#[poise::command(slash_command, rename = "twitch")]
+pub async fn unlink_twitch(ctx: Context<'_>) -> Result<(), Error> {
+    if !links::remove(&ctx.data().db, ctx.author().id).await? {
+        return Err(Error::Command(
+            "You don't have a linked Twitch account.".into(),
+        ));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Twitch Unlinked")
+        .description("Your Twitch account is no longer linked.");
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Show the accounts a member has linked.
+#[poise::command(slash_command, prefix_command)]
+pub async fn whois(
+    ctx: Context<'_>,
+    #[description = "Member to look up (defaults to you)"] user: Option<User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let twitch = links::find_by_discord(&ctx.data().db, user.id).await?;
+
+    let mut embed = embeds::crimson_embed()
+        .title(format!("Who is {}?", user.name))
+        .thumbnail(
+            user.avatar_url()
+                .unwrap_or_else(|| user.default_avatar_url()),
+        )
+        .field("Discord", user.mention().to_string(), true);
+
+    embed = match twitch {
+        Some(link) => embed.field(
+            "Twitch",
+            format!(
+                "[{}]({}) · linked <t:{}:R>",
+                link.platform_login,
+                link.profile_url(),
+                link.linked_at.timestamp()
+            ),
+            true,
+        ),
+        None => embed.field("Twitch", "Not linked", true),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::members::{self, MemberEvent};
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::{Mentionable, User};
+
+type Error = crate::error::Error;
+
+/// Show a member's join history, activity and warnings.
+#[poise::command(
+    slash_command,
+    guild_only,
+    required_permissions = "MODERATE_MEMBERS",
+    default_member_permissions = "MODERATE_MEMBERS"
+)]
+pub async fn userinfo(
+    ctx: Context<'_>,
+    #[description = "Member to look up"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let record = members::find(db, guild_id, user.id).await?;
+    let history = members::history(db, guild_id, user.id).await?;
+    let warnings = members::warning_count(db, guild_id, user.id).await?;
+    let member = guild_id.member(ctx.http(), user.id).await.ok();
+
+    let mut embed = embeds::crimson_embed()
+        .title(format!("User Info: {}", user.name))
+        .thumbnail(
+            user.avatar_url()
+                .unwrap_or_else(|| user.default_avatar_url()),
+        )
+        .field("User", format!("{} ({})", user.mention(), user.id), false)
+        .field(
+            "Account Created",
+            format!("<t:{}:R>", user.created_at().unix_timestamp()),
+            true,
+        );
+
+    // Discord knows when a current member joined even if the bot never saw it.
+    let joined = member
+        .as_ref()
+        .and_then(|m| m.joined_at)
+        .map(|at| at.unix_timestamp())
+        .or_else(|| record.as_ref()?.join_date.map(|at| at.timestamp()));
+    embed = match (&member, joined) {
+        (Some(_), Some(joined)) => embed.field("Joined", format!("<t:{joined}:R>"), true),
+        (Some(_), None) => embed.field("Joined", "Unknown", true),
+        (None, _) => {
+            let left = record
+                .as_ref()
+                .and_then(|r| r.left_at)
+                .map(|at| format!("Left <t:{}:R>", at.timestamp()))
+                .unwrap_or_else(|| "Not a member".to_string());
+            embed.field("Membership", left, true)
+        }
+    };
+
+    embed = match record {
+        Some(ref record) => {
+            let first_seen = record
+                .first_seen
+                .map(|at| format!("<t:{}:D>", at.timestamp()))
+                .unwrap_or_else(|| "Unknown".to_string());
+            let joins = match record.rejoins() {
+                0 => record.join_count.max(1).to_string(),
+                1 => format!("{} (rejoined once)", record.join_count),
+                n => format!("{} (rejoined {n} times)", record.join_count),
+            };
+            embed
+                .field("First Seen", first_seen, true)
+                .field("Joins", joins, true)
+                .field("Messages", record.message_count.to_string(), true)
+        }
+        None => embed.field("First Seen", "Never", true),
+    };
+    embed = embed.field("Warnings", warnings.to_string(), true);
+
+    if let Some(ref member) = member {
+        let roles: Vec<String> = member
+            .roles
+            .iter()
+            .map(|r| r.mention().to_string())
+            .collect();
+        embed = embed.field(
+            "Roles",
+            if roles.is_empty() {
+                "None".to_string()
+            } else {
+                roles.join(", ")
+            },
+            false,
+        );
+    }
+
+    if !history.is_empty() {
+        let lines: Vec<String> = history
+            .iter()
+            .map(|entry| {
+                let event = match entry.event() {
+                    Some(MemberEvent::Join) => "📥 Joined",
+                    Some(MemberEvent::Leave) => "📤 Left",
+                    None => "•",
+                };
+                format!("{event} <t:{}:f>", entry.created_at.timestamp())
+            })
+            .collect();
+        embed = embed.field("Join History", lines.join("\n"), false);
+    }
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
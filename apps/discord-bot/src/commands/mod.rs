@@ -1 +1,15 @@
+pub mod boosts;
+pub mod clips;
 pub mod general;
+pub mod github;
+pub mod jobs;
+pub mod link;
+pub mod members;
+pub mod persistence;
+pub mod raid;
+pub mod screening;
+pub mod stats;
+pub mod tickets;
+pub mod twitch;
+pub mod verification;
+pub mod welcome;
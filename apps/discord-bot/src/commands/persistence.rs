@@ -0,0 +1,147 @@
+use crate::persistence::{self, PersistenceConfig};
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::{Mentionable, Role, User};
+
+type Error = crate::error::Error;
+
+/// Give members their roles, nickname and timeout back when they rejoin.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "persistence_toggle",
+        "persistence_exclude",
+        "persistence_forget",
+        "persistence_status"
+    ),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn persistence(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Turn role persistence on or off.
+#[poise::command(slash_command, rename = "toggle")]
+pub async fn persistence_toggle(
+    ctx: Context<'_>,
+    #[description = "Restore roles when members rejoin"] enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let mut config = PersistenceConfig::load(db, guild_id).await?;
+    config.enabled = enabled;
+    config.save(db, guild_id).await?;
+
+    let description = if enabled {
+        "Members who leave and rejoin get their roles, nickname and any running timeout back."
+    } else {
+        "Rejoining members start fresh. Roles already saved are kept in case you turn this back on."
+    };
+    let embed = embeds::success_embed()
+        .title(if enabled {
+            "Role Persistence Enabled"
+        } else {
+            "Role Persistence Disabled"
+        })
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Add or remove a role that's never restored.
+#[poise::command(slash_command, rename = "exclude")]
+pub async fn persistence_exclude(
+    ctx: Context<'_>,
+    #[description = "Role to toggle"] role: Role,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let mut config = PersistenceConfig::load(db, guild_id).await?;
+    let id = role.id.get() as i64;
+    let excluded = if config.excluded_role_ids.contains(&id) {
+        config.excluded_role_ids.retain(|r| *r != id);
+        false
+    } else {
+        config.excluded_role_ids.push(id);
+        true
+    };
+    config.save(db, guild_id).await?;
+
+    let description = if excluded {
+        format!("{} won't be restored when members rejoin.", role.mention())
+    } else {
+        format!("{} will be restored again.", role.mention())
+    };
+    let embed = embeds::success_embed()
+        .title("Excluded Roles")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Forget a former member's saved roles so they start fresh if they rejoin.
+#[poise::command(slash_command, rename = "forget")]
+pub async fn persistence_forget(
+    ctx: Context<'_>,
+    #[description = "Former member"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    if !persistence::forget(&ctx.data().db, guild_id, user.id).await? {
+        return Err(Error::Command(format!(
+            "Nothing is saved for {}.",
+            user.mention()
+        )));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Saved Roles Cleared")
+        .description(format!(
+            "{} will start fresh if they rejoin.",
+            user.mention()
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Show the role persistence settings.
+#[poise::command(slash_command, rename = "status")]
+pub async fn persistence_status(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let config = PersistenceConfig::load(db, guild_id).await?;
+    let excluded: Vec<String> = config
+        .excluded_roles()
+        .map(|r| r.mention().to_string())
+        .collect();
+
+    let embed = embeds::crimson_embed()
+        .title("Role Persistence")
+        .field("Status", if config.enabled { "On" } else { "Off" }, true)
+        .field(
+            "Saved Members",
+            persistence::saved_count(db, guild_id).await?.to_string(),
+            true,
+        )
+        .field(
+            "Excluded Roles",
+            if excluded.is_empty() {
+                "None".to_string()
+            } else {
+                excluded.join(", ")
+            },
+            false,
+        );
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use crate::raid::{lockdown, RaidConfig};
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::{ChannelId, CreateEmbed, GuildChannel, Mentionable, Role};
+
+type Error = crate::error::Error;
+
+/// Detect join raids and lock the server down.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "raid_settings",
+        "raid_lockchannel",
+        "raid_lockdown",
+        "raid_end",
+        "raid_status"
+    ),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn raid(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Change what counts as a raid and where alerts go.
+#[poise::command(slash_command, rename = "settings")]
+pub async fn raid_settings(
+    ctx: Context<'_>,
+    #[description = "Turn raid detection on or off (off until turned on)"] enabled: Option<bool>,
+    #[description = "Joins within the window that count as a raid"]
+    #[min = 2]
+    #[max = 100]
+    joins: Option<i32>,
+    #[description = "Length of the window in seconds"]
+    #[min = 5]
+    #[max = 600]
+    seconds: Option<i32>,
+    #[description = "Accounts younger than this many days are suspicious"]
+    #[min = 0]
+    #[max = 365]
+    account_age_days: Option<i32>,
+    #[description = "Channel for raid alerts (default: the log channel)"]
+    #[channel_types("Text")]
+    alert_channel: Option<GuildChannel>,
+    #[description = "Role to ping with raid alerts"] alert_role: Option<Role>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let mut config = RaidConfig::load(db, guild_id).await?;
+    if let Some(enabled) = enabled {
+        config.enabled = enabled;
+    }
+    if let Some(joins) = joins {
+        config.join_threshold = joins;
+    }
+    if let Some(seconds) = seconds {
+        config.window_seconds = seconds;
+    }
+    if let Some(days) = account_age_days {
+        config.account_age_days = days;
+    }
+    if let Some(channel) = alert_channel {
+        config.alert_channel_id = Some(channel.id.get() as i64);
+    }
+    if let Some(role) = alert_role {
+        config.alert_role_id = Some(role.id.get() as i64);
+    }
+    config.save(db, guild_id).await?;
+
+    let embed =
+        settings_embed(&config, ctx.data().config.log_channel_id).title("Raid Settings Updated");
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Add or remove a channel locked during a lockdown.
+#[poise::command(slash_command, rename = "lockchannel")]
+pub async fn raid_lockchannel(
+    ctx: Context<'_>,
+    #[description = "Channel to toggle"]
+    #[channel_types("Text", "Voice", "Forum")]
+    channel: GuildChannel,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let mut config = RaidConfig::load(db, guild_id).await?;
+    let id = channel.id.get() as i64;
+    let added = if config.lock_channel_ids.contains(&id) {
+        config.lock_channel_ids.retain(|c| *c != id);
+        false
+    } else {
+        config.lock_channel_ids.push(id);
+        true
+    };
+    config.save(db, guild_id).await?;
+
+    let description = if added {
+        format!("{} will be locked during lockdowns.", channel.mention())
+    } else {
+        format!("{} will no longer be locked.", channel.mention())
+    };
+    let embed = embeds::success_embed()
+        .title("Lockdown Channels")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Lock the server down now.
+#[poise::command(slash_command, rename = "lockdown")]
+pub async fn raid_lockdown(
+    ctx: Context<'_>,
+    #[description = "Why (shown in the alert)"] reason: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    ctx.defer_ephemeral().await?;
+
+    let data = ctx.data();
+    let config = RaidConfig::load(&data.db, guild_id).await?;
+    let reason = format!(
+        "Manual lockdown by {}: {}",
+        ctx.author().name,
+        reason.as_deref().unwrap_or("no reason given")
+    );
+
+    let started = lockdown::start(ctx.http(), data, guild_id, &config, &reason, &[])
+        .await
+        .map_err(Error::Command)?;
+    if !started {
+        return Err(Error::Command("The server is already locked down.".into()));
+    }
+
+    let embed = embeds::moderation_embed()
+        .title("Lockdown Started")
+        .description(
+            "New members are held without roles and the lockdown channels are locked. \
+             End it with `/raid end` or the button on the alert.",
+        );
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// End the lockdown and undo its changes.
+#[poise::command(slash_command, rename = "end")]
+pub async fn raid_end(
+    ctx: Context<'_>,
+    #[description = "Ban everyone who joined in the raid or during the lockdown"] ban: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    ctx.defer_ephemeral().await?;
+
+    let summary = lockdown::end(
+        ctx.serenity_context(),
+        ctx.data(),
+        guild_id,
+        ctx.author(),
+        ban.unwrap_or(false),
+    )
+    .await
+    .map_err(Error::Command)?;
+
+    let embed = embeds::success_embed()
+        .title("Lockdown Ended")
+        .description(summary);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Show the raid settings and any active lockdown.
+#[poise::command(slash_command, rename = "status")]
+pub async fn raid_status(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let data = ctx.data();
+
+    let config = RaidConfig::load(&data.db, guild_id).await?;
+    let mut embed = settings_embed(&config, data.config.log_channel_id).title("Raid Protection");
+
+    embed = match lockdown::status(&data.db, guild_id).await? {
+        Some(status) => embed.field(
+            "🚨 Lockdown Active",
+            format!(
+                "{}\nStarted <t:{}:R> · **{}** raid accounts · **{}** held",
+                status.reason,
+                status.started_at.timestamp(),
+                status.raid_members,
+                status.held_members,
+            ),
+            false,
+        ),
+        None => embed.field("Lockdown", "Not active", false),
+    };
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+fn settings_embed(config: &RaidConfig, log_channel_id: Option<ChannelId>) -> CreateEmbed {
+    let alert_channel = config
+        .alert_channel(log_channel_id)
+        .map(|c| c.mention().to_string())
+        .unwrap_or_else(|| "None".into());
+    let alert_role = config
+        .alert_role()
+        .map(|r| r.mention().to_string())
+        .unwrap_or_else(|| "None".into());
+    let channels: Vec<String> = config
+        .lock_channels()
+        .map(|c| c.mention().to_string())
+        .collect();
+
+    embeds::crimson_embed()
+        .field("Detection", if config.enabled { "On" } else { "Off" }, true)
+        .field(
+            "Threshold",
+            format!(
+                "{} joins in {}s",
+                config.join_threshold, config.window_seconds
+            ),
+            true,
+        )
+        .field(
+            "New Accounts",
+            format!("Under {} days", config.account_age_days),
+            true,
+        )
+        .field("Alert Channel", alert_channel, true)
+        .field("Alert Role", alert_role, true)
+        .field(
+            "Locked Channels",
+            if channels.is_empty() {
+                "None".to_string()
+            } else {
+                channels.join(", ")
+            },
+            false,
+        )
+}
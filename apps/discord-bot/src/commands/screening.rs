@@ -0,0 +1,231 @@
+use crate::events::member;
+use crate::screening::{self, ScreeningAction, ScreeningConfig};
+use crate::utils::embeds;
+use crate::Context;
+use poise::ChoiceParameter;
+use serenity::all::{ChannelId, CreateEmbed, GuildChannel, Mentionable, Role, User};
+
+type Error = crate::error::Error;
+
+/// Allowlisted members shown by `/screening allowlist`.
+const MAX_LISTED: usize = 25;
+
+/// Screen new accounts as they join.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "screening_settings",
+        "screening_allow",
+        "screening_unallow",
+        "screening_allowlist",
+        "screening_status"
+    ),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn screening(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Change which accounts are screened and what happens to them.
+#[allow(clippy::too_many_arguments)]
+#[poise::command(slash_command, rename = "settings")]
+pub async fn screening_settings(
+    ctx: Context<'_>,
+    #[description = "Turn screening on or off"] enabled: Option<bool>,
+    #[description = "Screen accounts younger than this many days (0 to skip)"]
+    #[min = 0]
+    #[max = 365]
+    account_age_days: Option<i32>,
+    #[description = "Also screen accounts without an avatar"] default_avatar: Option<bool>,
+    #[description = "What happens to screened accounts"] action: Option<ScreeningAction>,
+    #[description = "Role given to quarantined accounts"] quarantine_role: Option<Role>,
+    #[description = "Channel for the daily summary (default: the log channel)"]
+    #[channel_types("Text")]
+    summary_channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let mut config = ScreeningConfig::load(db, guild_id).await?;
+    if let Some(enabled) = enabled {
+        config.enabled = enabled;
+    }
+    if let Some(days) = account_age_days {
+        config.min_account_age_days = days;
+    }
+    if let Some(default_avatar) = default_avatar {
+        config.default_avatar = default_avatar;
+    }
+    if let Some(action) = action {
+        config.action = action.as_str().to_string();
+    }
+    if let Some(role) = quarantine_role {
+        config.quarantine_role_id = Some(role.id.get() as i64);
+    }
+    if let Some(channel) = summary_channel {
+        config.summary_channel_id = Some(channel.id.get() as i64);
+    }
+
+    if config.action() == ScreeningAction::Quarantine && config.quarantine_role_id.is_none() {
+        return Err(Error::Command(
+            "Set a `quarantine_role` to quarantine screened accounts.".into(),
+        ));
+    }
+    config.save(db, guild_id).await?;
+
+    let embed = settings_embed(&config, ctx.data().config.log_channel_id)
+        .title("Screening Settings Updated");
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Let a member skip screening, releasing them from quarantine.
+#[poise::command(slash_command, rename = "allow")]
+pub async fn screening_allow(
+    ctx: Context<'_>,
+    #[description = "Member to allow"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let data = ctx.data();
+
+    let added = screening::allow(&data.db, guild_id, user.id, ctx.author().id).await?;
+    let mut description = if added {
+        format!("{} will skip screening.", user.mention())
+    } else {
+        format!("{} is already on the allowlist.", user.mention())
+    };
+
+    // Quarantined members are let in as if they had just joined.
+    let config = ScreeningConfig::load(&data.db, guild_id).await?;
+    if let (Some(role_id), Ok(held)) = (
+        config.quarantine_role(),
+        guild_id.member(ctx.http(), user.id).await,
+    ) {
+        if held.roles.contains(&role_id) {
+            held.remove_role(ctx.http(), role_id).await?;
+            member::admit(ctx.serenity_context(), &held, data).await;
+            description.push_str(" They've been released from quarantine.");
+        }
+    }
+
+    let embed = embeds::success_embed()
+        .title("Screening Allowlist")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Remove a member from the screening allowlist.
+#[poise::command(slash_command, rename = "unallow")]
+pub async fn screening_unallow(
+    ctx: Context<'_>,
+    #[description = "Member to remove"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    if !screening::unallow(&ctx.data().db, guild_id, user.id).await? {
+        return Err(Error::Command(format!(
+            "{} isn't on the allowlist.",
+            user.mention()
+        )));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Screening Allowlist")
+        .description(format!(
+            "{} will be screened if they join again.",
+            user.mention()
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// List members who skip screening.
+#[poise::command(slash_command, rename = "allowlist")]
+pub async fn screening_allowlist(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    let allowed = screening::allowlist(&ctx.data().db, guild_id).await?;
+    let description = if allowed.is_empty() {
+        "Nobody is on the allowlist. Add members with `/screening allow`.".to_string()
+    } else {
+        let mut lines: Vec<String> = allowed
+            .iter()
+            .take(MAX_LISTED)
+            .map(|entry| {
+                format!(
+                    "<@{}> — added by <@{}> <t:{}:R>",
+                    entry.user_id,
+                    entry.added_by,
+                    entry.created_at.timestamp()
+                )
+            })
+            .collect();
+        if allowed.len() > MAX_LISTED {
+            lines.push(format!("…and {} more", allowed.len() - MAX_LISTED));
+        }
+        lines.join("\n")
+    };
+
+    let embed = embeds::crimson_embed()
+        .title("Screening Allowlist")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Show the screening settings.
+#[poise::command(slash_command, rename = "status")]
+pub async fn screening_status(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let data = ctx.data();
+
+    let config = ScreeningConfig::load(&data.db, guild_id).await?;
+    let screened = screening::screened_today(&data.db, guild_id).await?;
+    let embed = settings_embed(&config, data.config.log_channel_id)
+        .title("Join Screening")
+        .field("Screened (24h)", screened.to_string(), true);
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+fn settings_embed(config: &ScreeningConfig, log_channel_id: Option<ChannelId>) -> CreateEmbed {
+    let age = if config.min_account_age_days > 0 {
+        format!("Under {} days", config.min_account_age_days)
+    } else {
+        "Off".to_string()
+    };
+    let quarantine_role = config
+        .quarantine_role()
+        .map(|r| r.mention().to_string())
+        .unwrap_or_else(|| "None".into());
+    let summary_channel = config
+        .summary_channel(log_channel_id)
+        .map(|c| c.mention().to_string())
+        .unwrap_or_else(|| "None".into());
+
+    embeds::crimson_embed()
+        .field("Screening", if config.enabled { "On" } else { "Off" }, true)
+        .field("Action", config.action().name(), true)
+        .field("Account Age", age, true)
+        .field(
+            "No Avatar",
+            if config.default_avatar {
+                "Screened"
+            } else {
+                "Allowed"
+            },
+            true,
+        )
+        .field("Quarantine Role", quarantine_role, true)
+        .field("Daily Summary", summary_channel, true)
+}
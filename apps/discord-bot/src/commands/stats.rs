@@ -0,0 +1,160 @@
+use crate::stats::{self, StatKind, StatSources};
+use crate::utils::embeds;
+use crate::Context;
+use poise::ChoiceParameter;
+use serenity::all::{
+    ChannelType, CreateChannel, GuildChannel, Mentionable, PermissionOverwrite,
+    PermissionOverwriteType, Permissions,
+};
+
+type Error = crate::error::Error;
+
+/// Voice channels whose names show live server and stream stats.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("statchannel_add", "statchannel_remove", "statchannel_list"),
+    subcommand_required,
+    required_permissions = "MANAGE_CHANNELS",
+    default_member_permissions = "MANAGE_CHANNELS"
+)]
+pub async fn statchannel(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Create a locked voice channel that shows a stat.
+#[poise::command(slash_command, rename = "add")]
+pub async fn statchannel_add(
+    ctx: Context<'_>,
+    #[description = "What the channel shows"] stat: StatKind,
+    #[description = "Channel name with {value} for the stat, e.g. \"👥 Members: {value}\""]
+    name: Option<String>,
+    #[description = "Category to create it in"]
+    #[channel_types("Category")]
+    category: Option<GuildChannel>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let data = ctx.data();
+
+    let template = name.unwrap_or_else(|| stat.default_template().to_string());
+    if !template.contains("{value}") {
+        return Err(Error::Command(
+            "The name needs a `{value}` placeholder for the stat.".into(),
+        ));
+    }
+    if stat != StatKind::Members && data.config.twitch.is_none() {
+        return Err(Error::Command(
+            "Twitch stats need the Twitch integration to be configured.".into(),
+        ));
+    }
+
+    ctx.defer_ephemeral().await?;
+    let sources = StatSources::new(
+        ctx.serenity_context().cache.clone(),
+        data.twitch_api.clone(),
+    );
+    let value = sources
+        .value(guild_id, stat)
+        .await
+        .map_err(|e| Error::Command(format!("Couldn't get the current value: {e}")))?;
+    let channel_name = stats::render(&template, &value);
+
+    // Visible to everyone, but nobody can join.
+    let locked = PermissionOverwrite {
+        allow: Permissions::VIEW_CHANNEL,
+        deny: Permissions::CONNECT,
+        kind: PermissionOverwriteType::Role(guild_id.everyone_role()),
+    };
+    let mut builder = CreateChannel::new(&channel_name)
+        .kind(ChannelType::Voice)
+        .permissions(vec![locked])
+        .audit_log_reason("Stat channel");
+    if let Some(category) = category {
+        builder = builder.category(category.id);
+    }
+    let channel = guild_id.create_channel(ctx.http(), builder).await?;
+
+    stats::add(
+        &data.db,
+        guild_id,
+        channel.id,
+        stat,
+        &template,
+        &channel_name,
+    )
+    .await?;
+
+    let embed = embeds::success_embed()
+        .title("Stat Channel Created")
+        .description(format!(
+            "{} shows **{}** and updates every 10 minutes when it changes.",
+            channel.mention(),
+            stat.name().to_lowercase(),
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Stop updating a stat channel.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn statchannel_remove(
+    ctx: Context<'_>,
+    #[description = "Stat channel"]
+    #[channel_types("Voice")]
+    channel: GuildChannel,
+    #[description = "Also delete the channel (default: yes)"] delete: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    if !stats::remove(&ctx.data().db, guild_id, channel.id).await? {
+        return Err(Error::Command(format!(
+            "{} isn't a stat channel.",
+            channel.mention()
+        )));
+    }
+
+    let description = if delete.unwrap_or(true) {
+        channel.id.delete(ctx.http()).await?;
+        format!("Deleted **{}**.", channel.name)
+    } else {
+        format!("{} will no longer be updated.", channel.mention())
+    };
+    let embed = embeds::success_embed()
+        .title("Stat Channel Removed")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// List the server's stat channels.
+#[poise::command(slash_command, rename = "list")]
+pub async fn statchannel_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    let channels = stats::list(&ctx.data().db, guild_id).await?;
+    let description = if channels.is_empty() {
+        "No stat channels yet. Create one with `/statchannel add`.".to_string()
+    } else {
+        channels
+            .iter()
+            .map(|channel| {
+                let kind = channel.kind().map(|k| k.name()).unwrap_or("Unknown");
+                format!(
+                    "{} — {kind} (`{}`)",
+                    channel.channel().mention(),
+                    channel.template
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = embeds::crimson_embed()
+        .title("Stat Channels")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use crate::tickets::{self, Ticket, TicketConfig, DEFAULT_INACTIVITY_HOURS};
+use crate::utils::embeds;
+use crate::Context;
+use serenity::all::{GuildChannel, Mentionable, Role, User};
+
+type Error = crate::error::Error;
+
+const DEFAULT_PANEL_TEXT: &str =
+    "Need help from the staff team? Press the button below to open a private ticket.";
+
+/// Private support tickets.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands(
+        "ticket_panel",
+        "ticket_claim",
+        "ticket_close",
+        "ticket_add",
+        "ticket_list"
+    ),
+    subcommand_required
+)]
+pub async fn ticket(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Post the panel members open tickets from, and save the ticket settings.
+#[poise::command(slash_command, rename = "panel", required_permissions = "MANAGE_GUILD")]
+pub async fn ticket_panel(
+    ctx: Context<'_>,
+    #[description = "Channel for the panel; tickets are private threads in it"]
+    #[channel_types("Text")]
+    channel: GuildChannel,
+    #[description = "Role that handles tickets"] staff_role: Role,
+    #[description = "Channel for transcripts (default: the log channel)"]
+    #[channel_types("Text")]
+    log_channel: Option<GuildChannel>,
+    #[description = "Close tickets after this many hours without messages, 0 to never (default 48)"]
+    #[min = 0]
+    #[max = 168]
+    auto_close_hours: Option<i32>,
+    #[description = "Text shown on the panel"] message: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    let config = TicketConfig {
+        channel_id: channel.id.get() as i64,
+        staff_role_id: staff_role.id.get() as i64,
+        log_channel_id: log_channel.map(|c| c.id.get() as i64),
+        inactivity_hours: auto_close_hours.unwrap_or(DEFAULT_INACTIVITY_HOURS),
+    };
+    channel
+        .id
+        .send_message(
+            ctx.http(),
+            tickets::panel_message(message.as_deref().unwrap_or(DEFAULT_PANEL_TEXT)),
+        )
+        .await?;
+    config.save(&ctx.data().db, guild_id).await?;
+
+    let auto_close = match config.inactivity_hours {
+        0 => "never closed automatically".to_string(),
+        hours => format!("closed after **{hours} hours** without messages"),
+    };
+    let transcripts = match config.log_channel(ctx.data().config.log_channel_id) {
+        Some(channel) => format!("Transcripts go to {}.", channel.mention()),
+        None => "No log channel is set, so transcripts won't be kept.".to_string(),
+    };
+    let embed = embeds::success_embed()
+        .title("Ticket Panel Posted")
+        .description(format!(
+            "Tickets open as private threads in {} for {}, and are {auto_close}. {transcripts}\n\n\
+             Make sure the bot can create private threads there, and that the staff role \
+             can see the channel and can be mentioned by the bot.",
+            channel.mention(),
+            staff_role.mention(),
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Take on the ticket in this thread.
+#[poise::command(slash_command, rename = "claim")]
+pub async fn ticket_claim(ctx: Context<'_>) -> Result<(), Error> {
+    let (_, ticket) = current_ticket(ctx, true).await?;
+
+    let message = tickets::claim(ctx.http(), &ctx.data().db, &ticket, ctx.author().id)
+        .await
+        .map_err(Error::Command)?;
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embeds::success_embed().description(message))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Close the ticket in this thread and save its transcript.
+#[poise::command(slash_command, rename = "close")]
+pub async fn ticket_close(
+    ctx: Context<'_>,
+    #[description = "Why it's being closed"] reason: Option<String>,
+) -> Result<(), Error> {
+    let (config, ticket) = current_ticket(ctx, false).await?;
+
+    ctx.defer_ephemeral().await?;
+    let message = tickets::close(
+        ctx.http(),
+        &ctx.data().db,
+        &ticket,
+        Some(ctx.author().id),
+        reason.as_deref(),
+        config.log_channel(ctx.data().config.log_channel_id),
+    )
+    .await
+    .map_err(Error::Command)?;
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embeds::success_embed().description(message))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Add someone to the ticket in this thread.
+#[poise::command(slash_command, rename = "add")]
+pub async fn ticket_add(
+    ctx: Context<'_>,
+    #[description = "Member to add"] user: User,
+) -> Result<(), Error> {
+    let (_, ticket) = current_ticket(ctx, true).await?;
+
+    ticket
+        .channel()
+        .add_thread_member(ctx.http(), user.id)
+        .await?;
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embeds::success_embed().description(format!(
+                "Added {} to ticket #{}.",
+                user.mention(),
+                ticket.id
+            )))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// List the server's open tickets.
+#[poise::command(slash_command, rename = "list")]
+pub async fn ticket_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let config = load_config(ctx).await?;
+    require_staff(ctx, &config).await?;
+
+    let open = tickets::list_open(&ctx.data().db, guild_id).await?;
+    let description = if open.is_empty() {
+        "No open tickets.".to_string()
+    } else {
+        open.iter()
+            .map(|ticket| {
+                let claimed = ticket
+                    .claimed_by()
+                    .map(|id| format!("claimed by {}", id.mention()))
+                    .unwrap_or_else(|| "unclaimed".to_string());
+                format!(
+                    "**#{}** {} — {}, opened <t:{}:R>, {claimed}",
+                    ticket.id,
+                    ticket.channel().mention(),
+                    ticket.user().mention(),
+                    ticket.created_at.timestamp()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = embeds::crimson_embed()
+        .title(format!("Open Tickets ({})", open.len()))
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+async fn load_config(ctx: Context<'_>) -> Result<TicketConfig, Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    TicketConfig::load(&ctx.data().db, guild_id)
+        .await?
+        .ok_or_else(|| {
+            Error::Command("Tickets aren't set up. Post a panel with `/ticket panel`.".into())
+        })
+}
+
+async fn require_staff(ctx: Context<'_>, config: &TicketConfig) -> Result<(), Error> {
+    let member = ctx.author_member().await.ok_or_else(Error::guild_only)?;
+    if config.is_staff(&member) {
+        Ok(())
+    } else {
+        Err(Error::Command("Only ticket staff can do that.".into()))
+    }
+}
+
+/// The open ticket this command was used in. The member who opened it counts
+/// as staff unless `staff_only`.
+async fn current_ticket(
+    ctx: Context<'_>,
+    staff_only: bool,
+) -> Result<(TicketConfig, Ticket), Error> {
+    let config = load_config(ctx).await?;
+    let ticket = tickets::find_open(&ctx.data().db, ctx.channel_id())
+        .await?
+        .ok_or_else(|| Error::Command("Use this in an open ticket thread.".into()))?;
+
+    if staff_only || ticket.user() != ctx.author().id {
+        require_staff(ctx, &config).await?;
+    }
+    Ok((config, ticket))
+}
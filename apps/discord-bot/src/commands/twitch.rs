@@ -0,0 +1,346 @@
+use crate::integrations::twitch::alerts::{self, Alert, AlertKind};
+use crate::integrations::twitch::redemptions::{self, RewardAction, RewardMapping};
+use crate::integrations::twitch::templates::{StreamTemplate, StreamVars, TemplateKind};
+use crate::utils::{embeds, permissions};
+use crate::Context;
+use poise::ChoiceParameter;
+
+type Error = crate::error::Error;
+
+const PLACEHOLDER_HELP: &str = "Placeholders: `{title}` `{game}` `{viewers}` `{uptime}` `{url}` \
+    `{box_art}` `{thumbnail}` `{role}`; ended only: `{duration}` `{peak_viewers}` `{avg_viewers}` \
+    `{titles}` `{games}` `{vod}`. Pass `-` to clear a field.";
+
+/// Most rewards shown by `/twitch reward list`.
+const MAX_LISTED: usize = 20;
+
+/// Manage Twitch stream notifications.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("template", "preview", "alert", "reward"),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn twitch(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Customise the go-live, update and stream-ended messages.
+#[poise::command(
+    slash_command,
+    subcommands("template_set", "template_reset"),
+    subcommand_required
+)]
+pub async fn template(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Change fields of a stream notification template.
+#[poise::command(slash_command, rename = "set")]
+#[allow(clippy::too_many_arguments)]
+pub async fn template_set(
+    ctx: Context<'_>,
+    #[description = "Which notification to edit"] kind: TemplateKind,
+    #[description = "Message text above the embed"] content: Option<String>,
+    #[description = "Embed title"] title: Option<String>,
+    #[description = "Embed description"] description: Option<String>,
+    #[description = "Large embed image URL"] image: Option<String>,
+    #[description = "Small embed thumbnail URL"] thumbnail: Option<String>,
+    #[description = "Show the Game/Viewers fields"] show_fields: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let mut template = StreamTemplate::load(db, Some(guild_id), kind).await?;
+    apply(&mut template.content, content);
+    apply(&mut template.title, title);
+    apply(&mut template.description, description);
+    apply(&mut template.image_url, image);
+    apply(&mut template.thumbnail_url, thumbnail);
+    if let Some(show_fields) = show_fields {
+        template.show_fields = show_fields;
+    }
+
+    template.save(db, guild_id, kind).await?;
+
+    let rendered = template.render(kind, &StreamVars::sample(ctx.data().config.twitch.as_ref()));
+    let reply = poise::CreateReply::default()
+        .content(format!(
+            "Template **{}** saved. Preview:\n{}",
+            kind.name(),
+            rendered.content
+        ))
+        .embed(rendered.embed)
+        .ephemeral(true);
+
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Restore the default template for a stream notification.
+#[poise::command(slash_command, rename = "reset")]
+pub async fn template_reset(
+    ctx: Context<'_>,
+    #[description = "Which notification to reset"] kind: TemplateKind,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    StreamTemplate::reset(&ctx.data().db, guild_id, kind).await?;
+
+    let embed = embeds::success_embed()
+        .title("Template Reset")
+        .description(format!(
+            "**{}** now uses the default template.",
+            kind.name()
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Render a stream notification template with sample data.
+#[poise::command(slash_command)]
+pub async fn preview(
+    ctx: Context<'_>,
+    #[description = "Which notification to preview"] kind: TemplateKind,
+) -> Result<(), Error> {
+    let template = StreamTemplate::load(&ctx.data().db, ctx.guild_id(), kind).await?;
+    let rendered = template.render(kind, &StreamVars::sample(ctx.data().config.twitch.as_ref()));
+
+    let reply = poise::CreateReply::default()
+        .content(format!("{}\n-# {PLACEHOLDER_HELP}", rendered.content))
+        .embed(rendered.embed)
+        .ephemeral(true);
+
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Customise follow, sub, cheer and raid alerts.
+#[poise::command(
+    slash_command,
+    subcommands("alert_set", "alert_reset"),
+    subcommand_required
+)]
+pub async fn alert(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Change the message posted for an activity alert.
+#[poise::command(slash_command, rename = "set")]
+pub async fn alert_set(
+    ctx: Context<'_>,
+    #[description = "Which alert to edit"] kind: AlertKind,
+    #[description = "Alert text. Placeholders: {user} {amount} {tier} {message}"] message: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    alerts::save_template(&ctx.data().db, guild_id, kind, &message).await?;
+
+    let embed = embeds::twitch_embed().description(Alert::sample(kind).render(&message));
+    let reply = poise::CreateReply::default()
+        .content(format!("Alert **{}** saved. Preview:", kind.name()))
+        .embed(embed)
+        .ephemeral(true);
+
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Restore the default message for an activity alert.
+#[poise::command(slash_command, rename = "reset")]
+pub async fn alert_reset(
+    ctx: Context<'_>,
+    #[description = "Which alert to reset"] kind: AlertKind,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    alerts::reset_template(&ctx.data().db, guild_id, kind).await?;
+
+    let embed = embeds::success_embed()
+        .title("Alert Reset")
+        .description(format!(
+            "**{}** now uses the default message: {}",
+            kind.name(),
+            kind.default_template()
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Map channel-point rewards to Discord actions.
+#[poise::command(
+    slash_command,
+    subcommands("reward_set", "reward_remove", "reward_list"),
+    subcommand_required
+)]
+pub async fn reward(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Map a channel-point reward to a Discord action.
+///
+/// Temporary roles and Crimson Coins go to the Discord member named in the
+/// redemption's text input. If the action fails the viewer is refunded.
+#[poise::command(slash_command, rename = "set")]
+#[allow(clippy::too_many_arguments)]
+pub async fn reward_set(
+    ctx: Context<'_>,
+    #[description = "Twitch reward ID (logged when an unmapped reward is redeemed)"]
+    #[max_length = 100]
+    reward_id: String,
+    #[description = "What to do when the reward is redeemed"] action: RewardAction,
+    #[description = "Role to grant (temporary role)"] role: Option<serenity::all::Role>,
+    #[description = "How long the role lasts, in minutes (temporary role)"]
+    #[min = 1]
+    duration_minutes: Option<i32>,
+    #[description = "Channel to post in (shoutout)"] channel: Option<serenity::all::GuildChannel>,
+    #[description = "Coins to credit (Crimson Coins)"]
+    #[min = 1]
+    amount: Option<i32>,
+    #[description = "Shoutout text. Placeholders: {user} {reward} {input}"] message: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    let missing = match action {
+        RewardAction::TempRole if role.is_none() || duration_minutes.is_none() => {
+            Some("a role and a duration")
+        }
+        RewardAction::Shoutout if channel.is_none() => Some("a channel"),
+        RewardAction::Currency if amount.is_none() => Some("an amount"),
+        _ => None,
+    };
+    if let Some(missing) = missing {
+        return Err(Error::Command(format!(
+            "The **{}** action needs {missing}.",
+            action.name()
+        )));
+    }
+
+    if let Some(ref role) = role {
+        let member = ctx.author_member().await.ok_or_else(Error::guild_only)?;
+        let bot_id = ctx.cache().current_user().id;
+        let problem = match ctx.guild() {
+            Some(guild) => permissions::role_assign_error(&guild, &member, bot_id, role),
+            None => Some("The server isn't cached yet. Try again in a moment.".to_string()),
+        };
+        if let Some(problem) = problem {
+            return Err(Error::Command(problem));
+        }
+    }
+
+    let mapping = RewardMapping {
+        reward_id: reward_id.trim().to_string(),
+        guild_id: guild_id.get() as i64,
+        action: action.as_str().to_string(),
+        role_id: role.map(|r| r.id.get() as i64),
+        duration_secs: duration_minutes.map(|m| m.saturating_mul(60)),
+        channel_id: channel.map(|c| c.id.get() as i64),
+        amount,
+        message,
+    };
+    if !redemptions::save_mapping(&ctx.data().db, &mapping).await? {
+        return Err(Error::Command(format!(
+            "Reward `{}` is already mapped in another server.",
+            mapping.reward_id
+        )));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Reward Mapped")
+        .description(format!(
+            "Redeeming `{}` will now trigger **{}**.",
+            mapping.reward_id,
+            action.name()
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Stop a channel-point reward from triggering a Discord action.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn reward_remove(
+    ctx: Context<'_>,
+    #[description = "Twitch reward ID"] reward_id: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    if !redemptions::remove_mapping(&ctx.data().db, guild_id, reward_id.trim()).await? {
+        return Err(Error::Command(format!(
+            "No mapping found for reward `{}`.",
+            reward_id.trim()
+        )));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Reward Unmapped")
+        .description(format!(
+            "`{}` no longer triggers anything.",
+            reward_id.trim()
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// List channel-point rewards mapped to Discord actions.
+#[poise::command(slash_command, rename = "list")]
+pub async fn reward_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let mappings = redemptions::list_mappings(&ctx.data().db, guild_id).await?;
+
+    let description = if mappings.is_empty() {
+        "No rewards are mapped yet. Use `/twitch reward set` to add one.".to_string()
+    } else {
+        let mut lines: Vec<String> = mappings
+            .iter()
+            .take(MAX_LISTED)
+            .map(describe_mapping)
+            .collect();
+        if mappings.len() > MAX_LISTED {
+            lines.push(format!("…and {} more", mappings.len() - MAX_LISTED));
+        }
+        lines.join("\n")
+    };
+
+    let embed = embeds::twitch_embed()
+        .title("Channel Point Rewards")
+        .description(description);
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+fn describe_mapping(mapping: &RewardMapping) -> String {
+    let action = match RewardAction::parse(&mapping.action) {
+        Some(RewardAction::TempRole) => format!(
+            "<@&{}> for {} min",
+            mapping.role_id.unwrap_or_default(),
+            mapping.duration_secs.unwrap_or_default() / 60
+        ),
+        Some(RewardAction::Shoutout) => {
+            format!("shoutout in <#{}>", mapping.channel_id.unwrap_or_default())
+        }
+        Some(RewardAction::Currency) => format!("{} coins", mapping.amount.unwrap_or_default()),
+        None => mapping.action.clone(),
+    };
+    format!("`{}` → {action}", mapping.reward_id)
+}
+
+/// Overwrite a template field with a user-provided value; `-` clears it.
+fn apply(field: &mut String, value: Option<String>) {
+    match value.as_deref() {
+        Some("-") => field.clear(),
+        Some(v) => *field = v.to_string(),
+        None => {}
+    }
+}
@@ -0,0 +1,117 @@
+use crate::utils::embeds;
+use crate::verification::{self, VerificationConfig, VerificationMode, DEFAULT_TIMEOUT_MINUTES};
+use crate::Context;
+use poise::ChoiceParameter;
+use serenity::all::{GuildChannel, Mentionable};
+
+type Error = crate::error::Error;
+
+/// Make new members verify before they get the auto-roles.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("verification_enable", "verification_disable", "verification_status"),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn verification(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Turn on the verification gate.
+#[poise::command(slash_command, rename = "enable")]
+pub async fn verification_enable(
+    ctx: Context<'_>,
+    #[description = "Channel where new members verify"]
+    #[channel_types("Text")]
+    channel: GuildChannel,
+    #[description = "Press a button, or also solve a captcha"] mode: VerificationMode,
+    #[description = "Minutes to verify before being kicked (default 10)"]
+    #[min = 1]
+    #[max = 1440]
+    timeout: Option<i32>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    if mode == VerificationMode::Captcha && ctx.data().welcome_cards.is_none() {
+        return Err(Error::Command(
+            "Captchas can't be drawn because the card font didn't load. Use button mode, \
+             or check the welcome card settings and restart the bot."
+                .into(),
+        ));
+    }
+
+    let config = VerificationConfig {
+        enabled: true,
+        channel_id: channel.id.get() as i64,
+        mode: mode.as_str().to_string(),
+        timeout_minutes: timeout.unwrap_or(DEFAULT_TIMEOUT_MINUTES),
+    };
+    config.save(&ctx.data().db, guild_id).await?;
+
+    let embed = embeds::success_embed()
+        .title("Verification Enabled")
+        .description(format!(
+            "New members must verify in {} within **{} minutes** ({}) before they get the auto-roles.\n\n\
+             Make sure members without roles can see that channel, and that the bot can \
+             kick members and manage roles.",
+            channel.mention(),
+            config.timeout_minutes,
+            mode.name().to_lowercase(),
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Turn off the verification gate.
+#[poise::command(slash_command, rename = "disable")]
+pub async fn verification_disable(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    if !VerificationConfig::disable(&ctx.data().db, guild_id).await? {
+        return Err(Error::Command("Verification isn't on.".into()));
+    }
+
+    let embed = embeds::success_embed()
+        .title("Verification Disabled")
+        .description(
+            "New members get the auto-roles straight away again. \
+             Members already waiting can still verify until their time runs out.",
+        );
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Show the verification settings.
+#[poise::command(slash_command, rename = "status")]
+pub async fn verification_status(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    let embed = match VerificationConfig::load(db, guild_id).await? {
+        Some(config) => embeds::crimson_embed()
+            .title("Verification")
+            .field("Status", if config.enabled { "On" } else { "Off" }, true)
+            .field("Mode", config.mode().name(), true)
+            .field(
+                "Time Limit",
+                format!("{} min", config.timeout_minutes),
+                true,
+            )
+            .field("Channel", config.channel().mention().to_string(), true)
+            .field(
+                "Waiting",
+                verification::pending_count(db, guild_id).await?.to_string(),
+                true,
+            ),
+        None => embeds::crimson_embed()
+            .title("Verification")
+            .description("Verification is off. Turn it on with `/verification enable`."),
+    };
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use crate::utils::embeds;
+use crate::welcome::templates::{WelcomeKind, WelcomeTemplate, WelcomeVars};
+use crate::Context;
+use poise::ChoiceParameter;
+use serenity::all::{GuildChannel, GuildId};
+
+type Error = crate::error::Error;
+
+const PLACEHOLDER_HELP: &str = "Placeholders: `{user}` `{username}` `{server}` `{member_count}` \
+    `{channel:name}`. Pass `-` to clear the title.";
+
+/// How a welcome or leave message is posted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum MessageMode {
+    Embed,
+    #[name = "Plain text"]
+    Plain,
+}
+
+/// Configure welcome and leave messages.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("welcome_set", "welcome_reset", "welcome_test"),
+    subcommand_required,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn welcome(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Change a welcome or leave message.
+#[poise::command(slash_command, rename = "set")]
+#[allow(clippy::too_many_arguments)]
+pub async fn welcome_set(
+    ctx: Context<'_>,
+    #[description = "Which message to edit"] kind: WelcomeKind,
+    #[description = "Message text"] message: Option<String>,
+    #[description = "Embed title"] title: Option<String>,
+    #[description = "Post as an embed or plain text"] mode: Option<MessageMode>,
+    #[description = "Channel to post in (defaults to the welcome channel)"]
+    #[channel_types("Text", "News")]
+    channel: Option<GuildChannel>,
+    #[description = "Attach a generated image card (welcome only)"] card: Option<bool>,
+    #[description = "Send this message at all"] enabled: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+    let db = &ctx.data().db;
+
+    if card == Some(true) && kind != WelcomeKind::Welcome {
+        return Err(Error::Command(
+            "Image cards are only available for welcome messages.".into(),
+        ));
+    }
+
+    let mut template = WelcomeTemplate::load(db, guild_id, kind).await?;
+    if let Some(message) = message {
+        template.message = message;
+    }
+    match title.as_deref() {
+        Some("-") => template.title.clear(),
+        Some(title) => template.title = title.to_string(),
+        None => {}
+    }
+    if let Some(mode) = mode {
+        template.embed = mode == MessageMode::Embed;
+    }
+    if let Some(channel) = channel {
+        template.channel_id = Some(channel.id.get() as i64);
+    }
+    if let Some(card) = card {
+        template.card = card;
+    }
+    if let Some(enabled) = enabled {
+        template.enabled = enabled;
+    }
+
+    template.save(db, guild_id, kind).await?;
+
+    let status = match template.channel(ctx.data().config.welcome_channel_id) {
+        _ if !template.enabled => "It's currently **off**.".to_string(),
+        Some(channel_id) => format!("It's posted in <#{channel_id}>."),
+        None => "No channel is set, so it won't be posted yet.".to_string(),
+    };
+    let reply = preview_reply(
+        ctx,
+        guild_id,
+        &template,
+        kind,
+        format!("**{}** message saved. {status} Preview:", kind.name()),
+    )
+    .await;
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Restore the default welcome or leave message.
+#[poise::command(slash_command, rename = "reset")]
+pub async fn welcome_reset(
+    ctx: Context<'_>,
+    #[description = "Which message to reset"] kind: WelcomeKind,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    WelcomeTemplate::reset(&ctx.data().db, guild_id, kind).await?;
+
+    let embed = embeds::success_embed()
+        .title("Message Reset")
+        .description(format!(
+            "The **{}** message now uses the default.",
+            kind.name()
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Preview a welcome or leave message as if you had just joined or left.
+#[poise::command(slash_command, rename = "test")]
+pub async fn welcome_test(
+    ctx: Context<'_>,
+    #[description = "Which message to preview"] kind: WelcomeKind,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or_else(Error::guild_only)?;
+
+    let template = WelcomeTemplate::load(&ctx.data().db, guild_id, kind).await?;
+    let reply = preview_reply(
+        ctx,
+        guild_id,
+        &template,
+        kind,
+        format!("-# {PLACEHOLDER_HELP}"),
+    )
+    .await;
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+/// Render the template for the invoking member, card included.
+async fn preview_reply(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    template: &WelcomeTemplate,
+    kind: WelcomeKind,
+    mut note: String,
+) -> poise::CreateReply {
+    let vars = WelcomeVars::new(ctx.serenity_context(), guild_id, ctx.author()).await;
+
+    let card = match ctx.data().welcome_cards {
+        _ if !template.card || kind != WelcomeKind::Welcome => None,
+        Some(ref cards) => match cards.attachment(ctx.author(), &vars).await {
+            Ok(card) => Some(card),
+            Err(e) => {
+                note.push_str(&format!("\n-# The image card failed to render: {e}"));
+                None
+            }
+        },
+        None => {
+            note.push_str("\n-# Image cards are unavailable: the card font couldn't be loaded.");
+            None
+        }
+    };
+
+    let rendered = template.render(kind, &vars, card);
+    let content = if rendered.content.is_empty() {
+        note
+    } else {
+        format!("{note}\n{}", rendered.content)
+    };
+
+    let mut reply = poise::CreateReply::default()
+        .content(content)
+        .ephemeral(true);
+    if let Some(embed) = rendered.embed {
+        reply = reply.embed(embed);
+    }
+    if let Some(card) = rendered.card {
+        reply = reply.attachment(card);
+    }
+    reply
+}
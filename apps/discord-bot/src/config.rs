@@ -1,6 +1,12 @@
 use crate::error::Error;
+use serde::Deserialize;
 use serenity::all::{ChannelId, GuildId, RoleId};
 
+const DEFAULT_CONFIG_PATH: &str = "config/config.toml";
+
+/// Font for welcome cards when `config.toml` doesn't name one (Debian's `fonts-dejavu-core`).
+const DEFAULT_CARD_FONT: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf";
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub discord_token: String,
@@ -9,9 +15,20 @@ pub struct Config {
     pub guild_id: Option<GuildId>,
     pub welcome_channel_id: Option<ChannelId>,
     pub log_channel_id: Option<ChannelId>,
+    /// Role held while a member streams on Twitch (per Discord presence).
+    pub streaming_role_id: Option<RoleId>,
+    /// When set, only members with this role get the streaming role.
+    pub content_creator_role_id: Option<RoleId>,
     pub bot_version: String,
+    /// Port for health checks, metrics and the GitHub webhook.
+    pub http_port: u16,
+    /// Fallback text for `/schedule` when the Twitch schedule is unavailable.
+    pub schedule_text: Option<String>,
     // Twitch (Phase 2)
     pub twitch: Option<TwitchConfig>,
+    // GitHub (Phase 5)
+    pub github: GithubConfig,
+    pub welcome_card: WelcomeCardConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -19,9 +36,48 @@ pub struct TwitchConfig {
     pub client_id: String,
     pub client_secret: String,
     pub channel_id: String,
+    pub username: String,
     pub live_channel_id: ChannelId,
     pub live_chat_channel_id: Option<ChannelId>,
     pub live_role_id: Option<RoleId>,
+    pub activity_channel_id: Option<ChannelId>,
+    pub clips_channel_id: Option<ChannelId>,
+    pub user_access_token: Option<String>,
+    pub user_refresh_token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GithubConfig {
+    /// Personal access token for the REST API; unauthenticated requests are heavily rate limited.
+    pub token: Option<String>,
+    /// Fallback for guilds that haven't picked a default repository.
+    pub default_repo: Option<String>,
+    /// The webhook receiver only runs when a secret is set.
+    pub webhook_secret: Option<String>,
+    /// Channel for repositories without their own route.
+    pub default_channel_id: Option<ChannelId>,
+    pub repos: Vec<RepoRoute>,
+}
+
+/// Look of the generated welcome image cards.
+#[derive(Debug, Clone)]
+pub struct WelcomeCardConfig {
+    /// Image cropped to fill the card; `None` uses a crimson gradient.
+    pub background: Option<String>,
+    /// TrueType or OpenType font file.
+    pub font: String,
+}
+
+/// Where a repository's webhook events are posted.
+#[derive(Debug, Clone)]
+pub struct RepoRoute {
+    /// `owner/name`, matched case-insensitively.
+    pub name: String,
+    pub channel_id: ChannelId,
+    /// Branch patterns for push, pull request and workflow events; empty allows all.
+    pub branches: Vec<String>,
+    /// Event names to post; empty allows all.
+    pub events: Vec<String>,
 }
 
 impl Config {
@@ -36,7 +92,16 @@ impl Config {
     /// - `GUILD_ID` — Guild for instant slash command registration
     /// - `WELCOME_CHANNEL_ID` — Channel for welcome embeds
     /// - `LOG_CHANNEL_ID` — Channel for mod-logs
+    /// - `STREAMING_ROLE_ID` — Role for members streaming on Twitch (needs the presence intent)
+    /// - `CONTENT_CREATOR_ROLE_ID` — Limit the streaming role to members with this role
     /// - `TWITCH_CLIENT_ID` + `TWITCH_CLIENT_SECRET` + `TWITCH_CHANNEL_ID` + `LIVE_CHANNEL_ID` — Twitch integration
+    /// - `TWITCH_USER_ACCESS_TOKEN` + `TWITCH_USER_REFRESH_TOKEN` — Broadcaster token for follow/sub/cheer events and the chat bridge
+    /// - `STREAM_ACTIVITY_CHANNEL_ID` — Channel for follow/sub/cheer/raid alerts
+    /// - `CLIPS_CHANNEL_ID` — Channel for `/clip` submissions and Clip of the Week
+    /// - `GITHUB_TOKEN` — GitHub REST API token for `/repo`, `/issues`, `/commits` and `/pr`
+    /// - `WEBHOOK_PORT` (or `PORT`) — HTTP port for health checks, metrics and webhooks (default 8080)
+    /// - `GITHUB_WEBHOOK_SECRET` — GitHub webhook receiver (routes in `config.toml`)
+    /// - `CONFIG_PATH` — Settings file (default `config/config.toml`)
     pub fn from_env() -> Result<Self, Error> {
         let discord_token = std::env::var("DISCORD_TOKEN")
             .map_err(|_| Error::Config("DISCORD_TOKEN environment variable is required".into()))?;
@@ -49,8 +114,13 @@ impl Config {
         let guild_id = parse_optional_id::<GuildId>("GUILD_ID")?;
         let welcome_channel_id = parse_optional_id::<ChannelId>("WELCOME_CHANNEL_ID")?;
         let log_channel_id = parse_optional_id::<ChannelId>("LOG_CHANNEL_ID")?;
+        let streaming_role_id = parse_optional_id::<RoleId>("STREAMING_ROLE_ID")?;
+        let content_creator_role_id = parse_optional_id::<RoleId>("CONTENT_CREATOR_ROLE_ID")?;
+        let http_port = parse_port()?;
 
         let twitch = TwitchConfig::from_env()?;
+        let file = FileConfig::load()?;
+        let github = GithubConfig::from_env(file.github)?;
 
         Ok(Self {
             discord_token,
@@ -59,12 +129,77 @@ impl Config {
             guild_id,
             welcome_channel_id,
             log_channel_id,
+            streaming_role_id,
+            content_creator_role_id,
             bot_version: env!("CARGO_PKG_VERSION").to_string(),
+            http_port,
+            schedule_text: file.schedule.text,
             twitch,
+            github,
+            welcome_card: WelcomeCardConfig {
+                background: file.welcome_card.background,
+                font: file
+                    .welcome_card
+                    .font
+                    .unwrap_or_else(|| DEFAULT_CARD_FONT.into()),
+            },
         })
     }
 }
 
+/// Non-secret settings from `config.toml`. A missing file means defaults.
+#[derive(Debug, Default, Deserialize)]
+struct FileConfig {
+    #[serde(default)]
+    schedule: ScheduleSection,
+    #[serde(default)]
+    github: GithubSection,
+    #[serde(default)]
+    welcome_card: WelcomeCardSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ScheduleSection {
+    text: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WelcomeCardSection {
+    background: Option<String>,
+    font: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GithubSection {
+    default_repo: Option<String>,
+    default_channel_id: Option<u64>,
+    #[serde(default)]
+    repos: Vec<RepoSection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoSection {
+    name: String,
+    channel_id: u64,
+    #[serde(default)]
+    branches: Vec<String>,
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+impl FileConfig {
+    fn load() -> Result<Self, Error> {
+        let path = optional_var("CONFIG_PATH").unwrap_or_else(|| DEFAULT_CONFIG_PATH.into());
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(Error::Config(format!("Failed to read {path}: {e}"))),
+        };
+
+        toml::from_str(&contents).map_err(|e| Error::Config(format!("Invalid {path}: {e}")))
+    }
+}
+
 impl TwitchConfig {
     fn from_env() -> Result<Option<Self>, Error> {
         let client_id = match std::env::var("TWITCH_CLIENT_ID") {
@@ -77,22 +212,103 @@ impl TwitchConfig {
         let channel_id = std::env::var("TWITCH_CHANNEL_ID").map_err(|_| {
             Error::Config("TWITCH_CHANNEL_ID is required when TWITCH_CLIENT_ID is set".into())
         })?;
+        let username = optional_var("TWITCH_USERNAME").unwrap_or_else(|| "0xDC143C".into());
         let live_channel_id =
             parse_optional_id::<ChannelId>("LIVE_CHANNEL_ID")?.ok_or_else(|| {
                 Error::Config("LIVE_CHANNEL_ID is required when TWITCH_CLIENT_ID is set".into())
             })?;
         let live_chat_channel_id = parse_optional_id::<ChannelId>("LIVE_CHAT_CHANNEL_ID")?;
         let live_role_id = parse_optional_id::<RoleId>("LIVE_ROLE_ID")?;
+        let activity_channel_id = parse_optional_id::<ChannelId>("STREAM_ACTIVITY_CHANNEL_ID")?;
+        let clips_channel_id = parse_optional_id::<ChannelId>("CLIPS_CHANNEL_ID")?;
+        let user_access_token = optional_var("TWITCH_USER_ACCESS_TOKEN");
+        let user_refresh_token = optional_var("TWITCH_USER_REFRESH_TOKEN");
 
         Ok(Some(Self {
             client_id,
             client_secret,
             channel_id,
+            username,
             live_channel_id,
             live_chat_channel_id,
             live_role_id,
+            activity_channel_id,
+            clips_channel_id,
+            user_access_token,
+            user_refresh_token,
         }))
     }
+
+    /// Public URL of the broadcaster's Twitch channel.
+    pub fn channel_url(&self) -> String {
+        format!("https://twitch.tv/{}", self.username)
+    }
+}
+
+impl GithubConfig {
+    fn from_env(file: GithubSection) -> Result<Self, Error> {
+        let repos = file
+            .repos
+            .into_iter()
+            .map(|repo| RepoRoute {
+                name: repo.name,
+                channel_id: ChannelId::new(repo.channel_id),
+                branches: repo.branches,
+                events: repo.events,
+            })
+            .collect();
+
+        Ok(Self {
+            token: optional_var("GITHUB_TOKEN"),
+            default_repo: file.default_repo,
+            webhook_secret: optional_var("GITHUB_WEBHOOK_SECRET"),
+            default_channel_id: file.default_channel_id.map(ChannelId::new),
+            repos,
+        })
+    }
+
+    /// The route for a repository, if one is configured.
+    pub fn route(&self, repo: &str) -> Option<&RepoRoute> {
+        self.repos
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(repo))
+    }
+}
+
+impl RepoRoute {
+    /// Whether this event should be posted. Event names are GitHub's `X-GitHub-Event` values.
+    pub fn wants_event(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+
+    /// Whether the branch passes the filter. A trailing `*` matches any suffix.
+    pub fn wants_branch(&self, branch: &str) -> bool {
+        self.branches.is_empty()
+            || self
+                .branches
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => branch.starts_with(prefix),
+                    None => branch == pattern,
+                })
+    }
+}
+
+/// `WEBHOOK_PORT`, falling back to the `PORT` that hosts like Railway assign.
+fn parse_port() -> Result<u16, Error> {
+    for var in ["WEBHOOK_PORT", "PORT"] {
+        if let Some(port) = optional_var(var) {
+            return port
+                .trim()
+                .parse()
+                .map_err(|_| Error::Config(format!("Invalid {var}: '{port}'")));
+        }
+    }
+    Ok(8080)
+}
+
+fn optional_var(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|v| !v.is_empty())
 }
 
 fn parse_id_list<T>(var: &str) -> Result<Vec<T>, Error>
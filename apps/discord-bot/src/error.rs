@@ -8,6 +8,9 @@ pub enum Error {
 
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
+
+    #[error("Command error: {0}")]
+    Command(String),
 }
 
 impl From<serenity::Error> for Error {
@@ -17,11 +20,17 @@ impl From<serenity::Error> for Error {
 }
 
 impl Error {
+    /// For guild-only commands that somehow ran outside a server.
+    pub fn guild_only() -> Self {
+        Error::Command("This command can only be used in a server.".into())
+    }
+
     pub fn user_message(&self) -> &str {
         match self {
             Error::Discord(_) => "Failed to communicate with Discord. Please try again.",
             Error::Config(msg) => msg,
             Error::Database(_) => "A database error occurred. Please try again later.",
+            Error::Command(msg) => msg,
         }
     }
 }
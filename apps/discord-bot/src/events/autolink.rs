@@ -0,0 +1,81 @@
+use crate::integrations::github::{autolink, defaults};
+use crate::Data;
+use serenity::all::{Context, CreateAllowedMentions, CreateMessage, FullEvent, Message};
+use tracing::{error, warn};
+
+/// Expand GitHub issue and pull request references in auto-link channels.
+pub async fn handle_event(ctx: &Context, event: &FullEvent, data: &Data) {
+    if let FullEvent::Message { new_message } = event {
+        handle_message(ctx, new_message, data).await;
+    }
+}
+
+async fn handle_message(ctx: &Context, message: &Message, data: &Data) {
+    let Some(guild_id) = message.guild_id else {
+        return;
+    };
+    if message.author.bot || message.webhook_id.is_some() || !message.content.contains('#') {
+        return;
+    }
+
+    let references = autolink::find_references(&message.content);
+    if references.is_empty() {
+        return;
+    }
+
+    match data
+        .autolinker
+        .is_enabled(&data.db, message.channel_id)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            error!(error = %e, "Failed to load auto-link channels");
+            return;
+        }
+    }
+
+    // Bare `#123` references use the server's default repository.
+    let default_repo = if references.iter().any(|r| r.repo.is_none()) {
+        match defaults::default_repo(&data.db, guild_id).await {
+            Ok(repo) => repo.or_else(|| data.config.github.default_repo.clone()),
+            Err(e) => {
+                warn!(error = %e, "Failed to load default repository");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut embeds = Vec::new();
+    for reference in references {
+        let Some(repo) = reference.repo.as_ref().or(default_repo.as_ref()) else {
+            continue;
+        };
+        if !data
+            .autolinker
+            .claim(message.channel_id, repo, reference.number)
+        {
+            continue;
+        }
+        if let Some(embed) =
+            autolink::reference_embed(&data.github_api, repo, reference.number).await
+        {
+            embeds.push(embed);
+        }
+    }
+
+    if embeds.is_empty() {
+        return;
+    }
+
+    let reply = CreateMessage::new()
+        .embeds(embeds)
+        .reference_message(message)
+        .allowed_mentions(CreateAllowedMentions::new().replied_user(false));
+    if let Err(e) = message.channel_id.send_message(&ctx.http, reply).await {
+        error!(error = %e, "Failed to post GitHub reference");
+    }
+}
@@ -0,0 +1,31 @@
+use crate::Data;
+use serenity::all::{Context, FullEvent, Message};
+
+/// Relay #live-chat messages to Twitch chat while the stream is live.
+pub async fn handle_event(ctx: &Context, event: &FullEvent, data: &Data) {
+    if let FullEvent::Message { new_message } = event {
+        handle_message(ctx, new_message, data);
+    }
+}
+
+fn handle_message(ctx: &Context, message: &Message, data: &Data) {
+    let Some(ref bridge) = data.chat_bridge else {
+        return;
+    };
+
+    // Bots and webhooks (including our own Twitch relay) are never sent back.
+    if message.channel_id != bridge.channel_id()
+        || message.author.bot
+        || message.webhook_id.is_some()
+    {
+        return;
+    }
+
+    let author = message
+        .member
+        .as_ref()
+        .and_then(|m| m.nick.as_deref())
+        .unwrap_or_else(|| message.author.display_name());
+
+    bridge.relay_from_discord(author, &message.content_safe(&ctx.cache));
+}
@@ -0,0 +1,39 @@
+use crate::integrations::github::pages;
+use crate::integrations::twitch::clips;
+use crate::raid;
+use crate::tickets;
+use crate::verification;
+use crate::Data;
+use serenity::all::{Context, FullEvent, Interaction};
+
+/// Route button presses and modal submissions on bot messages to the feature that owns them.
+///
+/// Slash commands are handled by poise; this only sees message components and modals.
+pub async fn handle_event(ctx: &Context, event: &FullEvent, data: &Data) {
+    let FullEvent::InteractionCreate { interaction } = event else {
+        return;
+    };
+
+    match interaction {
+        Interaction::Component(component) => match prefix(&component.data.custom_id) {
+            clips::VOTE_PREFIX => clips::handle_vote(ctx, component, &data.db).await,
+            pages::PAGE_PREFIX => pages::handle_page(ctx, component, &data.github_api).await,
+            raid::RAID_PREFIX => raid::handle_component(ctx, component, data).await,
+            tickets::TICKET_PREFIX => tickets::handle_component(ctx, component, data).await,
+            verification::VERIFY_PREFIX | verification::CODE_PREFIX => {
+                verification::handle_component(ctx, component, data).await
+            }
+            _ => {}
+        },
+        Interaction::Modal(modal)
+            if prefix(&modal.data.custom_id) == verification::ANSWER_PREFIX =>
+        {
+            verification::handle_modal(ctx, modal, data).await
+        }
+        _ => {}
+    }
+}
+
+fn prefix(custom_id: &str) -> &str {
+    custom_id.split(':').next().unwrap_or_default()
+}
@@ -1,23 +1,31 @@
+use crate::boosts;
+use crate::members;
+use crate::persistence;
+use crate::raid;
+use crate::screening;
 use crate::utils::embeds;
+use crate::verification;
+use crate::welcome::templates::{WelcomeKind, WelcomeTemplate, WelcomeVars};
 use crate::Data;
-use rand::seq::SliceRandom;
-use serenity::all::{Context, CreateMessage, FullEvent, Member, Mentionable};
-use tracing::{error, info};
-
-const WELCOME_MESSAGES: &[&str] = &[
-    "Welcome to The Crimson Den, **{user}**!",
-    "**{user}** just joined the crew!",
-    "Glad to have you here, **{user}**!",
-    "**{user}** has entered The Crimson Den!",
-    "Welcome aboard, **{user}**!",
-];
-
-/// Handle member-related Discord events (join/leave).
+use chrono::{DateTime, Utc};
+use poise::ChoiceParameter;
+use serenity::all::{
+    Context, CreateMessage, FullEvent, GuildId, Member, Mentionable, Message, User,
+};
+use tracing::{error, info, warn};
+
+/// Handle member-related Discord events (join/leave, boosts, message counts).
 pub async fn handle_event(ctx: &Context, event: &FullEvent, data: &Data) {
     match event {
+        FullEvent::Message { new_message } => {
+            handle_message(new_message, data).await;
+        }
         FullEvent::GuildMemberAddition { new_member } => {
             handle_member_join(ctx, new_member, data).await;
         }
+        FullEvent::GuildMemberUpdate { event, .. } => {
+            boosts::handle_member_update(ctx, event, data).await;
+        }
         FullEvent::GuildMemberRemoval {
             guild_id,
             user,
@@ -40,66 +48,31 @@ async fn handle_member_join(ctx: &Context, member: &Member, data: &Data) {
     let user_name = &member.user.name;
     let display_name = member.display_name();
 
-    // 1. Auto-role assignment
-    if !data.config.autorole_ids.is_empty() {
-        for role_id in &data.config.autorole_ids {
-            if let Err(why) = member.add_role(&ctx.http, *role_id).await {
-                error!(
-                    user = %user_name,
-                    role_id = %role_id,
-                    error = %why,
-                    "Failed to assign auto-role"
-                );
+    let joined_at = member
+        .joined_at
+        .and_then(|at| DateTime::from_timestamp(at.unix_timestamp(), 0))
+        .unwrap_or_else(Utc::now);
+    let join_count =
+        match members::record_join(&data.db, member.guild_id, member.user.id, joined_at).await {
+            Ok(count) => count,
+            Err(e) => {
+                error!(error = %e, "Failed to record member join");
+                1
             }
-        }
-        info!(
-            user = %user_name,
-            roles = ?data.config.autorole_ids,
-            "Assigned auto-roles to new member"
-        );
-    }
+        };
 
-    // 2. Welcome embed in #welcome
-    if let Some(channel_id) = data.config.welcome_channel_id {
-        let welcome_msg = WELCOME_MESSAGES
-            .choose(&mut rand::thread_rng())
-            .unwrap_or(&WELCOME_MESSAGES[0])
-            .replace("{user}", &member.mention().to_string());
-
-        let member_count = member
-            .guild_id
-            .to_partial_guild(&ctx.http)
-            .await
-            .map(|g| g.approximate_member_count.unwrap_or(0))
-            .unwrap_or(0);
-
-        let embed = embeds::crimson_embed()
-            .title("Welcome to The Crimson Den!")
-            .description(welcome_msg)
-            .thumbnail(
-                member
-                    .user
-                    .avatar_url()
-                    .unwrap_or_else(|| member.user.default_avatar_url()),
-            )
-            .field("Member #", member_count.to_string(), true)
-            .field(
-                "Quick Links",
-                "Check out <#rules> and grab your roles in <#roles>!",
-                false,
-            );
-
-        let message = CreateMessage::new().embed(embed);
-        if let Err(why) = channel_id.send_message(&ctx.http, message).await {
-            error!(error = %why, "Failed to send welcome message");
-        }
+    // 1. Raid detection; members who join during a lockdown are held
+    // 2. Account screening, which may quarantine or kick
+    // 3. Verification or auto-roles, and the welcome message
+    if !raid::handle_join(ctx, member, data).await && !screening::screen(ctx, member, data).await {
+        admit(ctx, member, data).await;
     }
 
-    // 3. Log join to #mod-logs
+    // 4. Log join to #mod-logs
     if let Some(log_channel) = data.config.log_channel_id {
         let account_age = member.user.created_at();
 
-        let embed = embeds::crimson_embed()
+        let mut embed = embeds::crimson_embed()
             .title("Member Joined")
             .field(
                 "User",
@@ -117,6 +90,9 @@ async fn handle_member_join(ctx: &Context, member: &Member, data: &Data) {
                     .avatar_url()
                     .unwrap_or_else(|| member.user.default_avatar_url()),
             );
+        if join_count > 1 {
+            embed = embed.field("Rejoined", format!("Joined {join_count} times"), true);
+        }
 
         let message = CreateMessage::new().embed(embed);
         if let Err(why) = log_channel.send_message(&ctx.http, message).await {
@@ -127,15 +103,64 @@ async fn handle_member_join(ctx: &Context, member: &Member, data: &Data) {
     info!(user = %user_name, display_name = %display_name, "Member joined");
 }
 
+/// Let a new member in: give a returning member their old roles back, or
+/// start verification or assign auto-roles, then post the welcome message.
+///
+/// Also used for members held during a raid lockdown once it ends.
+pub async fn admit(ctx: &Context, member: &Member, data: &Data) {
+    let user_name = &member.user.name;
+
+    // Returning members who already got in once skip verification
+    let restored = persistence::restore(ctx, member, data).await;
+    if !restored
+        && !verification::start(ctx, member, data).await
+        && !data.config.autorole_ids.is_empty()
+    {
+        for role_id in &data.config.autorole_ids {
+            if let Err(why) = member.add_role(&ctx.http, *role_id).await {
+                error!(
+                    user = %user_name,
+                    role_id = %role_id,
+                    error = %why,
+                    "Failed to assign auto-role"
+                );
+            }
+        }
+        info!(
+            user = %user_name,
+            roles = ?data.config.autorole_ids,
+            "Assigned auto-roles to new member"
+        );
+    }
+
+    send_template(
+        ctx,
+        member.guild_id,
+        &member.user,
+        WelcomeKind::Welcome,
+        data,
+    )
+    .await;
+}
+
 async fn handle_member_leave(
     ctx: &Context,
-    guild_id: serenity::all::GuildId,
-    user: &serenity::all::User,
+    guild_id: GuildId,
+    user: &User,
     member: Option<&Member>,
     data: &Data,
 ) {
     let user_name = &user.name;
 
+    if let Err(e) = members::record_leave(&data.db, guild_id, user.id).await {
+        error!(error = %e, "Failed to record member leave");
+    }
+
+    verification::handle_leave(ctx, guild_id, user, data).await;
+    persistence::handle_leave(data, guild_id, user.id, member).await;
+    boosts::handle_leave(ctx, guild_id, user, data).await;
+    send_template(ctx, guild_id, user, WelcomeKind::Leave, data).await;
+
     if let Some(log_channel) = data.config.log_channel_id {
         let mut embed = embeds::warning_embed().title("Member Left").field(
             "User",
@@ -172,3 +197,57 @@ async fn handle_member_leave(
 
     info!(user = %user_name, guild_id = %guild_id, "Member left");
 }
+
+async fn handle_message(message: &Message, data: &Data) {
+    let Some(guild_id) = message.guild_id else {
+        return;
+    };
+    if message.author.bot || message.webhook_id.is_some() {
+        return;
+    }
+
+    if let Err(e) = members::count_message(&data.db, guild_id, message.author.id).await {
+        error!(error = %e, "Failed to count message");
+    }
+}
+
+/// Post the guild's welcome or leave message, if it's enabled and has a channel.
+async fn send_template(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    kind: WelcomeKind,
+    data: &Data,
+) {
+    let template = match WelcomeTemplate::load(&data.db, guild_id, kind).await {
+        Ok(template) => template,
+        Err(e) => {
+            error!(error = %e, kind = kind.name(), "Failed to load welcome template");
+            return;
+        }
+    };
+    if !template.enabled {
+        return;
+    }
+    let Some(channel_id) = template.channel(data.config.welcome_channel_id) else {
+        return;
+    };
+
+    let vars = WelcomeVars::new(ctx, guild_id, user).await;
+    let card = match data.welcome_cards {
+        Some(ref cards) if template.card && kind == WelcomeKind::Welcome => {
+            match cards.attachment(user, &vars).await {
+                Ok(card) => Some(card),
+                Err(e) => {
+                    warn!(error = %e, "Failed to render welcome card, sending the message without it");
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    let message = template.render(kind, &vars, card).into_message();
+    if let Err(why) = channel_id.send_message(&ctx.http, message).await {
+        error!(error = %why, kind = kind.name(), "Failed to send welcome message");
+    }
+}
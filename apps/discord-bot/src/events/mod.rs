@@ -1 +1,6 @@
+pub mod autolink;
+pub mod chat_bridge;
+pub mod interactions;
 pub mod member;
+pub mod presence;
+pub mod tickets;
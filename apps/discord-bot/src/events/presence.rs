@@ -0,0 +1,129 @@
+use crate::Data;
+use futures_util::StreamExt;
+use serenity::all::{
+    ActivityType, Context, FullEvent, Guild, GuildId, Member, Presence, RoleId, UserId,
+};
+use std::collections::HashSet;
+use tracing::{error, info, warn};
+
+/// Grant the streaming role while members are live on Twitch, based on their
+/// Discord presence.
+pub async fn handle_event(ctx: &Context, event: &FullEvent, data: &Data) {
+    let Some(role_id) = data.config.streaming_role_id else {
+        return;
+    };
+
+    match event {
+        FullEvent::PresenceUpdate { new_data } => {
+            if let Some(guild_id) = new_data.guild_id {
+                handle_presence(ctx, guild_id, new_data, role_id, data).await;
+            }
+        }
+        FullEvent::GuildCreate { guild, .. } => {
+            sweep_guild(ctx, guild, role_id, data).await;
+        }
+        _ => {}
+    }
+}
+
+/// Whether the presence shows a Twitch stream.
+fn is_streaming_on_twitch(presence: &Presence) -> bool {
+    presence.activities.iter().any(|activity| {
+        activity.kind == ActivityType::Streaming
+            && activity
+                .url
+                .as_ref()
+                .and_then(|url| url.host_str())
+                .is_some_and(|host| host == "twitch.tv" || host.ends_with(".twitch.tv"))
+    })
+}
+
+/// Only content creators get the role when a creator role is configured.
+fn is_eligible(member: &Member, data: &Data) -> bool {
+    data.config
+        .content_creator_role_id
+        .is_none_or(|creator| member.roles.contains(&creator))
+}
+
+async fn handle_presence(
+    ctx: &Context,
+    guild_id: GuildId,
+    presence: &Presence,
+    role_id: RoleId,
+    data: &Data,
+) {
+    let user_id = presence.user.id;
+    let cached = ctx
+        .cache
+        .guild(guild_id)
+        .and_then(|g| g.members.get(&user_id).cloned());
+    let member = match cached {
+        Some(member) => member,
+        None => match guild_id.member(&ctx.http, user_id).await {
+            Ok(member) => member,
+            Err(e) => {
+                warn!(user_id = %user_id, error = %e, "Failed to fetch member for presence update");
+                return;
+            }
+        },
+    };
+
+    let live = is_streaming_on_twitch(presence) && is_eligible(&member, data);
+    set_streaming_role(ctx, &member, role_id, live).await;
+}
+
+/// Add or remove the role if it doesn't already match.
+async fn set_streaming_role(ctx: &Context, member: &Member, role_id: RoleId, live: bool) {
+    let has_role = member.roles.contains(&role_id);
+    if has_role == live {
+        return;
+    }
+
+    let result = if live {
+        member.add_role(&ctx.http, role_id).await
+    } else {
+        member.remove_role(&ctx.http, role_id).await
+    };
+
+    match result {
+        Ok(()) => info!(user = %member.user.name, live, "Streaming role updated"),
+        Err(e) => error!(user = %member.user.name, error = %e, "Failed to update streaming role"),
+    }
+}
+
+/// Fix streaming roles that went stale while the bot was offline.
+///
+/// Runs whenever a guild becomes available, using the presences it arrives with.
+async fn sweep_guild(ctx: &Context, guild: &Guild, role_id: RoleId, data: &Data) {
+    let streaming: HashSet<UserId> = guild
+        .presences
+        .values()
+        .filter(|p| is_streaming_on_twitch(p))
+        .map(|p| p.user.id)
+        .collect();
+
+    let mut members = guild.id.members_iter(&ctx.http).boxed();
+    let (mut added, mut removed) = (0, 0);
+
+    while let Some(member) = members.next().await {
+        let member = match member {
+            Ok(member) => member,
+            Err(e) => {
+                error!(error = %e, "Failed to list members for streaming role sweep");
+                return;
+            }
+        };
+
+        let live = streaming.contains(&member.user.id) && is_eligible(&member, data);
+        if live != member.roles.contains(&role_id) {
+            set_streaming_role(ctx, &member, role_id, live).await;
+            if live {
+                added += 1;
+            } else {
+                removed += 1;
+            }
+        }
+    }
+
+    info!(guild = %guild.name, added, removed, "Streaming role sweep complete");
+}
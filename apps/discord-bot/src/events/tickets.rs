@@ -0,0 +1,23 @@
+use crate::tickets;
+use crate::Data;
+use serenity::all::{Context, FullEvent};
+use tracing::error;
+
+/// Keep ticket activity up to date for the inactivity auto-close.
+pub async fn handle_event(_ctx: &Context, event: &FullEvent, data: &Data) {
+    match event {
+        FullEvent::Message { new_message }
+            if new_message.guild_id.is_some() && !new_message.author.bot =>
+        {
+            if let Err(e) = tickets::touch(&data.db, new_message.channel_id).await {
+                error!(error = %e, "Failed to update ticket activity");
+            }
+        }
+        FullEvent::ThreadDelete { thread, .. } => {
+            if let Err(e) = tickets::handle_thread_delete(&data.db, thread.id).await {
+                error!(error = %e, "Failed to close ticket for deleted thread");
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,98 @@
+use crate::integrations::twitch::TwitchApi;
+use crate::metrics;
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serenity::gateway::{ConnectionStage, ShardManager};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// How long `/readyz` waits on the database.
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What `/readyz` checks.
+#[derive(Clone)]
+pub struct HttpState {
+    pub db: PgPool,
+    pub shard_manager: Arc<ShardManager>,
+    /// Only set when the Twitch integration is configured.
+    pub twitch_api: Option<TwitchApi>,
+}
+
+/// Serve health checks, metrics and any extra routes (such as the GitHub webhook).
+pub async fn serve(port: u16, state: HttpState, extra: Router) {
+    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics_handler))
+        .with_state(state)
+        .merge(extra);
+
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(%addr, error = %e, "Failed to bind HTTP server");
+            return;
+        }
+    };
+
+    info!(%addr, "HTTP server listening");
+    if let Err(e) = axum::serve(listener, app).await {
+        error!(error = %e, "HTTP server stopped");
+    }
+}
+
+/// Liveness: the process is up and serving requests.
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Readiness: the gateway is connected, the database answers and, when
+/// Twitch is configured, the EventSub session is alive.
+async fn readyz(State(state): State<HttpState>) -> impl IntoResponse {
+    let gateway = {
+        let runners = state.shard_manager.runners.lock().await;
+        !runners.is_empty()
+            && runners
+                .values()
+                .all(|runner| runner.stage == ConnectionStage::Connected)
+    };
+
+    let database = matches!(
+        tokio::time::timeout(DB_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(&state.db)).await,
+        Ok(Ok(_))
+    );
+
+    let eventsub = state
+        .twitch_api
+        .as_ref()
+        .map(TwitchApi::is_eventsub_connected);
+
+    let ready = gateway && database && eventsub.unwrap_or(true);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let body = serde_json::json!({
+        "ready": ready,
+        "gateway": gateway,
+        "database": database,
+        "eventsub": eventsub,
+    });
+    (status, Json(body))
+}
+
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
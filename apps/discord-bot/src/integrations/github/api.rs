@@ -0,0 +1,378 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::warn;
+
+const API_URL: &str = "https://api.github.com";
+
+/// Responses kept for conditional requests before the least recently used is dropped.
+const MAX_CACHED: usize = 256;
+
+/// Items per page for `/issues` and `/commits`.
+pub const PAGE_SIZE: u32 = 8;
+
+/// A page of results and whether GitHub has another one.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub has_next: bool,
+}
+
+struct CachedResponse {
+    etag: String,
+    body: Vec<u8>,
+    has_next: bool,
+    used: Instant,
+}
+
+/// GitHub REST client shared by the GitHub commands.
+///
+/// Responses are cached with their ETag and revalidated with `If-None-Match`;
+/// a `304 Not Modified` doesn't count against the rate limit.
+#[derive(Clone)]
+pub struct GithubApi {
+    client: reqwest::Client,
+    cache: Arc<Mutex<HashMap<String, CachedResponse>>>,
+}
+
+impl GithubApi {
+    pub fn new(token: Option<&str>) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        headers.insert(
+            "X-GitHub-Api-Version",
+            HeaderValue::from_static("2022-11-28"),
+        );
+        if let Some(token) = token {
+            match HeaderValue::from_str(&format!("Bearer {token}")) {
+                Ok(mut value) => {
+                    value.set_sensitive(true);
+                    headers.insert(header::AUTHORIZATION, value);
+                }
+                Err(_) => warn!("GITHUB_TOKEN contains invalid characters, ignoring it"),
+            }
+        }
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("crimsonx-discord-bot/", env!("CARGO_PKG_VERSION")))
+            .default_headers(headers)
+            .build()
+            .expect("Failed to build GitHub HTTP client");
+
+        Self {
+            client,
+            cache: Arc::default(),
+        }
+    }
+
+    pub async fn repository(&self, repo: &str) -> Result<Repository, String> {
+        self.get(api_url(&format!("/repos/{repo}"), &[]))
+            .await
+            .map(|(r, _)| r)
+    }
+
+    /// Open issues, newest first. Pull requests are left out, so pages can run short.
+    pub async fn open_issues(&self, repo: &str, page: u32) -> Result<Page<Issue>, String> {
+        let url = api_url(
+            &format!("/repos/{repo}/issues"),
+            &[
+                ("state", "open"),
+                ("per_page", &PAGE_SIZE.to_string()),
+                ("page", &page.to_string()),
+            ],
+        );
+        let (issues, has_next): (Vec<Issue>, bool) = self.get(url).await?;
+        Ok(Page {
+            items: issues
+                .into_iter()
+                .filter(|i| i.pull_request.is_none())
+                .collect(),
+            has_next,
+        })
+    }
+
+    pub async fn commits(
+        &self,
+        repo: &str,
+        branch: Option<&str>,
+        page: u32,
+    ) -> Result<Page<CommitSummary>, String> {
+        let (per_page, page) = (PAGE_SIZE.to_string(), page.to_string());
+        let mut query = vec![("per_page", per_page.as_str()), ("page", page.as_str())];
+        if let Some(branch) = branch {
+            query.push(("sha", branch));
+        }
+        let url = api_url(&format!("/repos/{repo}/commits"), &query);
+        let (items, has_next) = self.get(url).await?;
+        Ok(Page { items, has_next })
+    }
+
+    /// An issue or pull request by number; both share GitHub's issue numbering.
+    pub async fn issue(&self, repo: &str, number: u64) -> Result<Issue, String> {
+        self.get(api_url(&format!("/repos/{repo}/issues/{number}"), &[]))
+            .await
+            .map(|(issue, _)| issue)
+    }
+
+    pub async fn pull_request(&self, repo: &str, number: u64) -> Result<PullRequest, String> {
+        self.get(api_url(&format!("/repos/{repo}/pulls/{number}"), &[]))
+            .await
+            .map(|(pr, _)| pr)
+    }
+
+    /// GET a URL, revalidating any cached copy. Also reports whether a next page exists.
+    async fn get<T: DeserializeOwned>(&self, url: Url) -> Result<(T, bool), String> {
+        let path = url.as_str();
+        let etag = self.cached(path, |c| c.etag.clone());
+
+        let mut request = self.client.get(url.clone());
+        if let Some(ref etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await.map_err(|e| {
+            warn!(path, error = %e, "GitHub request failed");
+            "Couldn't reach GitHub. Please try again.".to_string()
+        })?;
+
+        let status = response.status();
+        let (body, has_next) = if status == StatusCode::NOT_MODIFIED {
+            self.cached(path, |c| (c.body.clone(), c.has_next))
+                .ok_or_else(|| "GitHub returned a stale response. Please try again.".to_string())?
+        } else if status.is_success() {
+            let new_etag = header_str(response.headers(), header::ETAG);
+            let has_next = header_str(response.headers(), header::LINK)
+                .is_some_and(|link| link.contains("rel=\"next\""));
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| {
+                    warn!(path, error = %e, "Failed to read GitHub response");
+                    "Couldn't read GitHub's response. Please try again.".to_string()
+                })?
+                .to_vec();
+            if let Some(etag) = new_etag {
+                self.store(path, etag, body.clone(), has_next);
+            }
+            (body, has_next)
+        } else {
+            return Err(error_message(status, response.headers()));
+        };
+
+        let value = serde_json::from_slice(&body).map_err(|e| {
+            warn!(path, error = %e, "Unexpected GitHub response");
+            "GitHub sent something unexpected. Please try again.".to_string()
+        })?;
+        Ok((value, has_next))
+    }
+
+    fn cached<R>(&self, path: &str, f: impl FnOnce(&CachedResponse) -> R) -> Option<R> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get_mut(path).map(|entry| {
+            entry.used = Instant::now();
+            f(entry)
+        })
+    }
+
+    fn store(&self, path: &str, etag: String, body: Vec<u8>, has_next: bool) {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() >= MAX_CACHED && !cache.contains_key(path) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(
+            path.to_string(),
+            CachedResponse {
+                etag,
+                body,
+                has_next,
+                used: Instant::now(),
+            },
+        );
+    }
+}
+
+fn api_url(path: &str, query: &[(&str, &str)]) -> Url {
+    let mut url = Url::parse(API_URL)
+        .and_then(|base| base.join(path))
+        .expect("GitHub API paths are valid URLs");
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    url
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// A message for the user explaining why GitHub refused the request.
+fn error_message(status: StatusCode, headers: &HeaderMap) -> String {
+    let rate_limited = headers
+        .get("x-ratelimit-remaining")
+        .and_then(|v| v.to_str().ok())
+        == Some("0");
+
+    match status {
+        StatusCode::NOT_FOUND => "GitHub couldn't find that. Check the repository name and \
+                                  that it's public."
+            .into(),
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS if rate_limited => {
+            let reset = headers
+                .get("x-ratelimit-reset")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<i64>().ok())
+                .map(|ts| format!(" Try again <t:{ts}:R>."))
+                .unwrap_or_default();
+            format!("The GitHub API rate limit has been reached.{reset}")
+        }
+        StatusCode::UNAUTHORIZED => {
+            warn!("GitHub rejected GITHUB_TOKEN");
+            "The bot's GitHub token was rejected.".into()
+        }
+        _ => {
+            warn!(%status, "GitHub request failed");
+            format!("GitHub returned an error ({status}).")
+        }
+    }
+}
+
+/// Whether `repo` looks like `owner/name`.
+pub fn is_valid_repo(repo: &str) -> bool {
+    let mut parts = repo.split('/');
+    let valid_part = |part: Option<&str>| {
+        part.is_some_and(|p| {
+            !p.is_empty()
+                && p != "."
+                && p != ".."
+                && p.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        })
+    };
+    valid_part(parts.next()) && valid_part(parts.next()) && parts.next().is_none()
+}
+
+// ─── Response Types ──────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct Owner {
+    pub login: String,
+    pub avatar_url: String,
+    pub html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct License {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Repository {
+    pub full_name: String,
+    pub description: Option<String>,
+    pub html_url: String,
+    pub stargazers_count: u64,
+    pub forks_count: u64,
+    /// Includes open pull requests.
+    pub open_issues_count: u64,
+    pub subscribers_count: Option<u64>,
+    pub language: Option<String>,
+    pub default_branch: String,
+    pub pushed_at: Option<DateTime<Utc>>,
+    pub license: Option<License>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub owner: Owner,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub state: String,
+    pub state_reason: Option<String>,
+    pub user: Owner,
+    pub comments: u64,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub draft: bool,
+    /// Present when the "issue" is actually a pull request.
+    pub pull_request: Option<IssuePullRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IssuePullRequest {
+    pub merged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitSummary {
+    pub sha: String,
+    pub html_url: String,
+    pub commit: CommitDetail,
+    pub author: Option<Owner>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitDetail {
+    pub message: String,
+    pub author: Option<CommitSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitSignature {
+    pub name: String,
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BranchRef {
+    #[serde(rename = "ref")]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub state: String,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub merged: bool,
+    pub user: Owner,
+    pub base: BranchRef,
+    pub head: BranchRef,
+    pub created_at: DateTime<Utc>,
+    pub merged_at: Option<DateTime<Utc>>,
+    pub commits: u64,
+    pub additions: u64,
+    pub deletions: u64,
+    pub changed_files: u64,
+    pub comments: u64,
+    pub review_comments: u64,
+}
@@ -0,0 +1,233 @@
+use super::api::{GithubApi, Issue};
+use crate::utils::embeds::{self, Colors};
+use serenity::all::{ChannelId, CreateEmbed, CreateEmbedAuthor, GuildId};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// A reference isn't expanded again in the same channel within this window.
+const DEDUPE_WINDOW: Duration = Duration::from_secs(300);
+
+/// Most references expanded from a single message.
+const MAX_REFERENCES: usize = 3;
+
+/// Channel, lowercased repository and number of an expanded reference.
+type RecentKey = (ChannelId, String, u64);
+
+/// An issue or pull request mentioned in chat.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reference {
+    /// `owner/name`, or `None` for a bare `#123` that uses the default repository.
+    pub repo: Option<String>,
+    pub number: u64,
+}
+
+/// Which channels expand references, and which references were expanded recently.
+///
+/// Enabled channels are loaded from the database on first use and kept in
+/// memory, since every message in the guild is checked against them.
+#[derive(Clone, Default)]
+pub struct AutoLinker {
+    channels: Arc<OnceCell<Mutex<HashSet<ChannelId>>>>,
+    recent: Arc<Mutex<HashMap<RecentKey, Instant>>>,
+}
+
+impl AutoLinker {
+    pub async fn is_enabled(
+        &self,
+        db: &PgPool,
+        channel_id: ChannelId,
+    ) -> Result<bool, sqlx::Error> {
+        let channels = self.channels(db).await?;
+        let channels = channels.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(channels.contains(&channel_id))
+    }
+
+    /// Turn auto-linking on or off for a channel. Returns whether anything changed.
+    pub async fn set_enabled(
+        &self,
+        db: &PgPool,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        enabled: bool,
+    ) -> Result<bool, sqlx::Error> {
+        let changed = if enabled {
+            sqlx::query(
+                "INSERT INTO github_autolink_channels (channel_id, guild_id) VALUES ($1, $2) \
+                 ON CONFLICT (channel_id) DO NOTHING",
+            )
+            .bind(channel_id.get() as i64)
+            .bind(guild_id.get() as i64)
+            .execute(db)
+            .await?
+        } else {
+            sqlx::query("DELETE FROM github_autolink_channels WHERE channel_id = $1")
+                .bind(channel_id.get() as i64)
+                .execute(db)
+                .await?
+        }
+        .rows_affected()
+            > 0;
+
+        let channels = self.channels(db).await?;
+        let mut channels = channels.lock().unwrap_or_else(|e| e.into_inner());
+        if enabled {
+            channels.insert(channel_id);
+        } else {
+            channels.remove(&channel_id);
+        }
+        Ok(changed)
+    }
+
+    /// Channels in a guild with auto-linking on.
+    pub async fn guild_channels(
+        &self,
+        db: &PgPool,
+        guild_id: GuildId,
+    ) -> Result<Vec<ChannelId>, sqlx::Error> {
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT channel_id FROM github_autolink_channels WHERE guild_id = $1 ORDER BY channel_id",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_all(db)
+        .await?;
+
+        Ok(ids
+            .into_iter()
+            .map(|id| ChannelId::new(id as u64))
+            .collect())
+    }
+
+    /// Record that a reference is being expanded, unless it already was within the window.
+    pub fn claim(&self, channel_id: ChannelId, repo: &str, number: u64) -> bool {
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.retain(|_, at| now.duration_since(*at) < DEDUPE_WINDOW);
+
+        let key = (channel_id, repo.to_ascii_lowercase(), number);
+        if recent.contains_key(&key) {
+            return false;
+        }
+        recent.insert(key, now);
+        true
+    }
+
+    async fn channels(&self, db: &PgPool) -> Result<&Mutex<HashSet<ChannelId>>, sqlx::Error> {
+        self.channels
+            .get_or_try_init(|| async {
+                let ids: Vec<i64> =
+                    sqlx::query_scalar("SELECT channel_id FROM github_autolink_channels")
+                        .fetch_all(db)
+                        .await?;
+                Ok(Mutex::new(
+                    ids.into_iter()
+                        .map(|id| ChannelId::new(id as u64))
+                        .collect(),
+                ))
+            })
+            .await
+    }
+}
+
+/// Find `#123` and `owner/repo#45` references, skipping code spans and
+/// anything glued to other text (URL fragments, `<#channel>` mentions).
+pub fn find_references(content: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+
+    for (i, segment) in content.split('`').enumerate() {
+        // Odd segments sit between backticks.
+        if i % 2 == 1 {
+            continue;
+        }
+
+        for word in segment.split(|c: char| c.is_whitespace() || ",;()[]!?".contains(c)) {
+            let word = word.trim_end_matches(['.', ':']);
+            let Some((prefix, number)) = word.split_once('#') else {
+                continue;
+            };
+            let Ok(number) = number.parse::<u64>() else {
+                continue;
+            };
+            if number == 0 {
+                continue;
+            }
+
+            let repo = if prefix.is_empty() {
+                None
+            } else if super::api::is_valid_repo(prefix) {
+                Some(prefix.to_string())
+            } else {
+                continue;
+            };
+
+            let reference = Reference { repo, number };
+            if !references.contains(&reference) {
+                references.push(reference);
+            }
+            if references.len() == MAX_REFERENCES {
+                return references;
+            }
+        }
+    }
+
+    references
+}
+
+/// Fetch a referenced issue or pull request and build its compact embed.
+pub async fn reference_embed(api: &GithubApi, repo: &str, number: u64) -> Option<CreateEmbed> {
+    match api.issue(repo, number).await {
+        Ok(issue) => Some(issue_embed(repo, &issue)),
+        Err(e) => {
+            tracing::debug!(repo, number, error = %e, "Skipping unresolvable GitHub reference");
+            None
+        }
+    }
+}
+
+fn issue_embed(repo: &str, issue: &Issue) -> CreateEmbed {
+    let (state, color) = match (&issue.pull_request, issue.state.as_str()) {
+        (Some(pr), "closed") if pr.merged_at.is_some() => ("🟣 Merged", Colors::GITHUB),
+        (Some(_), "closed") => ("🔴 Closed", Colors::ERROR),
+        (Some(_), _) if issue.draft => ("⚪ Draft", Colors::GITHUB),
+        (None, "closed") if issue.state_reason.as_deref() == Some("not_planned") => {
+            ("⚪ Not planned", Colors::ERROR)
+        }
+        (None, "closed") => ("🟣 Closed", Colors::GITHUB),
+        _ => ("🟢 Open", Colors::SUCCESS),
+    };
+    let kind = if issue.pull_request.is_some() {
+        "Pull request"
+    } else {
+        "Issue"
+    };
+
+    let mut title = format!("{repo}#{} {}", issue.number, issue.title);
+    if title.chars().count() > 256 {
+        title = title.chars().take(255).collect::<String>() + "…";
+    }
+
+    let mut embed = embeds::github_embed()
+        .color(color)
+        .author(
+            CreateEmbedAuthor::new(&issue.user.login)
+                .url(&issue.user.html_url)
+                .icon_url(&issue.user.avatar_url),
+        )
+        .title(title)
+        .url(&issue.html_url)
+        .field(kind, state, true);
+
+    if !issue.labels.is_empty() {
+        let labels: Vec<String> = issue
+            .labels
+            .iter()
+            .take(10)
+            .map(|l| format!("`{}`", l.name))
+            .collect();
+        embed = embed.field("Labels", labels.join(" "), true);
+    }
+
+    embed
+}
@@ -0,0 +1,31 @@
+use serenity::all::GuildId;
+use sqlx::PgPool;
+
+/// The repository a guild's GitHub commands use when none is given.
+pub async fn default_repo(db: &PgPool, guild_id: GuildId) -> Result<Option<String>, sqlx::Error> {
+    let repo: Option<Option<String>> =
+        sqlx::query_scalar("SELECT github_repo FROM guild_config WHERE guild_id = $1")
+            .bind(guild_id.get() as i64)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(repo.flatten())
+}
+
+/// Set or clear a guild's default repository.
+pub async fn set_default_repo(
+    db: &PgPool,
+    guild_id: GuildId,
+    repo: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO guild_config (guild_id, github_repo) VALUES ($1, $2) \
+         ON CONFLICT (guild_id) DO UPDATE SET github_repo = EXCLUDED.github_repo",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(repo)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
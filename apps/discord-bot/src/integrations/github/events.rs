@@ -0,0 +1,408 @@
+use crate::utils::embeds::{self, Colors};
+use serde::Deserialize;
+use serenity::all::{CreateEmbed, CreateEmbedAuthor};
+
+/// Commits listed in a push embed before the rest are summarised.
+const MAX_PUSH_COMMITS: usize = 5;
+
+/// Discord's limit on embed titles.
+const MAX_TITLE_CHARS: usize = 256;
+
+/// Longest body excerpt shown for issues, pull requests and releases.
+const MAX_BODY_CHARS: usize = 300;
+
+/// A webhook event ready to be posted.
+pub struct Notification {
+    /// `owner/name` of the repository the event came from.
+    pub repo: String,
+    /// Branch the event applies to, for branch filters.
+    pub branch: Option<String>,
+    pub embed: CreateEmbed,
+}
+
+// ─── Payloads ────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    full_name: String,
+    html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    login: String,
+    html_url: String,
+    avatar_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushEvent {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    compare: String,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    forced: bool,
+    commits: Vec<Commit>,
+    repository: Repository,
+    sender: User,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    id: String,
+    message: String,
+    url: String,
+    author: CommitAuthor,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitAuthor {
+    name: String,
+    username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequestEvent {
+    action: String,
+    pull_request: PullRequest,
+    repository: Repository,
+    sender: User,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    number: u64,
+    title: String,
+    html_url: String,
+    body: Option<String>,
+    #[serde(default)]
+    merged: bool,
+    #[serde(default)]
+    draft: bool,
+    base: Branch,
+    head: Branch,
+}
+
+#[derive(Debug, Deserialize)]
+struct Branch {
+    #[serde(rename = "ref")]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssuesEvent {
+    action: String,
+    issue: Issue,
+    repository: Repository,
+    sender: User,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    html_url: String,
+    body: Option<String>,
+    #[serde(default)]
+    labels: Vec<Label>,
+    state_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseEvent {
+    action: String,
+    release: Release,
+    repository: Repository,
+    sender: User,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    name: Option<String>,
+    html_url: String,
+    body: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRunEvent {
+    action: String,
+    workflow_run: WorkflowRun,
+    repository: Repository,
+    sender: User,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRun {
+    name: Option<String>,
+    run_number: u64,
+    html_url: String,
+    head_branch: Option<String>,
+    head_sha: String,
+    event: String,
+    conclusion: Option<String>,
+}
+
+// ─── Embeds ──────────────────────────────────────────────────────────
+
+/// Turn a webhook delivery into a notification.
+///
+/// Returns `Ok(None)` for events and actions that aren't posted.
+pub fn parse(event: &str, body: &[u8]) -> Result<Option<Notification>, serde_json::Error> {
+    let notification = match event {
+        "push" => push(serde_json::from_slice(body)?),
+        "pull_request" => pull_request(serde_json::from_slice(body)?),
+        "issues" => issues(serde_json::from_slice(body)?),
+        "release" => release(serde_json::from_slice(body)?),
+        "workflow_run" => workflow_run(serde_json::from_slice(body)?),
+        _ => None,
+    };
+    Ok(notification)
+}
+
+fn push(event: PushEvent) -> Option<Notification> {
+    // Tags are announced through releases, and deleted branches have no commits.
+    let branch = event.git_ref.strip_prefix("refs/heads/")?;
+    if event.deleted || event.commits.is_empty() {
+        return None;
+    }
+
+    let count = event.commits.len();
+    let mut lines: Vec<String> = event
+        .commits
+        .iter()
+        .take(MAX_PUSH_COMMITS)
+        .map(|c| {
+            let author = c.author.username.as_deref().unwrap_or(&c.author.name);
+            format!(
+                "[`{}`]({}) {} — {author}",
+                short_sha(&c.id),
+                c.url,
+                first_line(&c.message)
+            )
+        })
+        .collect();
+    if count > MAX_PUSH_COMMITS {
+        lines.push(format!("…and {} more", count - MAX_PUSH_COMMITS));
+    }
+
+    let plural = if count == 1 { "" } else { "s" };
+    let forced = if event.forced { " (force-pushed)" } else { "" };
+    let embed = base(&event.sender)
+        .title(title(format!(
+            "[{}:{branch}] {count} new commit{plural}{forced}",
+            event.repository.full_name
+        )))
+        .url(event.compare)
+        .description(lines.join("\n"));
+
+    Some(Notification {
+        repo: event.repository.full_name,
+        branch: Some(branch.to_string()),
+        embed,
+    })
+}
+
+fn pull_request(event: PullRequestEvent) -> Option<Notification> {
+    let pr = event.pull_request;
+    let (verb, color) = match event.action.as_str() {
+        "opened" if pr.draft => ("Draft pull request opened", Colors::GITHUB),
+        "opened" => ("Pull request opened", Colors::GITHUB),
+        "reopened" => ("Pull request reopened", Colors::GITHUB),
+        "ready_for_review" => ("Pull request ready for review", Colors::GITHUB),
+        "closed" if pr.merged => ("Pull request merged", Colors::GITHUB),
+        "closed" => ("Pull request closed", Colors::ERROR),
+        _ => return None,
+    };
+
+    let mut embed = base(&event.sender)
+        .color(color)
+        .title(title(format!(
+            "[{}] {verb}: #{} {}",
+            event.repository.full_name, pr.number, pr.title
+        )))
+        .url(&pr.html_url)
+        .field(
+            "Branches",
+            format!("`{}` ← `{}`", pr.base.name, pr.head.name),
+            true,
+        );
+    if event.action == "opened" {
+        if let Some(body) = excerpt(pr.body.as_deref()) {
+            embed = embed.description(body);
+        }
+    }
+
+    Some(Notification {
+        repo: event.repository.full_name,
+        branch: Some(pr.base.name),
+        embed,
+    })
+}
+
+fn issues(event: IssuesEvent) -> Option<Notification> {
+    let issue = event.issue;
+    let (verb, color) = match event.action.as_str() {
+        "opened" => ("Issue opened", Colors::GITHUB),
+        "reopened" => ("Issue reopened", Colors::GITHUB),
+        "closed" if issue.state_reason.as_deref() == Some("not_planned") => {
+            ("Issue closed as not planned", Colors::ERROR)
+        }
+        "closed" => ("Issue closed", Colors::GITHUB),
+        _ => return None,
+    };
+
+    let mut embed = base(&event.sender)
+        .color(color)
+        .title(title(format!(
+            "[{}] {verb}: #{} {}",
+            event.repository.full_name, issue.number, issue.title
+        )))
+        .url(&issue.html_url);
+    if event.action == "opened" {
+        if let Some(body) = excerpt(issue.body.as_deref()) {
+            embed = embed.description(body);
+        }
+    }
+    if !issue.labels.is_empty() {
+        let labels: Vec<String> = issue
+            .labels
+            .iter()
+            .map(|l| format!("`{}`", l.name))
+            .collect();
+        embed = embed.field("Labels", labels.join(" "), true);
+    }
+
+    Some(Notification {
+        repo: event.repository.full_name,
+        branch: None,
+        embed,
+    })
+}
+
+fn release(event: ReleaseEvent) -> Option<Notification> {
+    if event.action != "published" {
+        return None;
+    }
+
+    let release = event.release;
+    let name = release
+        .name
+        .as_deref()
+        .filter(|n| !n.is_empty())
+        .unwrap_or(&release.tag_name);
+    let kind = if release.prerelease {
+        "Pre-release"
+    } else {
+        "Release"
+    };
+
+    let mut embed = base(&event.sender)
+        .title(title(format!(
+            "[{}] {kind} published: {name}",
+            event.repository.full_name
+        )))
+        .url(&release.html_url)
+        .field("Tag", format!("`{}`", release.tag_name), true);
+    if let Some(body) = excerpt(release.body.as_deref()) {
+        embed = embed.description(body);
+    }
+
+    Some(Notification {
+        repo: event.repository.full_name,
+        branch: None,
+        embed,
+    })
+}
+
+fn workflow_run(event: WorkflowRunEvent) -> Option<Notification> {
+    if event.action != "completed" {
+        return None;
+    }
+
+    let run = event.workflow_run;
+    let (outcome, color) = match run.conclusion.as_deref() {
+        Some("success") => ("succeeded", Colors::SUCCESS),
+        Some("failure") => ("failed", Colors::ERROR),
+        Some("timed_out") => ("timed out", Colors::ERROR),
+        Some("cancelled") => ("was cancelled", Colors::WARNING),
+        // Skipped and neutral runs aren't worth a message.
+        _ => return None,
+    };
+
+    let name = run.name.as_deref().unwrap_or("Workflow");
+    let mut embed = base(&event.sender)
+        .color(color)
+        .title(title(format!(
+            "[{}] {name} #{} {outcome}",
+            event.repository.full_name, run.run_number
+        )))
+        .url(&run.html_url)
+        .field(
+            "Commit",
+            format!(
+                "[`{}`]({}/commit/{})",
+                short_sha(&run.head_sha),
+                event.repository.html_url,
+                run.head_sha
+            ),
+            true,
+        )
+        .field("Trigger", format!("`{}`", run.event), true);
+    if let Some(ref branch) = run.head_branch {
+        embed = embed.field("Branch", format!("`{branch}`"), true);
+    }
+
+    Some(Notification {
+        repo: event.repository.full_name,
+        branch: run.head_branch,
+        embed,
+    })
+}
+
+fn base(sender: &User) -> CreateEmbed {
+    embeds::github_embed().author(
+        CreateEmbedAuthor::new(&sender.login)
+            .url(&sender.html_url)
+            .icon_url(&sender.avatar_url),
+    )
+}
+
+/// Shorten a title that would overflow the embed limit.
+fn title(text: String) -> String {
+    if text.chars().count() <= MAX_TITLE_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_TITLE_CHARS - 1).collect();
+    format!("{cut}…")
+}
+
+fn short_sha(sha: &str) -> &str {
+    sha.get(..7).unwrap_or(sha)
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+/// The start of a Markdown body, trimmed to fit an embed description.
+fn excerpt(body: Option<&str>) -> Option<String> {
+    let body = body?.trim();
+    if body.is_empty() {
+        return None;
+    }
+    if body.chars().count() <= MAX_BODY_CHARS {
+        return Some(body.to_string());
+    }
+    let cut: String = body.chars().take(MAX_BODY_CHARS).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
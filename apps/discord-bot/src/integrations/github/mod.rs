@@ -0,0 +1,6 @@
+pub mod api;
+pub mod autolink;
+pub mod defaults;
+pub mod events;
+pub mod pages;
+pub mod webhook;
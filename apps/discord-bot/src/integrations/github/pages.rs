@@ -0,0 +1,263 @@
+use super::api::{CommitSummary, GithubApi, Issue, Page};
+use crate::utils::embeds;
+use serenity::all::{
+    ButtonStyle, ComponentInteraction, Context as SerenityContext, CreateActionRow, CreateButton,
+    CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    CreateInteractionResponseMessage, EditInteractionResponse, MessageInteractionMetadata, UserId,
+};
+use tracing::{error, warn};
+
+/// Custom ID prefix for the previous/next buttons on `/issues` and `/commits`.
+pub const PAGE_PREFIX: &str = "github_page";
+
+/// Discord's limit on button custom IDs.
+const MAX_CUSTOM_ID: usize = 100;
+
+/// Discord's limit on embed descriptions.
+const MAX_DESCRIPTION_CHARS: usize = 4096;
+
+/// Longest issue title or commit subject shown in a listing.
+const MAX_LINE_TITLE_CHARS: usize = 100;
+
+/// Which listing a page belongs to.
+#[derive(Debug, Clone, Copy)]
+pub enum Listing {
+    Issues,
+    Commits,
+}
+
+impl Listing {
+    fn as_str(self) -> &'static str {
+        match self {
+            Listing::Issues => "issues",
+            Listing::Commits => "commits",
+        }
+    }
+}
+
+/// Render one page of a listing as an embed with previous/next buttons.
+pub async fn render(
+    api: &GithubApi,
+    listing: Listing,
+    repo: &str,
+    branch: Option<&str>,
+    page: u32,
+) -> Result<(CreateEmbed, Vec<CreateActionRow>), String> {
+    let (embed, has_next) = match listing {
+        Listing::Issues => {
+            let issues = api.open_issues(repo, page).await?;
+            (issues_embed(repo, &issues), issues.has_next)
+        }
+        Listing::Commits => {
+            let commits = api.commits(repo, branch, page).await?;
+            (commits_embed(repo, branch, &commits), commits.has_next)
+        }
+    };
+
+    let embed = embed.footer(CreateEmbedFooter::new(format!("Page {page}")));
+    Ok((embed, page_buttons(listing, repo, branch, page, has_next)))
+}
+
+fn issues_embed(repo: &str, issues: &Page<Issue>) -> CreateEmbed {
+    let lines: Vec<String> = issues
+        .items
+        .iter()
+        .map(|issue| {
+            let labels: String = issue
+                .labels
+                .iter()
+                .map(|l| format!(" `{}`", l.name))
+                .collect();
+            format!(
+                "[#{} {}]({}){labels}\nby {} · <t:{}:R> · {} 💬",
+                issue.number,
+                // A `]` in the title would end the link text early
+                truncate(&issue.title, MAX_LINE_TITLE_CHARS).replace(']', "\\]"),
+                issue.html_url,
+                issue.user.login,
+                issue.created_at.timestamp(),
+                issue.comments
+            )
+        })
+        .collect();
+
+    let description = if lines.is_empty() {
+        "No open issues on this page.".to_string()
+    } else {
+        join_lines(&lines)
+    };
+
+    embeds::github_embed()
+        .title(format!("Open issues in {repo}"))
+        .url(format!("https://github.com/{repo}/issues"))
+        .description(description)
+}
+
+fn commits_embed(repo: &str, branch: Option<&str>, commits: &Page<CommitSummary>) -> CreateEmbed {
+    let lines: Vec<String> = commits
+        .items
+        .iter()
+        .map(|c| {
+            let author = c
+                .author
+                .as_ref()
+                .map(|a| a.login.as_str())
+                .or_else(|| c.commit.author.as_ref().map(|a| a.name.as_str()))
+                .unwrap_or("unknown");
+            let when = c
+                .commit
+                .author
+                .as_ref()
+                .map(|a| format!(" · <t:{}:R>", a.date.timestamp()))
+                .unwrap_or_default();
+            format!(
+                "[`{}`]({}) {}\nby {author}{when}",
+                c.sha.get(..7).unwrap_or(&c.sha),
+                c.html_url,
+                truncate(
+                    c.commit.message.lines().next().unwrap_or_default(),
+                    MAX_LINE_TITLE_CHARS
+                )
+            )
+        })
+        .collect();
+
+    let description = if lines.is_empty() {
+        "No commits on this page.".to_string()
+    } else {
+        join_lines(&lines)
+    };
+    let title = match branch {
+        Some(branch) => format!("Recent commits to {repo}:{branch}"),
+        None => format!("Recent commits to {repo}"),
+    };
+
+    embeds::github_embed()
+        .title(title)
+        .url(format!("https://github.com/{repo}/commits"))
+        .description(description)
+}
+
+/// Join listing entries, dropping whole entries that would go past Discord's
+/// description limit.
+fn join_lines(lines: &[String]) -> String {
+    let mut description = String::new();
+    let mut chars = 0;
+    for line in lines {
+        let separator = if description.is_empty() { 0 } else { 2 };
+        let len = line.chars().count();
+        if chars + separator + len > MAX_DESCRIPTION_CHARS {
+            break;
+        }
+        if separator > 0 {
+            description.push_str("\n\n");
+        }
+        description.push_str(line);
+        chars += separator + len;
+    }
+    description
+}
+
+/// Shorten `text` to at most `max` characters, marking the cut with `…`.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+fn page_buttons(
+    listing: Listing,
+    repo: &str,
+    branch: Option<&str>,
+    page: u32,
+    has_next: bool,
+) -> Vec<CreateActionRow> {
+    let custom_id = |target: u32| {
+        let mut id = format!("{PAGE_PREFIX}:{}:{target}:{repo}", listing.as_str());
+        if let Some(branch) = branch {
+            id.push(':');
+            id.push_str(branch);
+        }
+        id
+    };
+
+    let (previous, next) = (custom_id(page.saturating_sub(1)), custom_id(page + 1));
+    // Very long repository or branch names can't be encoded in a button.
+    if next.len() > MAX_CUSTOM_ID || (page <= 1 && !has_next) {
+        return Vec::new();
+    }
+
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(previous)
+            .label("◀ Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled(page <= 1),
+        CreateButton::new(next)
+            .label("Next ▶")
+            .style(ButtonStyle::Secondary)
+            .disabled(!has_next),
+    ])]
+}
+
+/// Handle a previous/next button.
+///
+/// The member who ran the command flips the shared message; anyone else gets
+/// their own ephemeral copy so they don't page it out from under them.
+pub async fn handle_page(ctx: &SerenityContext, component: &ComponentInteraction, api: &GithubApi) {
+    let mut parts = component.data.custom_id.splitn(5, ':').skip(1);
+    let listing = match parts.next() {
+        Some("issues") => Listing::Issues,
+        Some("commits") => Listing::Commits,
+        _ => {
+            warn!(custom_id = %component.data.custom_id, "Malformed GitHub page button");
+            return;
+        }
+    };
+    let (Some(Ok(page)), Some(repo)) = (parts.next().map(str::parse::<u32>), parts.next()) else {
+        warn!(custom_id = %component.data.custom_id, "Malformed GitHub page button");
+        return;
+    };
+    let branch = parts.next();
+
+    let is_owner = command_user(component) == Some(component.user.id);
+    let defer = if is_owner {
+        CreateInteractionResponse::Acknowledge
+    } else {
+        CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new().ephemeral(true))
+    };
+    if let Err(e) = component.create_response(&ctx.http, defer).await {
+        error!(error = %e, "Failed to acknowledge GitHub page button");
+        return;
+    }
+
+    let edit = match render(api, listing, repo, branch, page.max(1)).await {
+        Ok((embed, components)) => EditInteractionResponse::new()
+            .embed(embed)
+            .components(components),
+        Err(e) if is_owner => {
+            let notice = CreateInteractionResponseFollowup::new()
+                .embed(embeds::error_embed().description(e))
+                .ephemeral(true);
+            if let Err(e) = component.create_followup(&ctx.http, notice).await {
+                error!(error = %e, "Failed to report GitHub page error");
+            }
+            return;
+        }
+        Err(e) => EditInteractionResponse::new().embed(embeds::error_embed().description(e)),
+    };
+
+    if let Err(e) = component.edit_response(&ctx.http, edit).await {
+        error!(error = %e, "Failed to show GitHub page");
+    }
+}
+
+/// Who ran the command that produced the message.
+fn command_user(component: &ComponentInteraction) -> Option<UserId> {
+    match component.message.interaction_metadata.as_deref()? {
+        MessageInteractionMetadata::Command(m) => Some(m.user.id),
+        MessageInteractionMetadata::Component(m) => Some(m.user.id),
+        _ => None,
+    }
+}
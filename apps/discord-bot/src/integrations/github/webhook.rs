@@ -0,0 +1,119 @@
+use super::events;
+use crate::config::GithubConfig;
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use hmac::{Hmac, Mac};
+use serenity::all::{CreateMessage, Http};
+use sha2::Sha256;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+/// GitHub caps webhook payloads at 25 MB.
+const MAX_PAYLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+#[derive(Clone)]
+struct WebhookState {
+    http: Arc<Http>,
+    config: Arc<GithubConfig>,
+    secret: Arc<str>,
+}
+
+/// Routes for GitHub webhook deliveries, mounted on the bot's HTTP server.
+pub fn router(http: Arc<Http>, config: GithubConfig, secret: String) -> Router {
+    let state = WebhookState {
+        http,
+        config: Arc::new(config),
+        secret: secret.into(),
+    };
+
+    Router::new()
+        .route("/github/webhook", post(receive))
+        .layer(DefaultBodyLimit::max(MAX_PAYLOAD_BYTES))
+        .with_state(state)
+}
+
+async fn receive(State(state): State<WebhookState>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    let signature = headers
+        .get("x-hub-signature-256")
+        .and_then(|v| v.to_str().ok());
+    if !verify_signature(&state.secret, &body, signature) {
+        warn!("Rejected GitHub webhook with a missing or invalid signature");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let Some(event) = headers.get("x-github-event").and_then(|v| v.to_str().ok()) else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let delivery = headers
+        .get("x-github-delivery")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    if event == "ping" {
+        info!(delivery, "GitHub webhook ping received");
+        return StatusCode::OK;
+    }
+
+    let notification = match events::parse(event, &body) {
+        Ok(Some(notification)) => notification,
+        Ok(None) => {
+            debug!(event, delivery, "Ignoring GitHub webhook event");
+            return StatusCode::NO_CONTENT;
+        }
+        Err(e) => {
+            warn!(event, delivery, error = %e, "Failed to parse GitHub webhook payload");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    let route = state.config.route(&notification.repo);
+    let channel_id = match route {
+        Some(route) => {
+            let branch_ok = notification
+                .branch
+                .as_deref()
+                .is_none_or(|branch| route.wants_branch(branch));
+            if !route.wants_event(event) || !branch_ok {
+                debug!(event, repo = %notification.repo, "GitHub event filtered out");
+                return StatusCode::NO_CONTENT;
+            }
+            route.channel_id
+        }
+        None => match state.config.default_channel_id {
+            Some(channel_id) => channel_id,
+            None => {
+                debug!(repo = %notification.repo, "No channel configured for repository");
+                return StatusCode::NO_CONTENT;
+            }
+        },
+    };
+
+    // Reply to GitHub straight away; it times out deliveries after 10 seconds.
+    tokio::spawn(async move {
+        let message = CreateMessage::new().embed(notification.embed);
+        if let Err(e) = channel_id.send_message(&state.http, message).await {
+            error!(repo = %notification.repo, error = %e, "Failed to post GitHub event");
+        }
+    });
+
+    StatusCode::ACCEPTED
+}
+
+/// Check `X-Hub-Signature-256` (`sha256=<hex HMAC of the body>`) in constant time.
+fn verify_signature(secret: &str, body: &[u8], signature: Option<&str>) -> bool {
+    let Some(expected) = signature
+        .and_then(|s| s.strip_prefix("sha256="))
+        .and_then(|hex| hex::decode(hex).ok())
+    else {
+        return false;
+    };
+
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
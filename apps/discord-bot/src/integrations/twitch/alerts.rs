@@ -0,0 +1,303 @@
+use crate::utils::{embeds, templates};
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, GuildId, Http};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+/// How long to wait for more alerts before posting a batch.
+const BATCH_WINDOW: Duration = Duration::from_secs(3);
+
+/// Minimum gap between two alert posts, to stay well clear of Discord rate limits.
+const MIN_POST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Batches up to this size are posted as one embed per alert; larger ones are
+/// condensed into a single summary embed.
+const MAX_INDIVIDUAL_ALERTS: usize = 5;
+
+/// Upper bound on alerts collected into one batch.
+const MAX_BATCH: usize = 50;
+
+/// Twitch channel activity that can be announced in Discord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum AlertKind {
+    Follow,
+    #[name = "Subscription"]
+    Subscribe,
+    #[name = "Gifted subs"]
+    GiftSub,
+    Cheer,
+    Raid,
+}
+
+impl AlertKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            AlertKind::Follow => "follow",
+            AlertKind::Subscribe => "subscribe",
+            AlertKind::GiftSub => "gift_sub",
+            AlertKind::Cheer => "cheer",
+            AlertKind::Raid => "raid",
+        }
+    }
+
+    /// The built-in template used when a guild hasn't customised one.
+    pub fn default_template(self) -> &'static str {
+        match self {
+            AlertKind::Follow => "**{user}** just followed!",
+            AlertKind::Subscribe => "**{user}** subscribed at {tier}!",
+            AlertKind::GiftSub => "**{user}** gifted **{amount}** {tier} subs!",
+            AlertKind::Cheer => "**{user}** cheered **{amount}** bits! {message}",
+            AlertKind::Raid => "**{user}** is raiding with **{amount}** viewers!",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AlertKind::Follow => "Follows",
+            AlertKind::Subscribe => "Subs",
+            AlertKind::GiftSub => "Gift Bombs",
+            AlertKind::Cheer => "Cheers",
+            AlertKind::Raid => "Raids",
+        }
+    }
+}
+
+/// A single piece of channel activity to announce.
+///
+/// Placeholders: `{user}`, `{amount}`, `{tier}`, `{message}`.
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub user: String,
+    pub amount: i64,
+    pub tier: String,
+    pub message: String,
+}
+
+impl Alert {
+    /// Sample alert used for template previews.
+    pub fn sample(kind: AlertKind) -> Self {
+        Self {
+            kind,
+            user: "CrimsonFan".into(),
+            amount: match kind {
+                AlertKind::GiftSub => 5,
+                AlertKind::Cheer => 500,
+                AlertKind::Raid => 42,
+                _ => 1,
+            },
+            tier: "Tier 1".into(),
+            message: "Let's go!".into(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "user" => Some(self.user.clone()),
+            "amount" => Some(self.amount.to_string()),
+            "tier" => Some(self.tier.clone()),
+            "message" => Some(self.message.clone()),
+            _ => None,
+        }
+    }
+
+    /// Render the alert with `template`.
+    pub fn render(&self, template: &str) -> String {
+        templates::render(template, |key| self.get(key))
+            .trim()
+            .to_string()
+    }
+}
+
+/// Load the guild's template for `kind`, falling back to the default.
+pub async fn load_template(
+    db: &PgPool,
+    guild_id: Option<GuildId>,
+    kind: AlertKind,
+) -> Result<String, sqlx::Error> {
+    let Some(guild_id) = guild_id else {
+        return Ok(kind.default_template().to_string());
+    };
+
+    let row: Option<(String,)> = sqlx::query_as(
+        "SELECT template FROM stream_alert_templates WHERE guild_id = $1 AND kind = $2",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(kind.as_str())
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row
+        .map(|(template,)| template)
+        .unwrap_or_else(|| kind.default_template().to_string()))
+}
+
+/// Insert or replace the guild's template for `kind`.
+pub async fn save_template(
+    db: &PgPool,
+    guild_id: GuildId,
+    kind: AlertKind,
+    template: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO stream_alert_templates (guild_id, kind, template) VALUES ($1, $2, $3) \
+         ON CONFLICT (guild_id, kind) DO UPDATE SET template = EXCLUDED.template",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(kind.as_str())
+    .bind(template)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove the guild's customised template so the default applies again.
+pub async fn reset_template(
+    db: &PgPool,
+    guild_id: GuildId,
+    kind: AlertKind,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM stream_alert_templates WHERE guild_id = $1 AND kind = $2")
+        .bind(guild_id.get() as i64)
+        .bind(kind.as_str())
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Handle to the background task that posts alerts to the activity channel.
+///
+/// Alerts are batched: during hype moments (raids, gift bombs) many events
+/// arrive within seconds, and posting each one separately would hit Discord's
+/// rate limits and flood the channel.
+#[derive(Clone)]
+pub struct AlertQueue {
+    tx: mpsc::UnboundedSender<Alert>,
+}
+
+impl AlertQueue {
+    pub fn spawn(
+        http: Arc<Http>,
+        db: PgPool,
+        guild_id: Option<GuildId>,
+        channel_id: ChannelId,
+    ) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Alert>();
+
+        tokio::spawn(async move {
+            while let Some(first) = rx.recv().await {
+                let mut batch = vec![first];
+                let deadline = tokio::time::Instant::now() + BATCH_WINDOW;
+
+                while batch.len() < MAX_BATCH {
+                    match tokio::time::timeout_at(deadline, rx.recv()).await {
+                        Ok(Some(alert)) => batch.push(alert),
+                        _ => break,
+                    }
+                }
+
+                post_batch(&http, &db, guild_id, channel_id, &batch).await;
+                tokio::time::sleep(MIN_POST_INTERVAL).await;
+            }
+        });
+
+        Self { tx }
+    }
+
+    pub fn push(&self, alert: Alert) {
+        // The receiver only goes away if the worker panicked; nothing to do then.
+        let _ = self.tx.send(alert);
+    }
+}
+
+async fn post_batch(
+    http: &Http,
+    db: &PgPool,
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+    batch: &[Alert],
+) {
+    let mut lines = Vec::with_capacity(batch.len());
+    for alert in batch {
+        let template = match load_template(db, guild_id, alert.kind).await {
+            Ok(t) => t,
+            Err(e) => {
+                error!(error = %e, "Failed to load alert template, using default");
+                alert.kind.default_template().to_string()
+            }
+        };
+        let mut line = alert.render(&template);
+        // Discord rejects embeds with an empty description
+        if line.is_empty() {
+            line = alert.render(alert.kind.default_template());
+        }
+        lines.push((alert.kind, line));
+    }
+
+    let embeds: Vec<CreateEmbed> = if lines.len() <= MAX_INDIVIDUAL_ALERTS {
+        lines
+            .into_iter()
+            .map(|(_, line)| embeds::twitch_embed().description(line))
+            .collect()
+    } else {
+        vec![summary_embed(batch, &lines)]
+    };
+
+    let message = CreateMessage::new().embeds(embeds);
+    match channel_id.send_message(http, message).await {
+        Ok(_) => info!(count = batch.len(), "Posted Twitch activity alerts"),
+        Err(e) => error!(error = %e, "Failed to post Twitch activity alerts"),
+    }
+}
+
+/// Condense a large batch into one embed with per-kind totals.
+fn summary_embed(batch: &[Alert], lines: &[(AlertKind, String)]) -> CreateEmbed {
+    const MAX_DESCRIPTION: usize = 4000;
+
+    let mut description = String::new();
+    let mut omitted = 0;
+    for (_, line) in lines {
+        if description.len() + line.len() + 1 > MAX_DESCRIPTION {
+            omitted += 1;
+            continue;
+        }
+        description.push_str(line);
+        description.push('\n');
+    }
+    if omitted > 0 {
+        description.push_str(&format!("…and {omitted} more"));
+    }
+
+    let mut embed = embeds::twitch_embed()
+        .title(format!("Hype! {} new events", batch.len()))
+        .description(description);
+
+    for kind in [
+        AlertKind::Follow,
+        AlertKind::Subscribe,
+        AlertKind::GiftSub,
+        AlertKind::Cheer,
+        AlertKind::Raid,
+    ] {
+        let matching: Vec<&Alert> = batch.iter().filter(|a| a.kind == kind).collect();
+        if matching.is_empty() {
+            continue;
+        }
+        let value = match kind {
+            AlertKind::Cheer => {
+                format!("{} bits", matching.iter().map(|a| a.amount).sum::<i64>())
+            }
+            AlertKind::GiftSub => {
+                format!("{} subs", matching.iter().map(|a| a.amount).sum::<i64>())
+            }
+            _ => matching.len().to_string(),
+        };
+        embed = embed.field(kind.label(), value, true);
+    }
+
+    embed
+}
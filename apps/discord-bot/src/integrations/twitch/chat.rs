@@ -0,0 +1,565 @@
+use crate::metrics;
+use futures_util::{SinkExt, StreamExt};
+use serenity::all::{
+    ChannelId, Context as SerenityContext, CreateAllowedMentions, CreateWebhook, ExecuteWebhook,
+    GuildId, MessageBuilder, MessageId, Webhook,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tracing::{error, info, warn};
+use twitch_api::twitch_oauth2::{Scope, TwitchToken, UserToken};
+
+const TWITCH_IRC_URL: &str = "wss://irc-ws.chat.twitch.tv:443";
+
+/// Prefix on messages relayed from Discord, also used to recognise them on the way back.
+const DISCORD_PREFIX: &str = "[Discord]";
+
+const WEBHOOK_NAME: &str = "Twitch Chat Bridge";
+
+/// Twitch rejects chat messages longer than this (in characters).
+const MAX_TWITCH_MESSAGE: usize = 500;
+
+/// The bridge sends as the broadcaster, who is allowed 100 messages per 30 seconds.
+const MIN_SEND_INTERVAL: Duration = Duration::from_millis(350);
+
+/// How many relayed messages to remember for mirroring Twitch deletions.
+const RELAYED_HISTORY: usize = 500;
+
+/// Handle shared between the Twitch IRC task and the Discord message handler.
+///
+/// The bridge only relays while the stream is live; `stream.online` and
+/// `stream.offline` toggle it.
+#[derive(Clone)]
+pub struct ChatBridge {
+    inner: Arc<BridgeInner>,
+}
+
+struct BridgeInner {
+    channel_id: ChannelId,
+    live: AtomicBool,
+    outgoing: mpsc::UnboundedSender<String>,
+    outgoing_rx: Mutex<Option<mpsc::UnboundedReceiver<String>>>,
+}
+
+impl ChatBridge {
+    pub fn new(channel_id: ChannelId) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            inner: Arc::new(BridgeInner {
+                channel_id,
+                live: AtomicBool::new(false),
+                outgoing: tx,
+                outgoing_rx: Mutex::new(Some(rx)),
+            }),
+        }
+    }
+
+    /// The Discord channel bridged with Twitch chat.
+    pub fn channel_id(&self) -> ChannelId {
+        self.inner.channel_id
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.inner.live.load(Ordering::Relaxed)
+    }
+
+    pub fn set_live(&self, live: bool) {
+        self.inner.live.store(live, Ordering::Relaxed);
+        info!(live, "Twitch chat bridge state changed");
+    }
+
+    /// Queue a Discord message for Twitch chat. Dropped while offline.
+    pub fn relay_from_discord(&self, author: &str, content: &str) {
+        if !self.is_live() {
+            return;
+        }
+
+        let text = translate_discord_emotes(content);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return;
+        }
+
+        let line = format!("{DISCORD_PREFIX} {author}: {text}");
+        let _ = self
+            .inner
+            .outgoing
+            .send(line.chars().take(MAX_TWITCH_MESSAGE).collect());
+    }
+}
+
+/// Connect to Twitch chat and keep the bridge running, reconnecting as needed.
+///
+/// `token` must carry the `chat:read` and `chat:edit` scopes.
+pub fn spawn(
+    ctx: SerenityContext,
+    bridge: ChatBridge,
+    token: Arc<RwLock<UserToken>>,
+    channel_login: String,
+) {
+    let Some(mut outgoing) = bridge.inner.outgoing_rx.lock().unwrap().take() else {
+        warn!("Twitch chat bridge already started");
+        return;
+    };
+
+    tokio::spawn(async move {
+        {
+            let token = token.read().await;
+            let scopes = token.scopes();
+            if !scopes.contains(&Scope::ChatRead) || !scopes.contains(&Scope::ChatEdit) {
+                warn!("Twitch user token lacks chat:read/chat:edit, chat bridge disabled");
+                return;
+            }
+        }
+
+        let mut relay = match DiscordRelay::new(&ctx, bridge.channel_id()).await {
+            Ok(relay) => relay,
+            Err(e) => {
+                error!(error = %e, "Failed to set up chat bridge webhook");
+                return;
+            }
+        };
+
+        let channel = channel_login.to_lowercase();
+        loop {
+            match run_irc_connection(&bridge, &token, &channel, &mut relay, &mut outgoing).await {
+                Ok(()) => info!("Twitch chat asked us to reconnect"),
+                Err(e) => error!(error = %e, "Twitch chat connection error, reconnecting in 5s..."),
+            }
+            metrics::record_twitch_reconnect("chat");
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+}
+
+/// Returns Ok(()) when Twitch asks for a reconnect, Err on connection failure.
+async fn run_irc_connection(
+    bridge: &ChatBridge,
+    token: &RwLock<UserToken>,
+    channel: &str,
+    relay: &mut DiscordRelay,
+    outgoing: &mut mpsc::UnboundedReceiver<String>,
+) -> Result<(), String> {
+    let (ws_stream, _) = tokio_tungstenite::connect_async(TWITCH_IRC_URL)
+        .await
+        .map_err(|e| format!("WebSocket connect failed: {e}"))?;
+    let (mut write, mut read) = ws_stream.split();
+
+    let (access_token, login) = {
+        let token = token.read().await;
+        (token.token().secret().to_string(), token.login.to_string())
+    };
+
+    for line in [
+        "CAP REQ :twitch.tv/tags twitch.tv/commands".to_string(),
+        format!("PASS oauth:{access_token}"),
+        format!("NICK {login}"),
+        format!("JOIN #{channel}"),
+    ] {
+        write
+            .send(WsMessage::Text(line))
+            .await
+            .map_err(|e| format!("WebSocket write failed: {e}"))?;
+    }
+
+    info!(channel, "Connected to Twitch chat");
+
+    let mut last_sent = tokio::time::Instant::now();
+    loop {
+        tokio::select! {
+            msg = read.next() => {
+                let text = match msg {
+                    Some(Ok(WsMessage::Text(text))) => text,
+                    Some(Ok(WsMessage::Close(_))) => return Err("closed by server".into()),
+                    Some(Ok(WsMessage::Ping(data))) => {
+                        let _ = write.send(WsMessage::Pong(data)).await;
+                        continue;
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(format!("WebSocket read error: {e}")),
+                    None => return Err("WebSocket stream ended".into()),
+                };
+
+                // A single frame may carry several IRC lines.
+                for line in text.lines().filter(|l| !l.is_empty()) {
+                    let Some(msg) = IrcMessage::parse(line) else {
+                        continue;
+                    };
+
+                    match msg.command {
+                        "PING" => {
+                            let pong = format!("PONG :{}", msg.trailing.unwrap_or("tmi.twitch.tv"));
+                            let _ = write.send(WsMessage::Text(pong)).await;
+                        }
+                        "RECONNECT" => return Ok(()),
+                        "NOTICE" if msg.trailing.is_some_and(|t| t.contains("authentication failed")) => {
+                            return Err("Twitch chat login failed".into());
+                        }
+                        "PRIVMSG" if bridge.is_live() => relay.relay_to_discord(&msg).await,
+                        "CLEARMSG" => {
+                            if let Some(id) = msg.tags.get("target-msg-id") {
+                                relay.delete_by_message(id).await;
+                            }
+                        }
+                        "CLEARCHAT" => match msg.tags.get("target-user-id") {
+                            // Timeout or ban
+                            Some(user_id) => relay.delete_by_user(user_id).await,
+                            None => relay.delete_all().await,
+                        },
+                        _ => {}
+                    }
+                }
+            }
+
+            Some(line) = outgoing.recv() => {
+                // Let messages queued while offline lapse rather than dumping them at go-live.
+                if !bridge.is_live() {
+                    continue;
+                }
+
+                let wait = MIN_SEND_INTERVAL.saturating_sub(last_sent.elapsed());
+                tokio::time::sleep(wait).await;
+
+                write
+                    .send(WsMessage::Text(format!("PRIVMSG #{channel} :{line}")))
+                    .await
+                    .map_err(|e| format!("WebSocket write failed: {e}"))?;
+                last_sent = tokio::time::Instant::now();
+            }
+        }
+    }
+}
+
+// ─── Twitch → Discord ────────────────────────────────────────────────
+
+struct RelayedMessage {
+    twitch_message_id: String,
+    twitch_user_id: String,
+    discord_message_id: MessageId,
+}
+
+/// Posts Twitch chat into Discord through a webhook and remembers what it
+/// posted so Twitch moderation can be mirrored.
+struct DiscordRelay {
+    ctx: SerenityContext,
+    webhook: Webhook,
+    guild_id: Option<GuildId>,
+    relayed: VecDeque<RelayedMessage>,
+}
+
+impl DiscordRelay {
+    async fn new(ctx: &SerenityContext, channel_id: ChannelId) -> Result<Self, String> {
+        let guild_id = channel_id
+            .to_channel(&ctx.http)
+            .await
+            .map_err(|e| format!("failed to fetch chat channel: {e}"))?
+            .guild()
+            .map(|gc| gc.guild_id);
+
+        // Reuse our webhook across restarts instead of creating a new one each time.
+        let existing = channel_id
+            .webhooks(&ctx.http)
+            .await
+            .map_err(|e| format!("failed to list webhooks: {e}"))?
+            .into_iter()
+            .find(|w| w.token.is_some() && w.name.as_deref() == Some(WEBHOOK_NAME));
+
+        let webhook = match existing {
+            Some(webhook) => webhook,
+            None => channel_id
+                .create_webhook(&ctx.http, CreateWebhook::new(WEBHOOK_NAME))
+                .await
+                .map_err(|e| format!("failed to create webhook: {e}"))?,
+        };
+
+        Ok(Self {
+            ctx: ctx.clone(),
+            webhook,
+            guild_id,
+            relayed: VecDeque::with_capacity(RELAYED_HISTORY),
+        })
+    }
+
+    async fn relay_to_discord(&mut self, msg: &IrcMessage<'_>) {
+        let Some(text) = msg.trailing else {
+            return;
+        };
+
+        // Our own Discord relays (e.g. from a second bot instance) must not bounce back.
+        if text.starts_with(DISCORD_PREFIX) {
+            return;
+        }
+
+        let (text, is_action) = match text
+            .strip_prefix("\u{1}ACTION ")
+            .and_then(|t| t.strip_suffix('\u{1}'))
+        {
+            Some(action) => (action, true),
+            None => (text, false),
+        };
+
+        let name = msg
+            .tags
+            .get("display-name")
+            .filter(|n| !n.is_empty())
+            .cloned()
+            .or_else(|| msg.nick().map(str::to_string))
+            .unwrap_or_else(|| "Twitch".into());
+
+        let emotes = msg
+            .tags
+            .get("emotes")
+            .map(|spec| twitch_emote_names(text, spec))
+            .unwrap_or_default();
+        let mut content = self.translate_twitch_emotes(text, &emotes);
+        if is_action {
+            content = format!("*{content}*");
+        }
+
+        let builder = ExecuteWebhook::new()
+            .username(webhook_username(&name))
+            .content(content)
+            .allowed_mentions(CreateAllowedMentions::new());
+
+        match self.webhook.execute(&self.ctx.http, true, builder).await {
+            Ok(Some(message)) => {
+                if let (Some(msg_id), Some(user_id)) = (msg.tags.get("id"), msg.tags.get("user-id"))
+                {
+                    if self.relayed.len() == RELAYED_HISTORY {
+                        self.relayed.pop_front();
+                    }
+                    self.relayed.push_back(RelayedMessage {
+                        twitch_message_id: msg_id.clone(),
+                        twitch_user_id: user_id.clone(),
+                        discord_message_id: message.id,
+                    });
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!(error = %e, "Failed to relay Twitch chat message"),
+        }
+    }
+
+    /// Replace Twitch emotes with same-named server emojis; keep the rest as escaped text.
+    fn translate_twitch_emotes(&self, text: &str, emotes: &[String]) -> String {
+        let guild_emojis: HashMap<String, String> = self
+            .guild_id
+            .and_then(|id| self.ctx.cache.guild(id))
+            .map(|guild| {
+                guild
+                    .emojis
+                    .values()
+                    .filter(|e| emotes.contains(&e.name))
+                    .map(|e| (e.name.clone(), e.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut builder = MessageBuilder::new();
+        for (i, word) in text.split(' ').enumerate() {
+            if i > 0 {
+                builder.push(" ");
+            }
+            match guild_emojis.get(word) {
+                Some(emoji) => builder.push(emoji.as_str()),
+                None => builder.push_safe(word),
+            };
+        }
+        builder.build()
+    }
+
+    async fn delete_by_message(&mut self, twitch_message_id: &str) {
+        let deleted = self.take_relayed(|m| m.twitch_message_id == twitch_message_id);
+        self.delete(deleted).await;
+    }
+
+    async fn delete_by_user(&mut self, twitch_user_id: &str) {
+        let deleted = self.take_relayed(|m| m.twitch_user_id == twitch_user_id);
+        self.delete(deleted).await;
+    }
+
+    async fn delete_all(&mut self) {
+        let deleted = self.take_relayed(|_| true);
+        self.delete(deleted).await;
+    }
+
+    /// Remove and return the remembered messages matching `pred`.
+    fn take_relayed(&mut self, pred: impl Fn(&RelayedMessage) -> bool) -> Vec<RelayedMessage> {
+        let (taken, kept) = std::mem::take(&mut self.relayed)
+            .into_iter()
+            .partition::<Vec<_>, _>(|m| pred(m));
+        self.relayed = kept.into();
+        taken
+    }
+
+    async fn delete(&self, messages: Vec<RelayedMessage>) {
+        for message in messages {
+            if let Err(e) = self
+                .webhook
+                .delete_message(&self.ctx.http, None, message.discord_message_id)
+                .await
+            {
+                warn!(error = %e, "Failed to delete relayed message removed on Twitch");
+            }
+        }
+    }
+}
+
+/// Names of the emotes listed in a PRIVMSG `emotes` tag (`id:start-end,start-end/...`).
+///
+/// Positions count characters, not bytes.
+fn twitch_emote_names(text: &str, spec: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut names = Vec::new();
+
+    for emote in spec.split('/') {
+        let Some((_, ranges)) = emote.split_once(':') else {
+            continue;
+        };
+        // Every range of one emote is the same name, so the first is enough.
+        let Some((start, end)) = ranges.split(',').next().and_then(|r| r.split_once('-')) else {
+            continue;
+        };
+        let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) else {
+            continue;
+        };
+        if start <= end && end < chars.len() {
+            names.push(chars[start..=end].iter().collect());
+        }
+    }
+
+    names
+}
+
+/// Discord refuses webhook names containing "discord" or "clyde", so break those up.
+fn webhook_username(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::with_capacity(name.len());
+    for (i, c) in chars.iter().enumerate() {
+        result.push(*c);
+        let starts_banned = ["discord", "clyde"].iter().any(|banned| {
+            banned
+                .chars()
+                .enumerate()
+                .all(|(j, b)| chars.get(i + j).is_some_and(|c| c.eq_ignore_ascii_case(&b)))
+        });
+        if starts_banned {
+            result.push('\u{200B}');
+        }
+    }
+    result.chars().take(80).collect()
+}
+
+// ─── Discord → Twitch ────────────────────────────────────────────────
+
+/// Turn custom emoji markup (`<:name:id>`, `<a:name:id>`) into the bare name,
+/// which is how the matching Twitch emote is typed.
+fn translate_discord_emotes(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+
+        let parsed = candidate.find('>').and_then(|end| {
+            let inner = &candidate[1..end];
+            let inner = inner
+                .strip_prefix("a:")
+                .or_else(|| inner.strip_prefix(':'))?;
+            let (name, id) = inner.split_once(':')?;
+            id.chars()
+                .all(|c| c.is_ascii_digit())
+                .then_some((name, end))
+        });
+
+        match parsed {
+            Some((name, end)) => {
+                result.push_str(name);
+                rest = &candidate[end + 1..];
+            }
+            None => {
+                result.push('<');
+                rest = &candidate[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+// ─── IRC Parsing ─────────────────────────────────────────────────────
+
+/// A single line of Twitch IRC: `@tags :prefix COMMAND params :trailing`.
+struct IrcMessage<'a> {
+    tags: HashMap<&'a str, String>,
+    prefix: Option<&'a str>,
+    command: &'a str,
+    trailing: Option<&'a str>,
+}
+
+impl<'a> IrcMessage<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let mut rest = line.trim_end_matches('\r');
+        let mut tags = HashMap::new();
+
+        if let Some(tagged) = rest.strip_prefix('@') {
+            let (raw_tags, remainder) = tagged.split_once(' ')?;
+            for tag in raw_tags.split(';') {
+                let (key, value) = tag.split_once('=').unwrap_or((tag, ""));
+                tags.insert(key, unescape_tag(value));
+            }
+            rest = remainder;
+        }
+
+        let mut prefix = None;
+        if let Some(prefixed) = rest.strip_prefix(':') {
+            let (p, remainder) = prefixed.split_once(' ')?;
+            prefix = Some(p);
+            rest = remainder;
+        }
+
+        let (head, trailing) = match rest.split_once(" :") {
+            Some((head, trailing)) => (head, Some(trailing)),
+            None => (rest, None),
+        };
+        let command = head.split(' ').next().filter(|c| !c.is_empty())?;
+
+        Some(Self {
+            tags,
+            prefix,
+            command,
+            trailing,
+        })
+    }
+
+    /// Login of the sender, from `nick!user@host`.
+    fn nick(&self) -> Option<&'a str> {
+        self.prefix.and_then(|p| p.split('!').next())
+    }
+}
+
+fn unescape_tag(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some(':') => result.push(';'),
+            Some('r') => result.push('\r'),
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
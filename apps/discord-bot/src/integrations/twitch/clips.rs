@@ -0,0 +1,411 @@
+use crate::scheduler::Scheduler;
+use crate::utils::embeds;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context as SerenityContext, CreateActionRow,
+    CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateMessage, GuildId, Http, Mentionable, MessageId, UserId,
+};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use twitch_api::helix::clips::Clip;
+
+/// Custom ID prefix of the vote buttons: `clip_vote:<submission id>:<up|down>`.
+pub const VOTE_PREFIX: &str = "clip_vote";
+
+/// Scheduler job announcing the Clip of the Week, every Sunday at 18:00 UTC.
+const WEEKLY_JOB: &str = "clip_of_the_week";
+const WEEKLY_CRON: &str = "0 18 * * Sun";
+
+/// How many clips are archived (and shown) per weekly leaderboard.
+const LEADERBOARD_SIZE: i64 = 10;
+const LEADERBOARD_SHOWN: usize = 5;
+
+/// A clip posted to the clips channel.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ClipSubmission {
+    pub id: i64,
+    pub guild_id: i64,
+    pub clip_id: String,
+    pub title: String,
+    pub url: String,
+    pub creator_name: String,
+    pub thumbnail_url: String,
+    pub submitted_by: i64,
+    pub channel_id: i64,
+    pub message_id: Option<i64>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+impl ClipSubmission {
+    /// Link to the Discord message the clip was posted in, if it was posted.
+    pub fn message_link(&self) -> Option<String> {
+        self.message_id.map(|message_id| {
+            format!(
+                "https://discord.com/channels/{}/{}/{message_id}",
+                self.guild_id, self.channel_id
+            )
+        })
+    }
+}
+
+const SUBMISSION_COLUMNS: &str = "id, guild_id, clip_id, title, url, creator_name, \
+    thumbnail_url, submitted_by, channel_id, message_id, submitted_at";
+
+/// Extract the clip slug from any of the URL shapes Twitch hands out.
+///
+/// - `https://clips.twitch.tv/<slug>`
+/// - `https://clips.twitch.tv/embed?clip=<slug>`
+/// - `https://www.twitch.tv/<channel>/clip/<slug>`
+pub fn parse_clip_id(input: &str) -> Option<String> {
+    let url = reqwest::Url::parse(input.trim()).ok()?;
+    let host = url
+        .host_str()?
+        .trim_start_matches("www.")
+        .trim_start_matches("m.");
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    let slug = match (host, segments.as_slice()) {
+        ("clips.twitch.tv", ["embed"]) => url
+            .query_pairs()
+            .find(|(key, _)| key == "clip")
+            .map(|(_, value)| value.into_owned())?,
+        ("clips.twitch.tv", [slug]) => slug.to_string(),
+        ("twitch.tv", [_, "clip", slug]) => slug.to_string(),
+        _ => return None,
+    };
+
+    let valid = !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(slug)
+}
+
+/// The submission of a clip in a guild, if it has already been submitted.
+pub async fn find_submission(
+    db: &PgPool,
+    guild_id: GuildId,
+    clip_id: &str,
+) -> Result<Option<ClipSubmission>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {SUBMISSION_COLUMNS} FROM clip_submissions WHERE guild_id = $1 AND clip_id = $2"
+    ))
+    .bind(guild_id.get() as i64)
+    .bind(clip_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Record a new submission and return its ID.
+pub async fn create_submission(
+    db: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    clip: &Clip,
+    submitted_by: UserId,
+) -> Result<i64, sqlx::Error> {
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO clip_submissions \
+            (guild_id, clip_id, title, url, creator_name, thumbnail_url, submitted_by, channel_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+         RETURNING id",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(&clip.id)
+    .bind(&clip.title)
+    .bind(&clip.url)
+    .bind(clip.creator_name.as_str())
+    .bind(&clip.thumbnail_url)
+    .bind(submitted_by.get() as i64)
+    .bind(channel_id.get() as i64)
+    .fetch_one(db)
+    .await?;
+
+    Ok(id)
+}
+
+/// Remember which message a submission was posted in.
+pub async fn set_message(db: &PgPool, id: i64, message_id: MessageId) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE clip_submissions SET message_id = $2 WHERE id = $1")
+        .bind(id)
+        .bind(message_id.get() as i64)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Drop a submission whose message couldn't be posted.
+pub async fn delete_submission(db: &PgPool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM clip_submissions WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Cast, change or withdraw a member's vote.
+///
+/// Each member has at most one vote per clip: voting the other way switches
+/// it, voting the same way again takes it back.
+async fn toggle_vote(
+    db: &PgPool,
+    submission_id: i64,
+    user_id: UserId,
+    value: i16,
+) -> Result<(), sqlx::Error> {
+    let removed = sqlx::query(
+        "DELETE FROM clip_votes WHERE submission_id = $1 AND user_id = $2 AND value = $3",
+    )
+    .bind(submission_id)
+    .bind(user_id.get() as i64)
+    .bind(value)
+    .execute(db)
+    .await?;
+
+    if removed.rows_affected() == 0 {
+        sqlx::query(
+            "INSERT INTO clip_votes (submission_id, user_id, value) VALUES ($1, $2, $3) \
+             ON CONFLICT (submission_id, user_id) \
+             DO UPDATE SET value = EXCLUDED.value, voted_at = now()",
+        )
+        .bind(submission_id)
+        .bind(user_id.get() as i64)
+        .bind(value)
+        .execute(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Up- and down-vote counts for a submission.
+async fn tally(db: &PgPool, submission_id: i64) -> Result<(i64, i64), sqlx::Error> {
+    sqlx::query_as(
+        "SELECT COUNT(*) FILTER (WHERE value = 1), COUNT(*) FILTER (WHERE value = -1) \
+         FROM clip_votes WHERE submission_id = $1",
+    )
+    .bind(submission_id)
+    .fetch_one(db)
+    .await
+}
+
+/// Embed for a freshly submitted clip.
+pub fn clip_embed(clip: &Clip, submitted_by: UserId) -> CreateEmbed {
+    embeds::twitch_embed()
+        .title(&clip.title)
+        .url(&clip.url)
+        .image(&clip.thumbnail_url)
+        .field("Clipped by", clip.creator_name.as_str(), true)
+        .field("Views", clip.view_count.to_string(), true)
+        .field("Length", format!("{:.0}s", clip.duration), true)
+        .field("Submitted by", submitted_by.mention().to_string(), true)
+}
+
+/// The 👍/👎 buttons under a clip, showing the current counts.
+pub fn vote_buttons(submission_id: i64, up: i64, down: i64) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{VOTE_PREFIX}:{submission_id}:up"))
+            .label(up.to_string())
+            .emoji('👍')
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(format!("{VOTE_PREFIX}:{submission_id}:down"))
+            .label(down.to_string())
+            .emoji('👎')
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+/// Record a vote button press and refresh the counts on the message.
+pub async fn handle_vote(ctx: &SerenityContext, component: &ComponentInteraction, db: &PgPool) {
+    let mut parts = component.data.custom_id.split(':').skip(1);
+    let (Some(Ok(submission_id)), Some(direction)) =
+        (parts.next().map(str::parse::<i64>), parts.next())
+    else {
+        warn!(custom_id = %component.data.custom_id, "Malformed clip vote button");
+        return;
+    };
+    let value = if direction == "up" { 1 } else { -1 };
+
+    let counts = match toggle_vote(db, submission_id, component.user.id, value).await {
+        Ok(()) => tally(db, submission_id).await,
+        Err(e) => Err(e),
+    };
+
+    let response = match counts {
+        Ok((up, down)) => CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new().components(vote_buttons(
+                submission_id,
+                up,
+                down,
+            )),
+        ),
+        Err(e) => {
+            error!(error = %e, submission_id, "Failed to record clip vote");
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(
+                        embeds::error_embed()
+                            .description("Your vote couldn't be saved. Please try again."),
+                    )
+                    .ephemeral(true),
+            )
+        }
+    };
+
+    if let Err(e) = component.create_response(&ctx.http, response).await {
+        error!(error = %e, "Failed to respond to clip vote");
+    }
+}
+
+// ─── Clip of the Week ────────────────────────────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+struct RankedClip {
+    id: i64,
+    title: String,
+    url: String,
+    creator_name: String,
+    thumbnail_url: String,
+    submitted_by: i64,
+    score: i32,
+}
+
+/// Announce the Clip of the Week in `channel_id` every week.
+pub async fn register_clip_of_the_week(scheduler: &Scheduler, channel_id: ChannelId) {
+    scheduler.register(WEEKLY_JOB, |ctx, job| async move {
+        let channel_id = job
+            .payload
+            .get("channel_id")
+            .and_then(serde_json::Value::as_u64)
+            .map(ChannelId::new)
+            .ok_or("Clip of the Week job has no channel")?;
+
+        // A run delayed by downtime still closes the week it was due for.
+        announce_clip_of_the_week(&ctx.http, &ctx.db, channel_id, job.run_at)
+            .await
+            .map_err(|e| format!("Failed to announce Clip of the Week: {e}"))
+    });
+
+    let payload = serde_json::json!({ "channel_id": channel_id.get() });
+    if let Err(e) = scheduler
+        .ensure_recurring(WEEKLY_JOB, WEEKLY_JOB, WEEKLY_CRON, None, payload)
+        .await
+    {
+        error!(error = %e, "Failed to schedule Clip of the Week");
+    }
+}
+
+/// Rank the week's clips, archive the leaderboard and post the winner.
+pub async fn announce_clip_of_the_week(
+    http: &Http,
+    db: &PgPool,
+    channel_id: ChannelId,
+    week_ending: DateTime<Utc>,
+) -> Result<(), String> {
+    let guild_id = channel_id
+        .to_channel(http)
+        .await
+        .map_err(|e| format!("failed to fetch clips channel: {e}"))?
+        .guild()
+        .map(|gc| gc.guild_id)
+        .ok_or("clips channel is not in a guild")?;
+
+    let (already_archived,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM clip_weekly_results WHERE guild_id = $1 AND week_ending = $2)",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(week_ending)
+    .fetch_one(db)
+    .await
+    .map_err(|e| e.to_string())?;
+    if already_archived {
+        return Ok(());
+    }
+
+    let ranked: Vec<RankedClip> = sqlx::query_as(
+        "SELECT s.id, s.title, s.url, s.creator_name, s.thumbnail_url, s.submitted_by, \
+                COALESCE(SUM(v.value), 0)::INT AS score \
+         FROM clip_submissions s \
+         LEFT JOIN clip_votes v ON v.submission_id = s.id \
+         WHERE s.guild_id = $1 AND s.submitted_at >= $2 AND s.submitted_at < $3 \
+         GROUP BY s.id \
+         ORDER BY score DESC, s.submitted_at ASC \
+         LIMIT $4",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(week_ending - ChronoDuration::weeks(1))
+    .bind(week_ending)
+    .bind(LEADERBOARD_SIZE)
+    .fetch_all(db)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some(winner) = ranked.first() else {
+        info!("No clips submitted this week, skipping Clip of the Week");
+        return Ok(());
+    };
+
+    let mut tx = db.begin().await.map_err(|e| e.to_string())?;
+    for (rank, clip) in ranked.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO clip_weekly_results (guild_id, week_ending, rank, submission_id, score) \
+             VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(week_ending)
+        .bind(rank as i32 + 1)
+        .bind(clip.id)
+        .bind(clip.score)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    let leaderboard = ranked
+        .iter()
+        .take(LEADERBOARD_SHOWN)
+        .enumerate()
+        .map(|(i, clip)| {
+            format!(
+                "**{}.** [{}]({}) — {:+}",
+                i + 1,
+                // A `]` in the title would end the link text early
+                clip.title.replace(']', "\\]"),
+                clip.url,
+                clip.score
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let embed = embeds::twitch_embed()
+        .title(format!("🏆 Clip of the Week: {}", winner.title))
+        .url(&winner.url)
+        .image(&winner.thumbnail_url)
+        .field("Clipped by", &winner.creator_name, true)
+        .field(
+            "Submitted by",
+            UserId::new(winner.submitted_by as u64)
+                .mention()
+                .to_string(),
+            true,
+        )
+        .field("Score", format!("{:+}", winner.score), true)
+        .field("This Week's Leaderboard", leaderboard, false);
+
+    channel_id
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await
+        .map_err(|e| format!("failed to post Clip of the Week: {e}"))?;
+
+    info!(
+        submission_id = winner.id,
+        score = winner.score,
+        "Clip of the Week announced"
+    );
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serenity::all::UserId;
+use sqlx::PgPool;
+
+const PLATFORM: &str = "twitch";
+
+/// A Discord member's linked Twitch account.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TwitchLink {
+    pub discord_user_id: i64,
+    pub platform_user_id: String,
+    pub platform_login: String,
+    pub linked_at: DateTime<Utc>,
+}
+
+impl TwitchLink {
+    pub fn profile_url(&self) -> String {
+        format!("https://twitch.tv/{}", self.platform_login)
+    }
+}
+
+/// The Twitch account linked to a Discord user, if any.
+pub async fn find_by_discord(
+    db: &PgPool,
+    user_id: UserId,
+) -> Result<Option<TwitchLink>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT discord_user_id, platform_user_id, platform_login, linked_at \
+         FROM account_links WHERE discord_user_id = $1 AND platform = $2",
+    )
+    .bind(user_id.get() as i64)
+    .bind(PLATFORM)
+    .fetch_optional(db)
+    .await
+}
+
+/// The Discord user a Twitch account is linked to, if any.
+pub async fn find_by_twitch(
+    db: &PgPool,
+    twitch_user_id: &str,
+) -> Result<Option<TwitchLink>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT discord_user_id, platform_user_id, platform_login, linked_at \
+         FROM account_links WHERE platform = $1 AND platform_user_id = $2",
+    )
+    .bind(PLATFORM)
+    .bind(twitch_user_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Link (or re-link) a Discord user to a Twitch account.
+pub async fn save(
+    db: &PgPool,
+    user_id: UserId,
+    twitch_user_id: &str,
+    twitch_login: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO account_links (discord_user_id, platform, platform_user_id, platform_login) \
+         VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (discord_user_id, platform) DO UPDATE SET \
+            platform_user_id = EXCLUDED.platform_user_id, \
+            platform_login = EXCLUDED.platform_login, \
+            linked_at = now()",
+    )
+    .bind(user_id.get() as i64)
+    .bind(PLATFORM)
+    .bind(twitch_user_id)
+    .bind(twitch_login)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a Discord user's Twitch link. Returns `false` if there was none.
+pub async fn remove(db: &PgPool, user_id: UserId) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM account_links WHERE discord_user_id = $1 AND platform = $2")
+            .bind(user_id.get() as i64)
+            .bind(PLATFORM)
+            .execute(db)
+            .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,1269 @@
+pub mod alerts;
+pub mod chat;
+pub mod clips;
+pub mod links;
+pub mod redemptions;
+pub mod schedule;
+pub mod sessions;
+pub mod templates;
+mod tokens;
+
+use crate::config::TwitchConfig;
+use crate::metrics;
+use crate::scheduler::Scheduler;
+use crate::utils::templates as text_templates;
+use alerts::{Alert, AlertKind, AlertQueue};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serenity::all::{
+    ChannelId, Context as SerenityContext, CreateMessage, EditChannel, EditMessage, Mentionable,
+    MessageId, PermissionOverwrite, PermissionOverwriteType, Permissions, RoleId,
+};
+use serenity::model::id::GuildId;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use templates::{StreamTemplate, StreamVars, TemplateKind};
+use tokio::sync::RwLock;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tracing::{error, info, warn};
+use twitch_api::eventsub::{
+    channel::{
+        channel_points_custom_reward_redemption::RedemptionStatus, ChannelCheerV1, ChannelFollowV2,
+        ChannelPointsCustomRewardRedemptionAddV1, ChannelRaidV1, ChannelSubscribeV1,
+        ChannelSubscriptionGiftV1, ChannelUpdateV2,
+    },
+    stream::StreamOfflineV1,
+    stream::StreamOnlineV1,
+    Event, EventsubWebsocketData, Message, Payload, Transport,
+};
+use twitch_api::helix::channels::GetChannelFollowersRequest;
+use twitch_api::helix::clips::{Clip, GetClipsRequest};
+use twitch_api::helix::games::GetGamesRequest;
+use twitch_api::helix::points::{
+    CustomRewardRedemptionStatus, UpdateRedemptionStatusBody, UpdateRedemptionStatusRequest,
+};
+use twitch_api::helix::schedule::GetChannelStreamScheduleRequest;
+use twitch_api::helix::streams::GetStreamsRequest;
+use twitch_api::helix::videos::{GetVideosRequest, VideoTypeFilter};
+use twitch_api::twitch_oauth2::{AppAccessToken, ClientSecret, UserToken};
+use twitch_api::types::{
+    CategoryIdRef, ClipIdRef, RedemptionIdRef, RewardIdRef, SubscriptionTier, UserIdRef,
+};
+use twitch_api::HelixClient;
+
+const TWITCH_EVENTSUB_URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+
+/// How often viewer counts are sampled while live (for peak/average stats).
+const VIEWER_SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// How long to keep looking for a VOD after the stream ends (12 × 5 min = 1h).
+const VOD_LOOKUP_ATTEMPTS: u32 = 12;
+const VOD_LOOKUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+// ─── Twitch Client State ────────────────────────────────────────────
+
+struct TwitchState {
+    helix: HelixClient<'static, reqwest::Client>,
+    token: Arc<RwLock<AppAccessToken>>,
+    /// Broadcaster token, required for follower/subscriber/bits events.
+    user_token: Option<Arc<RwLock<UserToken>>>,
+    chat_bridge: Option<chat::ChatBridge>,
+    config: TwitchConfig,
+    /// Whether an EventSub WebSocket session is currently established.
+    eventsub_connected: AtomicBool,
+}
+
+impl TwitchState {
+    async fn new(
+        config: &TwitchConfig,
+        db: &PgPool,
+        chat_bridge: Option<chat::ChatBridge>,
+        alert: tokens::RevocationAlert,
+    ) -> Result<Self, String> {
+        let helix: HelixClient<'static, reqwest::Client> = HelixClient::new();
+
+        let token = tokens::app_token(&helix, db, config).await?;
+        let user_token = tokens::user_token(&helix, db, config, &alert)
+            .await
+            .map(|t| Arc::new(RwLock::new(t)));
+        if user_token.is_none() {
+            warn!("No usable Twitch user token, activity alerts and redemptions disabled");
+        }
+
+        Ok(Self {
+            helix,
+            token: Arc::new(RwLock::new(token)),
+            user_token,
+            chat_bridge,
+            config: config.clone(),
+            eventsub_connected: AtomicBool::new(false),
+        })
+    }
+
+    fn spawn_refresh_loop(&self, db: &PgPool, alert: &tokens::RevocationAlert) {
+        tokens::spawn_refresh(
+            self.helix.clone(),
+            Arc::clone(&self.token),
+            ClientSecret::new(self.config.client_secret.clone()),
+            db.clone(),
+            alert.clone(),
+        );
+        if let Some(ref user_token) = self.user_token {
+            tokens::spawn_refresh(
+                self.helix.clone(),
+                Arc::clone(user_token),
+                ClientSecret::new(self.config.client_secret.clone()),
+                db.clone(),
+                alert.clone(),
+            );
+        }
+    }
+
+    async fn fetch_stream_info(
+        &self,
+    ) -> Result<Option<twitch_api::helix::streams::Stream>, String> {
+        let token = self.token.read().await;
+        let ids: &[&UserIdRef] = &[self.config.channel_id.as_str().into()];
+        let req = GetStreamsRequest::user_ids(ids);
+
+        let response = self
+            .helix
+            .req_get(req, &*token)
+            .await
+            .map_err(|e| format!("Helix GetStreams failed: {e}"))?;
+
+        Ok(response.data.into_iter().next())
+    }
+
+    async fn fetch_box_art(&self, game_id: &CategoryIdRef) -> Option<String> {
+        let token = self.token.read().await;
+        let ids: &[&CategoryIdRef] = &[game_id];
+        let req = GetGamesRequest::ids(ids);
+
+        match self.helix.req_get(req, &*token).await {
+            Ok(response) => response.data.into_iter().next().map(|game| {
+                game.box_art_url
+                    .replace("{width}", "285")
+                    .replace("{height}", "380")
+            }),
+            Err(e) => {
+                warn!(error = %e, "Helix GetGames failed");
+                None
+            }
+        }
+    }
+
+    /// Find the archive VOD for a specific broadcast, if Twitch has published it.
+    async fn fetch_vod_url(&self, stream_id: &str) -> Result<Option<String>, String> {
+        let token = self.token.read().await;
+        let mut req = GetVideosRequest::user_id(self.config.channel_id.as_str());
+        req.type_ = Some(VideoTypeFilter::Archive);
+        req.first = Some(5);
+
+        let response = self
+            .helix
+            .req_get(req, &*token)
+            .await
+            .map_err(|e| format!("Helix GetVideos failed: {e}"))?;
+
+        Ok(response
+            .data
+            .into_iter()
+            .find(|v| v.stream_id.as_ref().map(|id| id.as_str()) == Some(stream_id))
+            .map(|v| v.url))
+    }
+
+    /// Template values that don't depend on a live stream.
+    fn base_vars(&self) -> StreamVars {
+        StreamVars {
+            url: self.config.channel_url(),
+            role: self
+                .config
+                .live_role_id
+                .map(|r| r.mention().to_string())
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// Build template values from a Helix stream.
+    async fn stream_vars(&self, stream: &twitch_api::helix::streams::Stream) -> StreamVars {
+        StreamVars {
+            title: stream.title.clone(),
+            game: stream.game_name.clone(),
+            viewers: stream.viewer_count.to_string(),
+            uptime: parse_timestamp(&stream.started_at)
+                .map(format_uptime)
+                .unwrap_or_default(),
+            box_art: self
+                .fetch_box_art(&stream.game_id)
+                .await
+                .unwrap_or_default(),
+            thumbnail: stream
+                .thumbnail_url
+                .replace("{width}", "440")
+                .replace("{height}", "248"),
+            ..self.base_vars()
+        }
+    }
+
+    async fn subscribe_events(&self, session_id: &str) -> Result<(), String> {
+        let token = self.token.read().await;
+        let transport = Transport::websocket(session_id);
+        let channel_id = self.config.channel_id.as_str();
+
+        // stream.online
+        self.helix
+            .create_eventsub_subscription(
+                StreamOnlineV1::broadcaster_user_id(channel_id),
+                transport.clone(),
+                &*token,
+            )
+            .await
+            .map_err(|e| format!("Failed to subscribe stream.online: {e}"))?;
+        info!("Subscribed to stream.online");
+
+        // stream.offline
+        self.helix
+            .create_eventsub_subscription(
+                StreamOfflineV1::broadcaster_user_id(channel_id),
+                transport.clone(),
+                &*token,
+            )
+            .await
+            .map_err(|e| format!("Failed to subscribe stream.offline: {e}"))?;
+        info!("Subscribed to stream.offline");
+
+        // channel.update (v2)
+        self.helix
+            .create_eventsub_subscription(
+                ChannelUpdateV2::broadcaster_user_id(channel_id),
+                transport,
+                &*token,
+            )
+            .await
+            .map_err(|e| format!("Failed to subscribe channel.update: {e}"))?;
+        info!("Subscribed to channel.update");
+
+        if self.config.activity_channel_id.is_some() {
+            self.subscribe_activity_events(session_id).await;
+        }
+
+        self.subscribe_redemption_events(session_id).await;
+
+        Ok(())
+    }
+
+    /// Subscribe to channel-point redemptions so mapped rewards can trigger Discord actions.
+    async fn subscribe_redemption_events(&self, session_id: &str) {
+        let Some(ref user_token) = self.user_token else {
+            return;
+        };
+
+        let token = user_token.read().await;
+        log_subscription(
+            "channel.channel_points_custom_reward_redemption.add",
+            self.helix
+                .create_eventsub_subscription(
+                    ChannelPointsCustomRewardRedemptionAddV1::broadcaster_user_id(
+                        self.config.channel_id.as_str(),
+                    ),
+                    Transport::websocket(session_id),
+                    &*token,
+                )
+                .await,
+        );
+    }
+
+    /// Mark a redemption fulfilled, or cancel it to refund the viewer's points.
+    async fn update_redemption_status(
+        &self,
+        reward_id: &RewardIdRef,
+        redemption_id: &RedemptionIdRef,
+        status: CustomRewardRedemptionStatus,
+    ) -> Result<(), String> {
+        let user_token = self
+            .user_token
+            .as_ref()
+            .ok_or("no Twitch user token configured")?;
+        let token = user_token.read().await;
+
+        let req = UpdateRedemptionStatusRequest::new(
+            self.config.channel_id.as_str(),
+            reward_id,
+            redemption_id,
+        );
+        self.helix
+            .req_patch(req, UpdateRedemptionStatusBody::status(status), &*token)
+            .await
+            .map_err(|e| format!("Helix UpdateRedemptionStatus failed: {e}"))?;
+
+        Ok(())
+    }
+
+    /// Subscribe to follow/sub/cheer/raid events for the activity channel.
+    ///
+    /// These need a broadcaster user token with the matching scopes. Each topic
+    /// is subscribed independently so a missing scope only disables that alert.
+    async fn subscribe_activity_events(&self, session_id: &str) {
+        let Some(ref user_token) = self.user_token else {
+            warn!("STREAM_ACTIVITY_CHANNEL_ID is set but no Twitch user token is configured");
+            return;
+        };
+
+        let token = user_token.read().await;
+        let transport = Transport::websocket(session_id);
+        let channel_id = self.config.channel_id.as_str();
+
+        log_subscription(
+            "channel.follow",
+            self.helix
+                .create_eventsub_subscription(
+                    ChannelFollowV2::new(channel_id, token.user_id.clone()),
+                    transport.clone(),
+                    &*token,
+                )
+                .await,
+        );
+        log_subscription(
+            "channel.subscribe",
+            self.helix
+                .create_eventsub_subscription(
+                    ChannelSubscribeV1::broadcaster_user_id(channel_id),
+                    transport.clone(),
+                    &*token,
+                )
+                .await,
+        );
+        log_subscription(
+            "channel.subscription.gift",
+            self.helix
+                .create_eventsub_subscription(
+                    ChannelSubscriptionGiftV1::broadcaster_user_id(channel_id),
+                    transport.clone(),
+                    &*token,
+                )
+                .await,
+        );
+        log_subscription(
+            "channel.cheer",
+            self.helix
+                .create_eventsub_subscription(
+                    ChannelCheerV1::broadcaster_user_id(channel_id),
+                    transport.clone(),
+                    &*token,
+                )
+                .await,
+        );
+        log_subscription(
+            "channel.raid",
+            self.helix
+                .create_eventsub_subscription(
+                    ChannelRaidV1::to_broadcaster_user_id(channel_id),
+                    transport,
+                    &*token,
+                )
+                .await,
+        );
+    }
+}
+
+fn log_subscription<T, E: std::fmt::Display>(topic: &str, result: Result<T, E>) {
+    match result {
+        Ok(_) => info!(topic, "Subscribed to EventSub topic"),
+        Err(e) => warn!(topic, error = %e, "Failed to subscribe to EventSub topic"),
+    }
+}
+
+// ─── Command Access ──────────────────────────────────────────────────
+
+/// Twitch API access for commands, available once the integration has started.
+#[derive(Clone, Default)]
+pub struct TwitchApi {
+    state: Arc<tokio::sync::OnceCell<Arc<TwitchState>>>,
+}
+
+impl TwitchApi {
+    /// Whether the EventSub session is up, for readiness checks.
+    pub fn is_eventsub_connected(&self) -> bool {
+        self.state
+            .get()
+            .is_some_and(|s| s.eventsub_connected.load(Ordering::Relaxed))
+    }
+
+    fn state(&self) -> Result<&TwitchState, String> {
+        self.state
+            .get()
+            .map(Arc::as_ref)
+            .ok_or_else(|| "The Twitch integration isn't connected.".to_string())
+    }
+
+    /// Look up a clip by its slug.
+    pub async fn get_clip(&self, clip_id: &str) -> Result<Option<Clip>, String> {
+        let twitch = self.state()?;
+        let token = twitch.token.read().await;
+        let ids: &[&ClipIdRef] = &[clip_id.into()];
+        let req = GetClipsRequest::clip_ids(ids);
+
+        let response = twitch
+            .helix
+            .req_get(req, &*token)
+            .await
+            .map_err(|e| format!("Helix GetClips failed: {e}"))?;
+
+        Ok(response.data.into_iter().next())
+    }
+
+    /// The broadcaster's upcoming schedule.
+    pub async fn get_schedule(&self) -> Result<schedule::StreamSchedule, String> {
+        let twitch = self.state()?;
+        let token = twitch.token.read().await;
+        let mut req =
+            GetChannelStreamScheduleRequest::broadcaster_id(twitch.config.channel_id.as_str());
+        req.first = Some(25);
+
+        let response = twitch
+            .helix
+            .req_get(req, &*token)
+            .await
+            .map_err(|e| format!("Helix GetChannelStreamSchedule failed: {e}"))?;
+
+        Ok(schedule::StreamSchedule::from_helix(response.data))
+    }
+
+    /// Whether the channel is live right now.
+    pub async fn is_live(&self) -> Result<bool, String> {
+        Ok(self.state()?.fetch_stream_info().await?.is_some())
+    }
+
+    /// The channel's follower count.
+    ///
+    /// Twitch only reports it to user tokens, so this needs the broadcaster
+    /// token.
+    pub async fn follower_count(&self) -> Result<i64, String> {
+        let twitch = self.state()?;
+        let token = twitch
+            .user_token
+            .as_ref()
+            .ok_or("the follower count needs a Twitch user token, none is configured")?;
+        let mut req = GetChannelFollowersRequest::broadcaster_id(twitch.config.channel_id.as_str());
+        req.first = Some(1);
+
+        let response = twitch
+            .helix
+            .req_get(req, &*token.read().await)
+            .await
+            .map_err(|e| format!("Helix GetChannelFollowers failed: {e}"))?;
+
+        response
+            .total
+            .ok_or_else(|| "Twitch didn't return a follower count".to_string())
+    }
+}
+
+// ─── Notification State ──────────────────────────────────────────────
+
+struct LiveState {
+    go_live_message_id: Option<MessageId>,
+    guild_id: Option<GuildId>,
+    started_at: Option<DateTime<Utc>>,
+    session_id: Option<i64>,
+    last_vars: Option<StreamVars>,
+}
+
+// ─── Channel Lock/Unlock ─────────────────────────────────────────────
+
+async fn set_channel_locked(ctx: &SerenityContext, channel_id: ChannelId, locked: bool) {
+    let guild_id = match channel_id.to_channel(&ctx.http).await {
+        Ok(channel) => match channel.guild() {
+            Some(gc) => gc.guild_id,
+            None => return,
+        },
+        Err(e) => {
+            error!(error = %e, "Failed to fetch channel for lock/unlock");
+            return;
+        }
+    };
+
+    let everyone_role = RoleId::new(guild_id.get()); // @everyone role ID == guild ID
+
+    let overwrite = PermissionOverwrite {
+        allow: if locked {
+            Permissions::empty()
+        } else {
+            Permissions::SEND_MESSAGES
+        },
+        deny: if locked {
+            Permissions::SEND_MESSAGES
+        } else {
+            Permissions::empty()
+        },
+        kind: PermissionOverwriteType::Role(everyone_role),
+    };
+
+    let edit = EditChannel::new().permissions(vec![overwrite]);
+
+    if let Err(e) = channel_id.edit(&ctx.http, edit).await {
+        error!(error = %e, locked, "Failed to lock/unlock channel");
+    } else {
+        info!(channel_id = %channel_id, locked, "Channel lock state updated");
+    }
+}
+
+// ─── Go-Live / Offline Handlers ──────────────────────────────────────
+
+async fn handle_stream_online(
+    ctx: &SerenityContext,
+    twitch: &TwitchState,
+    db: &PgPool,
+    live_state: &Arc<RwLock<LiveState>>,
+) {
+    let stream = match twitch.fetch_stream_info().await {
+        Ok(Some(s)) => s,
+        Ok(None) => {
+            warn!("stream.online received but no stream data from Helix");
+            return;
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to fetch stream info");
+            return;
+        }
+    };
+
+    let vars = twitch.stream_vars(&stream).await;
+    let guild_id = live_state.read().await.guild_id;
+
+    let template = load_template(db, guild_id, TemplateKind::GoLive).await;
+    let rendered = template.render(TemplateKind::GoLive, &vars);
+
+    let mut message = CreateMessage::new().embed(rendered.embed);
+    if !rendered.content.is_empty() {
+        message = message.content(rendered.content);
+    }
+
+    let message_id = match twitch
+        .config
+        .live_channel_id
+        .send_message(&ctx.http, message)
+        .await
+    {
+        Ok(msg) => {
+            let mut state = live_state.write().await;
+            state.go_live_message_id = Some(msg.id);
+            state.guild_id = msg.guild_id.or(state.guild_id);
+            info!(message_id = %msg.id, "Go-live notification posted");
+            Some(msg.id)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to post go-live notification");
+            None
+        }
+    };
+
+    let started_at = parse_timestamp(&stream.started_at);
+    let session_id = match sessions::start(
+        db,
+        stream.id.as_str(),
+        started_at.unwrap_or_else(Utc::now),
+        &stream.title,
+        &stream.game_name,
+        stream.viewer_count as i32,
+        message_id,
+    )
+    .await
+    {
+        Ok(id) => Some(id),
+        Err(e) => {
+            error!(error = %e, "Failed to record stream session");
+            None
+        }
+    };
+
+    {
+        let mut state = live_state.write().await;
+        state.started_at = started_at;
+        state.session_id = session_id;
+        state.last_vars = Some(vars);
+    }
+
+    // Unlock #live-chat
+    if let Some(chat_channel) = twitch.config.live_chat_channel_id {
+        set_channel_locked(ctx, chat_channel, false).await;
+    }
+    if let Some(ref bridge) = twitch.chat_bridge {
+        bridge.set_live(true);
+    }
+
+    // Update bot status
+    match serenity::all::ActivityData::streaming(&stream.title, twitch.config.channel_url()) {
+        Ok(activity) => ctx.set_activity(Some(activity)),
+        Err(e) => error!(error = %e, "Failed to set streaming activity"),
+    }
+}
+
+async fn handle_stream_offline(
+    ctx: &SerenityContext,
+    twitch: &Arc<TwitchState>,
+    db: &PgPool,
+    live_state: &Arc<RwLock<LiveState>>,
+) {
+    let state = live_state.read().await;
+
+    if let Some(msg_id) = state.go_live_message_id {
+        let mut vars = state
+            .last_vars
+            .clone()
+            .unwrap_or_else(|| twitch.base_vars());
+        if let Some(started_at) = state.started_at {
+            vars.uptime = format_uptime(started_at);
+        }
+
+        let session = match state.session_id {
+            Some(id) => match sessions::finish(db, id).await {
+                Ok(session) => Some(session),
+                Err(e) => {
+                    error!(error = %e, session_id = id, "Failed to close stream session");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        if let Some(ref session) = session {
+            apply_session(&mut vars, session);
+        }
+
+        edit_ended_message(ctx, twitch, db, state.guild_id, msg_id, &vars).await;
+
+        // The VOD usually isn't listed the moment the stream ends, so keep
+        // looking for it in the background and fill it in when it appears.
+        if let Some(session) = session.filter(|s| s.vod_url.is_none()) {
+            spawn_vod_lookup(
+                ctx.clone(),
+                Arc::clone(twitch),
+                db.clone(),
+                state.guild_id,
+                msg_id,
+                session,
+                vars,
+            );
+        }
+    }
+
+    drop(state);
+
+    {
+        let mut state = live_state.write().await;
+        state.go_live_message_id = None;
+        state.started_at = None;
+        state.session_id = None;
+        state.last_vars = None;
+    }
+
+    // Lock #live-chat
+    if let Some(ref bridge) = twitch.chat_bridge {
+        bridge.set_live(false);
+    }
+    if let Some(chat_channel) = twitch.config.live_chat_channel_id {
+        set_channel_locked(ctx, chat_channel, true).await;
+    }
+
+    // Reset bot status
+    ctx.set_activity(Some(serenity::all::ActivityData::watching(
+        "the crimson tide",
+    )));
+}
+
+/// Render the ended template into the go-live message.
+async fn edit_ended_message(
+    ctx: &SerenityContext,
+    twitch: &TwitchState,
+    db: &PgPool,
+    guild_id: Option<GuildId>,
+    msg_id: MessageId,
+    vars: &StreamVars,
+) {
+    let template = load_template(db, guild_id, TemplateKind::Ended).await;
+    let rendered = template.render(TemplateKind::Ended, vars);
+
+    let mut edit = EditMessage::new().embed(rendered.embed);
+    if !rendered.content.is_empty() {
+        edit = edit.content(rendered.content);
+    }
+
+    if let Err(e) = twitch
+        .config
+        .live_channel_id
+        .edit_message(&ctx.http, msg_id, edit)
+        .await
+    {
+        error!(error = %e, "Failed to edit go-live message");
+    } else {
+        info!("Go-live message updated to stream ended");
+    }
+}
+
+/// Poll Helix for the session's VOD and add it to the ended message.
+fn spawn_vod_lookup(
+    ctx: SerenityContext,
+    twitch: Arc<TwitchState>,
+    db: PgPool,
+    guild_id: Option<GuildId>,
+    msg_id: MessageId,
+    session: sessions::StreamSession,
+    mut vars: StreamVars,
+) {
+    let Some(stream_id) = session.twitch_stream_id.clone() else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        for attempt in 1..=VOD_LOOKUP_ATTEMPTS {
+            match twitch.fetch_vod_url(&stream_id).await {
+                Ok(Some(url)) => {
+                    match sessions::set_vod_url(&db, session.id, &url).await {
+                        Ok(updated) => apply_session(&mut vars, &updated),
+                        Err(e) => {
+                            error!(error = %e, "Failed to store VOD URL");
+                            vars.vod = url;
+                        }
+                    }
+                    edit_ended_message(&ctx, &twitch, &db, guild_id, msg_id, &vars).await;
+                    info!(attempt, "VOD link added to stream-ended message");
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => warn!(error = %e, attempt, "VOD lookup failed"),
+            }
+
+            tokio::time::sleep(VOD_LOOKUP_INTERVAL).await;
+        }
+
+        info!(stream_id, "No VOD found for stream, giving up");
+    });
+}
+
+/// Copy a session's summary stats into template values.
+fn apply_session(vars: &mut StreamVars, session: &sessions::StreamSession) {
+    let bullets = |items: &[String]| {
+        items
+            .iter()
+            .map(|item| format!("• {item}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    vars.duration = text_templates::format_duration(session.duration());
+    vars.peak_viewers = session.peak_viewers.to_string();
+    vars.avg_viewers = session.average_viewers().to_string();
+    vars.titles = bullets(&session.titles);
+    vars.games = bullets(&session.games);
+    vars.vod = session.vod_url.clone().unwrap_or_default();
+}
+
+async fn handle_channel_update(
+    ctx: &SerenityContext,
+    twitch: &TwitchState,
+    db: &PgPool,
+    live_state: &Arc<RwLock<LiveState>>,
+    title: &str,
+    category_name: &str,
+) {
+    let state = live_state.read().await;
+    let msg_id = match state.go_live_message_id {
+        Some(id) => id,
+        None => return, // Not currently live, ignore
+    };
+    let guild_id = state.guild_id;
+    let session_id = state.session_id;
+    drop(state);
+
+    if let Some(id) = session_id {
+        if let Err(e) = sessions::record_update(db, id, title, category_name).await {
+            error!(error = %e, "Failed to record stream title/category change");
+        }
+    }
+
+    // Re-fetch for updated viewer count + thumbnail. The notification is the
+    // source of truth for title/category since Helix can lag behind it.
+    let mut vars = match twitch.fetch_stream_info().await {
+        Ok(Some(s)) => twitch.stream_vars(&s).await,
+        _ => StreamVars {
+            viewers: "0".into(),
+            ..twitch.base_vars()
+        },
+    };
+    vars.title = title.to_string();
+    vars.game = category_name.to_string();
+
+    let template = load_template(db, guild_id, TemplateKind::Update).await;
+    let rendered = template.render(TemplateKind::Update, &vars);
+
+    let mut edit = EditMessage::new().embed(rendered.embed);
+    if !rendered.content.is_empty() {
+        edit = edit.content(rendered.content);
+    }
+
+    if let Err(e) = twitch
+        .config
+        .live_channel_id
+        .edit_message(&ctx.http, msg_id, edit)
+        .await
+    {
+        error!(error = %e, "Failed to update go-live embed");
+    } else {
+        info!(
+            title,
+            category_name, "Go-live embed updated (channel.update)"
+        );
+    }
+
+    live_state.write().await.last_vars = Some(vars);
+
+    // Update bot status with new title
+    match serenity::all::ActivityData::streaming(title, twitch.config.channel_url()) {
+        Ok(activity) => ctx.set_activity(Some(activity)),
+        Err(e) => error!(error = %e, "Failed to set streaming activity"),
+    }
+}
+
+/// Load a stream template, falling back to the default if the lookup fails.
+async fn load_template(
+    db: &PgPool,
+    guild_id: Option<GuildId>,
+    kind: TemplateKind,
+) -> StreamTemplate {
+    match StreamTemplate::load(db, guild_id, kind).await {
+        Ok(template) => template,
+        Err(e) => {
+            error!(error = %e, ?kind, "Failed to load stream template, using default");
+            StreamTemplate::default_for(kind)
+        }
+    }
+}
+
+fn parse_timestamp(ts: &twitch_api::types::Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(ts.as_str())
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn format_uptime(started_at: DateTime<Utc>) -> String {
+    let elapsed = (Utc::now() - started_at).to_std().unwrap_or_default();
+    text_templates::format_duration(elapsed)
+}
+
+/// Periodically sample the live viewer count into the current session.
+fn spawn_viewer_sampler(twitch: Arc<TwitchState>, db: PgPool, live_state: Arc<RwLock<LiveState>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(VIEWER_SAMPLE_INTERVAL);
+        interval.tick().await; // The first tick fires immediately; go-live records the first sample
+
+        loop {
+            interval.tick().await;
+
+            let Some(session_id) = live_state.read().await.session_id else {
+                continue;
+            };
+
+            match twitch.fetch_stream_info().await {
+                Ok(Some(stream)) => {
+                    let viewers = stream.viewer_count as i32;
+                    if let Err(e) = sessions::record_viewers(&db, session_id, viewers).await {
+                        error!(error = %e, "Failed to record viewer sample");
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!(error = %e, "Failed to sample viewer count"),
+            }
+        }
+    });
+}
+
+// ─── Activity Alerts ─────────────────────────────────────────────────
+
+fn push_alert(
+    alerts: Option<&AlertQueue>,
+    kind: AlertKind,
+    user: String,
+    amount: i64,
+    tier: String,
+    message: String,
+) {
+    let Some(alerts) = alerts else {
+        return;
+    };
+    info!(?kind, user, amount, "Twitch activity alert queued");
+    alerts.push(Alert {
+        kind,
+        user,
+        amount,
+        tier,
+        message,
+    });
+}
+
+fn tier_name(tier: &SubscriptionTier) -> String {
+    match tier {
+        SubscriptionTier::Tier1 => "Tier 1".into(),
+        SubscriptionTier::Tier2 => "Tier 2".into(),
+        SubscriptionTier::Tier3 => "Tier 3".into(),
+        SubscriptionTier::Prime => "Prime".into(),
+        SubscriptionTier::Other(other) => other.clone(),
+    }
+}
+
+fn display_name_or_anonymous(
+    is_anonymous: bool,
+    name: Option<&twitch_api::types::DisplayName>,
+) -> String {
+    match name {
+        Some(name) if !is_anonymous => name.to_string(),
+        _ => "Anonymous".into(),
+    }
+}
+
+// ─── Channel Point Redemptions ───────────────────────────────────────
+
+async fn handle_redemption(
+    ctx: &SerenityContext,
+    twitch: &TwitchState,
+    db: &PgPool,
+    notif: &twitch_api::eventsub::channel::ChannelPointsCustomRewardRedemptionAddV1Payload,
+) {
+    let mapping = match redemptions::find_mapping(db, notif.reward.id.as_str()).await {
+        Ok(Some(mapping)) => mapping,
+        Ok(None) => {
+            info!(
+                reward_id = %notif.reward.id,
+                reward = %notif.reward.title,
+                "Redemption for unmapped reward, ignoring"
+            );
+            return;
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to look up reward mapping");
+            return;
+        }
+    };
+
+    let redemption = redemptions::Redemption {
+        reward_title: notif.reward.title.clone(),
+        twitch_user_id: notif.user_id.to_string(),
+        user_name: notif.user_name.to_string(),
+        user_input: notif.user_input.clone(),
+    };
+
+    let status = match redemptions::execute(&ctx.http, db, &mapping, &redemption).await {
+        Ok(summary) => {
+            info!(reward = %notif.reward.title, user = %notif.user_name, summary, "Redemption handled");
+            CustomRewardRedemptionStatus::Fulfilled
+        }
+        Err(e) => {
+            warn!(reward = %notif.reward.title, user = %notif.user_name, error = %e, "Redemption action failed, refunding");
+            CustomRewardRedemptionStatus::Canceled
+        }
+    };
+
+    // Rewards that skip the request queue are already fulfilled and can't be updated.
+    if notif.status != RedemptionStatus::Unfulfilled {
+        return;
+    }
+
+    if let Err(e) = twitch
+        .update_redemption_status(&notif.reward.id, &notif.id, status)
+        .await
+    {
+        error!(error = %e, "Failed to update redemption status on Twitch");
+    }
+}
+
+// ─── Main EventSub Loop ─────────────────────────────────────────────
+
+pub async fn start_eventsub(
+    ctx: SerenityContext,
+    twitch_config: TwitchConfig,
+    db: PgPool,
+    chat_bridge: Option<chat::ChatBridge>,
+    api: TwitchApi,
+    log_channel_id: Option<ChannelId>,
+    scheduler: Scheduler,
+) {
+    let alert = tokens::RevocationAlert {
+        http: ctx.http.clone(),
+        channel_id: log_channel_id,
+    };
+    let twitch = match TwitchState::new(&twitch_config, &db, chat_bridge, alert.clone()).await {
+        Ok(t) => Arc::new(t),
+        Err(e) => {
+            error!(error = %e, "Failed to initialize Twitch client");
+            return;
+        }
+    };
+    let _ = api.state.set(Arc::clone(&twitch));
+
+    twitch.spawn_refresh_loop(&db, &alert);
+
+    // Templates are stored per guild, so resolve which guild the go-live channel lives in.
+    let guild_id = match twitch_config.live_channel_id.to_channel(&ctx.http).await {
+        Ok(channel) => channel.guild().map(|gc| gc.guild_id),
+        Err(e) => {
+            warn!(error = %e, "Failed to resolve go-live channel guild, using default templates");
+            None
+        }
+    };
+
+    let live_state = Arc::new(RwLock::new(LiveState {
+        go_live_message_id: None,
+        guild_id,
+        started_at: None,
+        session_id: None,
+        last_vars: None,
+    }));
+
+    spawn_viewer_sampler(Arc::clone(&twitch), db.clone(), Arc::clone(&live_state));
+
+    if twitch.user_token.is_some() {
+        redemptions::register_temp_role_sweeper(&scheduler).await;
+    }
+
+    if let Some(guild_id) = guild_id {
+        schedule::register_event_sync(&scheduler, api, guild_id, twitch_config.channel_url()).await;
+    }
+
+    if let Some(channel_id) = twitch_config.clips_channel_id {
+        clips::register_clip_of_the_week(&scheduler, channel_id).await;
+    }
+
+    if let Some(ref bridge) = twitch.chat_bridge {
+        match twitch.user_token {
+            Some(ref user_token) => {
+                // The bot may start mid-stream, after stream.online has already fired.
+                if let Ok(Some(_)) = twitch.fetch_stream_info().await {
+                    bridge.set_live(true);
+                }
+                chat::spawn(
+                    ctx.clone(),
+                    bridge.clone(),
+                    Arc::clone(user_token),
+                    twitch_config.username.clone(),
+                );
+            }
+            None => warn!("Twitch chat bridge needs TWITCH_USER_ACCESS_TOKEN, skipping"),
+        }
+    }
+
+    let alerts = twitch_config
+        .activity_channel_id
+        .map(|channel_id| AlertQueue::spawn(ctx.http.clone(), db.clone(), guild_id, channel_id));
+
+    let mut url = TWITCH_EVENTSUB_URL.to_string();
+    loop {
+        let result =
+            run_eventsub_connection(&ctx, &twitch, &db, &live_state, alerts.as_ref(), &url).await;
+        twitch.eventsub_connected.store(false, Ordering::Relaxed);
+        metrics::record_twitch_reconnect("eventsub");
+
+        match result {
+            Ok(Some(reconnect_url)) => {
+                info!("Reconnecting to new EventSub URL...");
+                url = reconnect_url;
+            }
+            Ok(None) => {
+                info!("EventSub connection closed cleanly, reconnecting in 5s...");
+                url = TWITCH_EVENTSUB_URL.to_string();
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+            Err(e) => {
+                error!(error = %e, "EventSub connection error, reconnecting in 5s...");
+                url = TWITCH_EVENTSUB_URL.to_string();
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+/// Returns Ok(Some(url)) for reconnect, Ok(None) for clean close, Err for error.
+async fn run_eventsub_connection(
+    ctx: &SerenityContext,
+    twitch: &Arc<TwitchState>,
+    db: &PgPool,
+    live_state: &Arc<RwLock<LiveState>>,
+    alerts: Option<&AlertQueue>,
+    url: &str,
+) -> Result<Option<String>, String> {
+    let (ws_stream, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .map_err(|e| format!("WebSocket connect failed: {e}"))?;
+
+    info!("Connected to Twitch EventSub WebSocket");
+
+    let (mut write, mut read) = ws_stream.split();
+    let mut keepalive_timeout = std::time::Duration::from_secs(30);
+
+    while let Ok(msg) = tokio::time::timeout(keepalive_timeout, read.next()).await {
+        let text = match msg {
+            Some(Ok(WsMessage::Text(text))) => text,
+            Some(Ok(WsMessage::Close(_))) => {
+                info!("EventSub WebSocket closed by server");
+                return Ok(None);
+            }
+            Some(Ok(WsMessage::Ping(data))) => {
+                let _ = write.send(WsMessage::Pong(data)).await;
+                continue;
+            }
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(format!("WebSocket read error: {e}")),
+            None => return Err("WebSocket stream ended".into()),
+        };
+
+        let parsed = match Event::parse_websocket(&text) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(error = %e, "Failed to parse EventSub message");
+                continue;
+            }
+        };
+
+        match parsed {
+            EventsubWebsocketData::Welcome { payload, .. } => {
+                let session_id = payload.session.id.as_ref();
+
+                if let Some(timeout_secs) = payload.session.keepalive_timeout_seconds {
+                    keepalive_timeout = std::time::Duration::from_secs((timeout_secs as u64) + 5);
+                }
+
+                info!(session_id, "EventSub session established");
+                twitch.eventsub_connected.store(true, Ordering::Relaxed);
+
+                if let Err(e) = twitch.subscribe_events(session_id).await {
+                    error!(error = %e, "Failed to subscribe to events");
+                }
+            }
+
+            EventsubWebsocketData::Keepalive { .. } => {
+                // Server is alive, no action needed
+            }
+
+            EventsubWebsocketData::Notification { payload, .. } => match payload {
+                Event::StreamOnlineV1(Payload {
+                    message: Message::Notification(_notif),
+                    ..
+                }) => {
+                    info!("Stream went live!");
+                    handle_stream_online(ctx, twitch, db, live_state).await;
+                }
+                Event::StreamOfflineV1(Payload {
+                    message: Message::Notification(_notif),
+                    ..
+                }) => {
+                    info!("Stream went offline");
+                    handle_stream_offline(ctx, twitch, db, live_state).await;
+                }
+                Event::ChannelUpdateV2(Payload {
+                    message: Message::Notification(notif),
+                    ..
+                }) => {
+                    handle_channel_update(
+                        ctx,
+                        twitch,
+                        db,
+                        live_state,
+                        &notif.title,
+                        &notif.category_name,
+                    )
+                    .await;
+                }
+                Event::ChannelFollowV2(Payload {
+                    message: Message::Notification(notif),
+                    ..
+                }) => push_alert(
+                    alerts,
+                    AlertKind::Follow,
+                    notif.user_name.to_string(),
+                    1,
+                    String::new(),
+                    String::new(),
+                ),
+                Event::ChannelSubscribeV1(Payload {
+                    message: Message::Notification(notif),
+                    ..
+                }) => {
+                    // Gifted subs are announced once by channel.subscription.gift
+                    if !notif.is_gift {
+                        push_alert(
+                            alerts,
+                            AlertKind::Subscribe,
+                            notif.user_name.to_string(),
+                            1,
+                            tier_name(&notif.tier),
+                            String::new(),
+                        );
+                    }
+                }
+                Event::ChannelSubscriptionGiftV1(Payload {
+                    message: Message::Notification(notif),
+                    ..
+                }) => push_alert(
+                    alerts,
+                    AlertKind::GiftSub,
+                    display_name_or_anonymous(notif.is_anonymous, notif.user_name.as_ref()),
+                    notif.total,
+                    tier_name(&notif.tier),
+                    String::new(),
+                ),
+                Event::ChannelCheerV1(Payload {
+                    message: Message::Notification(notif),
+                    ..
+                }) => push_alert(
+                    alerts,
+                    AlertKind::Cheer,
+                    display_name_or_anonymous(notif.is_anonymous, notif.user_name.as_ref()),
+                    notif.bits,
+                    String::new(),
+                    notif.message.clone(),
+                ),
+                Event::ChannelRaidV1(Payload {
+                    message: Message::Notification(notif),
+                    ..
+                }) => push_alert(
+                    alerts,
+                    AlertKind::Raid,
+                    notif.from_broadcaster_user_name.to_string(),
+                    notif.viewers,
+                    String::new(),
+                    String::new(),
+                ),
+                Event::ChannelPointsCustomRewardRedemptionAddV1(Payload {
+                    message: Message::Notification(notif),
+                    ..
+                }) => {
+                    handle_redemption(ctx, twitch, db, &notif).await;
+                }
+                other => {
+                    warn!(event = ?other, "Unhandled EventSub notification");
+                }
+            },
+
+            EventsubWebsocketData::Reconnect { payload, .. } => {
+                if let Some(reconnect_url) = payload.session.reconnect_url.as_deref() {
+                    info!(url = reconnect_url, "EventSub requesting reconnect");
+                    return Ok(Some(reconnect_url.to_string()));
+                }
+                return Ok(None);
+            }
+
+            EventsubWebsocketData::Revocation { metadata, .. } => {
+                warn!(
+                    subscription_type = %metadata.subscription_type,
+                    "EventSub subscription revoked"
+                );
+            }
+
+            _ => {}
+        }
+    }
+
+    Err("Keepalive timeout — server stopped responding".into())
+}
@@ -0,0 +1,347 @@
+use super::links;
+use crate::scheduler::Scheduler;
+use crate::utils::{embeds, templates};
+use chrono::{DateTime, Utc};
+use serenity::all::{ChannelId, CreateMessage, GuildId, Http, Member, RoleId, UserId};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// Scheduler job that removes expired temporary roles, every minute.
+const TEMP_ROLE_SWEEP_JOB: &str = "temp_role_sweep";
+const TEMP_ROLE_SWEEP_CRON: &str = "* * * * *";
+
+const DEFAULT_SHOUTOUT: &str = "**{user}** redeemed **{reward}**! {input}";
+
+/// What the bot does in Discord when a mapped channel-point reward is redeemed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum RewardAction {
+    #[name = "Temporary role"]
+    TempRole,
+    Shoutout,
+    #[name = "Crimson Coins"]
+    Currency,
+}
+
+impl RewardAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RewardAction::TempRole => "temp_role",
+            RewardAction::Shoutout => "shoutout",
+            RewardAction::Currency => "currency",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "temp_role" => Some(RewardAction::TempRole),
+            "shoutout" => Some(RewardAction::Shoutout),
+            "currency" => Some(RewardAction::Currency),
+            _ => None,
+        }
+    }
+}
+
+/// A channel-point reward mapped to a Discord action.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RewardMapping {
+    pub reward_id: String,
+    pub guild_id: i64,
+    pub action: String,
+    pub role_id: Option<i64>,
+    pub duration_secs: Option<i32>,
+    pub channel_id: Option<i64>,
+    pub amount: Option<i32>,
+    pub message: Option<String>,
+}
+
+const MAPPING_COLUMNS: &str =
+    "reward_id, guild_id, action, role_id, duration_secs, channel_id, amount, message";
+
+/// Look up the mapping for a reward, if one is configured.
+pub async fn find_mapping(
+    db: &PgPool,
+    reward_id: &str,
+) -> Result<Option<RewardMapping>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {MAPPING_COLUMNS} FROM channel_point_rewards WHERE reward_id = $1"
+    ))
+    .bind(reward_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// All reward mappings configured for a guild.
+pub async fn list_mappings(
+    db: &PgPool,
+    guild_id: GuildId,
+) -> Result<Vec<RewardMapping>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {MAPPING_COLUMNS} FROM channel_point_rewards WHERE guild_id = $1 ORDER BY reward_id"
+    ))
+    .bind(guild_id.get() as i64)
+    .fetch_all(db)
+    .await
+}
+
+/// Insert or replace a reward mapping.
+///
+/// Returns `false` if the reward is already mapped in another guild, which
+/// is left alone.
+pub async fn save_mapping(db: &PgPool, mapping: &RewardMapping) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO channel_point_rewards \
+            (reward_id, guild_id, action, role_id, duration_secs, channel_id, amount, message) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+         ON CONFLICT (reward_id) DO UPDATE SET \
+            action = EXCLUDED.action, role_id = EXCLUDED.role_id, \
+            duration_secs = EXCLUDED.duration_secs, channel_id = EXCLUDED.channel_id, \
+            amount = EXCLUDED.amount, message = EXCLUDED.message \
+         WHERE channel_point_rewards.guild_id = EXCLUDED.guild_id",
+    )
+    .bind(&mapping.reward_id)
+    .bind(mapping.guild_id)
+    .bind(&mapping.action)
+    .bind(mapping.role_id)
+    .bind(mapping.duration_secs)
+    .bind(mapping.channel_id)
+    .bind(mapping.amount)
+    .bind(&mapping.message)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete a reward mapping. Returns `false` if there was nothing to delete.
+pub async fn remove_mapping(
+    db: &PgPool,
+    guild_id: GuildId,
+    reward_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM channel_point_rewards WHERE guild_id = $1 AND reward_id = $2")
+            .bind(guild_id.get() as i64)
+            .bind(reward_id)
+            .execute(db)
+            .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// The parts of a redemption event the Discord actions need.
+pub struct Redemption {
+    pub reward_title: String,
+    pub twitch_user_id: String,
+    pub user_name: String,
+    pub user_input: String,
+}
+
+/// Run the Discord action for a redemption.
+///
+/// Returns a short description of what was done, or why it couldn't be done
+/// (in which case the redemption should be refunded).
+pub async fn execute(
+    http: &Http,
+    db: &PgPool,
+    mapping: &RewardMapping,
+    redemption: &Redemption,
+) -> Result<String, String> {
+    let guild_id = GuildId::new(mapping.guild_id as u64);
+
+    match RewardAction::parse(&mapping.action) {
+        Some(RewardAction::TempRole) => {
+            let role_id = mapping
+                .role_id
+                .map(|id| RoleId::new(id as u64))
+                .ok_or("reward has no role configured")?;
+            let duration =
+                Duration::from_secs(mapping.duration_secs.unwrap_or(3600).max(60) as u64);
+
+            let member = find_member(http, db, guild_id, redemption).await?;
+            grant_temp_role(http, db, &member, role_id, duration).await?;
+
+            Ok(format!(
+                "granted <@&{role_id}> to {} for {}",
+                member.user.name,
+                templates::format_duration(duration)
+            ))
+        }
+        Some(RewardAction::Shoutout) => {
+            let channel_id = mapping
+                .channel_id
+                .map(|id| ChannelId::new(id as u64))
+                .ok_or("reward has no shoutout channel configured")?;
+            let template = mapping.message.as_deref().unwrap_or(DEFAULT_SHOUTOUT);
+            let text = templates::render(template, |key| match key {
+                "user" => Some(redemption.user_name.clone()),
+                "reward" => Some(redemption.reward_title.clone()),
+                "input" => Some(redemption.user_input.clone()),
+                _ => None,
+            });
+
+            let message = CreateMessage::new().embed(embeds::twitch_embed().description(text));
+            channel_id
+                .send_message(http, message)
+                .await
+                .map_err(|e| format!("failed to post shoutout: {e}"))?;
+
+            Ok(format!("posted shoutout in <#{channel_id}>"))
+        }
+        Some(RewardAction::Currency) => {
+            let amount = mapping
+                .amount
+                .ok_or("reward has no coin amount configured")?;
+            let member = find_member(http, db, guild_id, redemption).await?;
+
+            sqlx::query(
+                "INSERT INTO members (guild_id, user_id, currency_balance) VALUES ($1, $2, $3) \
+                 ON CONFLICT (guild_id, user_id) \
+                 DO UPDATE SET currency_balance = members.currency_balance + EXCLUDED.currency_balance",
+            )
+            .bind(guild_id.get() as i64)
+            .bind(member.user.id.get() as i64)
+            .bind(amount)
+            .execute(db)
+            .await
+            .map_err(|e| format!("failed to credit coins: {e}"))?;
+
+            Ok(format!("credited {amount} coins to {}", member.user.name))
+        }
+        None => Err(format!("unknown action '{}'", mapping.action)),
+    }
+}
+
+/// Resolve the Discord member a redemption is for.
+///
+/// A Discord username typed into the redemption input wins; otherwise the
+/// viewer's linked Discord account is used.
+async fn find_member(
+    http: &Http,
+    db: &PgPool,
+    guild_id: GuildId,
+    redemption: &Redemption,
+) -> Result<Member, String> {
+    let name = redemption.user_input.trim().trim_start_matches('@');
+    if name.is_empty() {
+        let link = links::find_by_twitch(db, &redemption.twitch_user_id)
+            .await
+            .map_err(|e| format!("failed to look up linked account: {e}"))?
+            .ok_or("no Discord username given and the viewer hasn't linked their account")?;
+        return guild_id
+            .member(http, UserId::new(link.discord_user_id as u64))
+            .await
+            .map_err(|e| format!("linked member not found in the server: {e}"));
+    }
+
+    let candidates = guild_id
+        .search_members(http, name, Some(10))
+        .await
+        .map_err(|e| format!("member search failed: {e}"))?;
+
+    candidates
+        .into_iter()
+        .find(|m| {
+            m.user.name.eq_ignore_ascii_case(name)
+                || m.user
+                    .global_name
+                    .as_deref()
+                    .is_some_and(|g| g.eq_ignore_ascii_case(name))
+                || m.nick
+                    .as_deref()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        })
+        .ok_or_else(|| format!("no member named '{name}' in the server"))
+}
+
+async fn grant_temp_role(
+    http: &Http,
+    db: &PgPool,
+    member: &Member,
+    role_id: RoleId,
+    duration: Duration,
+) -> Result<(), String> {
+    member
+        .add_role(http, role_id)
+        .await
+        .map_err(|e| format!("failed to add role: {e}"))?;
+
+    let expires_at = Utc::now()
+        + chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::hours(1));
+
+    // Redeeming again while the role is still active extends it.
+    sqlx::query(
+        "INSERT INTO temporary_roles (guild_id, user_id, role_id, expires_at) \
+         VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (guild_id, user_id, role_id) DO UPDATE SET expires_at = EXCLUDED.expires_at",
+    )
+    .bind(member.guild_id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .bind(role_id.get() as i64)
+    .bind(expires_at)
+    .execute(db)
+    .await
+    .map_err(|e| format!("failed to record temporary role: {e}"))?;
+
+    Ok(())
+}
+
+/// Periodically remove temporary roles whose time is up.
+pub async fn register_temp_role_sweeper(scheduler: &Scheduler) {
+    scheduler.register(TEMP_ROLE_SWEEP_JOB, |ctx, _job| async move {
+        sweep_temp_roles(&ctx.http, &ctx.db)
+            .await
+            .map_err(|e| format!("Failed to sweep temporary roles: {e}"))
+    });
+
+    if let Err(e) = scheduler
+        .ensure_recurring(
+            TEMP_ROLE_SWEEP_JOB,
+            TEMP_ROLE_SWEEP_JOB,
+            TEMP_ROLE_SWEEP_CRON,
+            None,
+            serde_json::Value::Null,
+        )
+        .await
+    {
+        error!(error = %e, "Failed to schedule temporary role sweep");
+    }
+}
+
+async fn sweep_temp_roles(http: &Http, db: &PgPool) -> Result<(), sqlx::Error> {
+    let expired: Vec<(i64, i64, i64, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT guild_id, user_id, role_id, expires_at FROM temporary_roles WHERE expires_at <= now()",
+    )
+    .fetch_all(db)
+    .await?;
+
+    for (guild_id, user_id, role_id, expires_at) in expired {
+        let guild = GuildId::new(guild_id as u64);
+        let user = UserId::new(user_id as u64);
+        let role = RoleId::new(role_id as u64);
+
+        // If the member left or the role is gone there's nothing to undo.
+        match http
+            .remove_member_role(guild, user, role, Some("Temporary role expired"))
+            .await
+        {
+            Ok(()) => info!(user_id, role_id, "Temporary role expired and removed"),
+            Err(e) => {
+                warn!(user_id, role_id, error = %e, "Failed to remove expired temporary role")
+            }
+        }
+
+        sqlx::query(
+            "DELETE FROM temporary_roles \
+             WHERE guild_id = $1 AND user_id = $2 AND role_id = $3 AND expires_at = $4",
+        )
+        .bind(guild_id)
+        .bind(user_id)
+        .bind(role_id)
+        .bind(expires_at)
+        .execute(db)
+        .await?;
+    }
+
+    Ok(())
+}
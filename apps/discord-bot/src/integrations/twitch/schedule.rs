@@ -0,0 +1,320 @@
+use super::{parse_timestamp, TwitchApi};
+use crate::scheduler::Scheduler;
+use crate::utils::embeds;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serenity::all::{
+    CreateEmbed, CreateEmbedFooter, CreateScheduledEvent, EditScheduledEvent, GuildId, Http,
+    ScheduledEventId, ScheduledEventType,
+};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use tracing::{error, info, warn};
+use twitch_api::helix::schedule::ScheduledBroadcasts;
+
+/// Scheduler job that mirrors the Twitch schedule into Discord Scheduled Events, hourly.
+const EVENT_SYNC_JOB: &str = "twitch_event_sync";
+const EVENT_SYNC_CRON: &str = "0 * * * *";
+
+/// Only segments starting within this window get a Discord event.
+const EVENT_SYNC_HORIZON_DAYS: i64 = 14;
+
+/// Maximum number of upcoming streams listed by `/schedule`.
+const MAX_LISTED: usize = 7;
+
+/// A single upcoming broadcast from the Twitch schedule.
+#[derive(Debug, Clone)]
+pub struct ScheduledStream {
+    pub segment_id: String,
+    pub title: String,
+    pub category: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl ScheduledStream {
+    fn display_title(&self) -> &str {
+        if self.title.is_empty() {
+            "Stream"
+        } else {
+            &self.title
+        }
+    }
+}
+
+/// The broadcaster's upcoming streams and any vacation.
+#[derive(Debug, Clone, Default)]
+pub struct StreamSchedule {
+    pub segments: Vec<ScheduledStream>,
+    pub vacation: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl StreamSchedule {
+    /// Convert a Helix schedule, dropping canceled occurrences.
+    pub(super) fn from_helix(schedule: ScheduledBroadcasts) -> Self {
+        let segments = schedule
+            .segments
+            .into_iter()
+            .filter_map(|segment| {
+                let start = parse_timestamp(&segment.start_time)?;
+                let end = parse_timestamp(&segment.end_time)?;
+
+                // Occurrences of a recurring segment before `canceled_until` were canceled.
+                let canceled = segment
+                    .canceled_until
+                    .as_ref()
+                    .and_then(parse_timestamp)
+                    .is_some_and(|until| start < until);
+                if canceled {
+                    return None;
+                }
+
+                Some(ScheduledStream {
+                    segment_id: segment.id.to_string(),
+                    title: segment.title,
+                    category: segment.category.map(|c| c.name),
+                    start,
+                    end,
+                })
+            })
+            .collect();
+
+        let vacation = schedule.vacation.and_then(|v| {
+            Some((
+                parse_timestamp(&v.start_time)?,
+                parse_timestamp(&v.end_time)?,
+            ))
+        });
+
+        Self { segments, vacation }
+    }
+}
+
+/// Embed listing upcoming streams.
+///
+/// Discord timestamps render in each viewer's own timezone.
+pub fn schedule_embed(schedule: &StreamSchedule, channel_url: &str) -> CreateEmbed {
+    let lines: Vec<String> = schedule
+        .segments
+        .iter()
+        .take(MAX_LISTED)
+        .map(|s| {
+            let start = s.start.timestamp();
+            let category = s
+                .category
+                .as_deref()
+                .map(|c| format!(" · *{c}*"))
+                .unwrap_or_default();
+            format!(
+                "<t:{start}:F> (<t:{start}:R>)\n**{}**{category}",
+                s.display_title()
+            )
+        })
+        .collect();
+
+    let mut embed = embeds::twitch_embed()
+        .title("Streaming Schedule")
+        .url(channel_url)
+        .description(lines.join("\n\n"))
+        .footer(CreateEmbedFooter::new("Times are shown in your timezone"));
+
+    if let Some((start, end)) = schedule.vacation {
+        embed = embed.field(
+            "On Break",
+            format!("<t:{}:D> – <t:{}:D>", start.timestamp(), end.timestamp()),
+            false,
+        );
+    }
+
+    embed
+}
+
+/// Embed shown when the Twitch schedule can't be fetched or is empty.
+pub fn fallback_embed(text: Option<&str>) -> CreateEmbed {
+    embeds::twitch_embed()
+        .title("Streaming Schedule")
+        .description(text.unwrap_or("Check back soon for the updated schedule!"))
+}
+
+// ─── Discord Scheduled Events ────────────────────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+struct MirroredEvent {
+    segment_id: String,
+    event_id: i64,
+    title: String,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
+
+/// Keep Discord Scheduled Events in step with the Twitch schedule.
+pub async fn register_event_sync(
+    scheduler: &Scheduler,
+    api: TwitchApi,
+    guild_id: GuildId,
+    channel_url: String,
+) {
+    scheduler.register(EVENT_SYNC_JOB, move |ctx, _job| {
+        let api = api.clone();
+        let channel_url = channel_url.clone();
+        async move {
+            let schedule = match api.get_schedule().await {
+                Ok(schedule) => schedule,
+                Err(e) => {
+                    warn!(error = %e, "Skipping scheduled event sync");
+                    return Ok(());
+                }
+            };
+
+            sync_events(&ctx.http, &ctx.db, guild_id, &schedule, &channel_url)
+                .await
+                .map_err(|e| format!("Failed to sync Discord scheduled events: {e}"))
+        }
+    });
+
+    if let Err(e) = scheduler
+        .ensure_recurring(
+            EVENT_SYNC_JOB,
+            EVENT_SYNC_JOB,
+            EVENT_SYNC_CRON,
+            Some(guild_id),
+            serde_json::Value::Null,
+        )
+        .await
+    {
+        error!(error = %e, "Failed to schedule Discord scheduled event sync");
+    }
+}
+
+async fn sync_events(
+    http: &Http,
+    db: &PgPool,
+    guild_id: GuildId,
+    schedule: &StreamSchedule,
+    channel_url: &str,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let horizon = now + ChronoDuration::days(EVENT_SYNC_HORIZON_DAYS);
+
+    let mirrored: Vec<MirroredEvent> = sqlx::query_as(
+        "SELECT segment_id, event_id, title, start_time, end_time \
+         FROM stream_schedule_events WHERE guild_id = $1",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(db)
+    .await?;
+
+    let upcoming: Vec<&ScheduledStream> = schedule
+        .segments
+        .iter()
+        .filter(|s| s.start > now && s.start < horizon)
+        .collect();
+    let current: HashSet<&str> = upcoming.iter().map(|s| s.segment_id.as_str()).collect();
+
+    // Events for segments that were removed or canceled on Twitch
+    for event in mirrored
+        .iter()
+        .filter(|e| !current.contains(e.segment_id.as_str()))
+    {
+        // Past events have run their course; only forget them.
+        if event.start_time > now {
+            let event_id = ScheduledEventId::new(event.event_id as u64);
+            if let Err(e) = guild_id.delete_scheduled_event(http, event_id).await {
+                warn!(error = %e, "Failed to delete scheduled event for removed segment");
+            }
+        }
+        remove_mirrored(db, guild_id, &event.segment_id).await?;
+    }
+
+    for segment in upcoming {
+        let existing = mirrored.iter().find(|e| e.segment_id == segment.segment_id);
+        let description = segment
+            .category
+            .as_deref()
+            .map(|c| format!("Streaming {c} on Twitch"))
+            .unwrap_or_else(|| "Live on Twitch".into());
+
+        let result = match existing {
+            Some(event)
+                if event.title == segment.display_title()
+                    && event.start_time == segment.start
+                    && event.end_time == segment.end =>
+            {
+                continue;
+            }
+            Some(event) => {
+                let edit = EditScheduledEvent::new()
+                    .name(segment.display_title())
+                    .description(description)
+                    .start_time(segment.start)
+                    .end_time(segment.end)
+                    .location(channel_url);
+                guild_id
+                    .edit_scheduled_event(http, ScheduledEventId::new(event.event_id as u64), edit)
+                    .await
+            }
+            None => {
+                let create = CreateScheduledEvent::new(
+                    ScheduledEventType::External,
+                    segment.display_title(),
+                    segment.start,
+                )
+                .description(description)
+                .end_time(segment.end)
+                .location(channel_url);
+                guild_id.create_scheduled_event(http, create).await
+            }
+        };
+
+        match result {
+            Ok(event) => save_mirrored(db, guild_id, segment, event.id).await?,
+            Err(e) => warn!(
+                error = %e,
+                segment_id = %segment.segment_id,
+                "Failed to mirror stream segment as a scheduled event"
+            ),
+        }
+    }
+
+    info!("Discord scheduled events synced with Twitch schedule");
+    Ok(())
+}
+
+async fn save_mirrored(
+    db: &PgPool,
+    guild_id: GuildId,
+    segment: &ScheduledStream,
+    event_id: ScheduledEventId,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO stream_schedule_events \
+            (guild_id, segment_id, event_id, title, start_time, end_time) \
+         VALUES ($1, $2, $3, $4, $5, $6) \
+         ON CONFLICT (guild_id, segment_id) DO UPDATE SET \
+            event_id = EXCLUDED.event_id, title = EXCLUDED.title, \
+            start_time = EXCLUDED.start_time, end_time = EXCLUDED.end_time",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(&segment.segment_id)
+    .bind(event_id.get() as i64)
+    .bind(segment.display_title())
+    .bind(segment.start)
+    .bind(segment.end)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+async fn remove_mirrored(
+    db: &PgPool,
+    guild_id: GuildId,
+    segment_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM stream_schedule_events WHERE guild_id = $1 AND segment_id = $2")
+        .bind(guild_id.get() as i64)
+        .bind(segment_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
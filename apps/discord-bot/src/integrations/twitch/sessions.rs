@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serenity::all::MessageId;
+use sqlx::PgPool;
+
+/// A single broadcast, tracked from `stream.online` to `stream.offline`.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StreamSession {
+    pub id: i64,
+    pub twitch_stream_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub peak_viewers: i32,
+    pub viewer_sample_count: i32,
+    pub viewer_sample_total: i64,
+    pub titles: Vec<String>,
+    pub games: Vec<String>,
+    pub vod_url: Option<String>,
+    pub notification_message_id: Option<i64>,
+}
+
+impl StreamSession {
+    /// Mean of all viewer samples taken during the session.
+    pub fn average_viewers(&self) -> i64 {
+        if self.viewer_sample_count == 0 {
+            return 0;
+        }
+        self.viewer_sample_total / i64::from(self.viewer_sample_count)
+    }
+
+    /// Wall-clock length of the session (up to now if it hasn't ended).
+    pub fn duration(&self) -> std::time::Duration {
+        let end = self.ended_at.unwrap_or_else(Utc::now);
+        (end - self.started_at).to_std().unwrap_or_default()
+    }
+}
+
+const SESSION_COLUMNS: &str = "id, twitch_stream_id, started_at, ended_at, peak_viewers, \
+    viewer_sample_count, viewer_sample_total, titles, games, vod_url, notification_message_id";
+
+/// Record the start of a broadcast and return the new session ID.
+///
+/// The go-live viewer count only seeds the peak: it's close to zero, so
+/// counting it as a sample would drag the average down.
+pub async fn start(
+    db: &PgPool,
+    twitch_stream_id: &str,
+    started_at: DateTime<Utc>,
+    title: &str,
+    game: &str,
+    viewers: i32,
+    message_id: Option<MessageId>,
+) -> Result<i64, sqlx::Error> {
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO stream_sessions \
+            (twitch_stream_id, started_at, title, game, peak_viewers, \
+             viewer_sample_count, viewer_sample_total, titles, games, notification_message_id) \
+         VALUES ($1, $2, $3, $4, $5, 0, 0, ARRAY[$3], ARRAY[$4], $6) \
+         RETURNING id",
+    )
+    .bind(twitch_stream_id)
+    .bind(started_at)
+    .bind(title)
+    .bind(game)
+    .bind(viewers)
+    .bind(message_id.map(|id| id.get() as i64))
+    .fetch_one(db)
+    .await?;
+
+    Ok(id)
+}
+
+/// Add a viewer-count sample, raising the peak if needed.
+pub async fn record_viewers(db: &PgPool, id: i64, viewers: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE stream_sessions SET \
+            peak_viewers = GREATEST(peak_viewers, $2), \
+            viewer_sample_count = viewer_sample_count + 1, \
+            viewer_sample_total = viewer_sample_total + $3 \
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(viewers)
+    .bind(i64::from(viewers))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Remember a title/category change, keeping each distinct value once.
+pub async fn record_update(
+    db: &PgPool,
+    id: i64,
+    title: &str,
+    game: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE stream_sessions SET \
+            title = $2, \
+            game = $3, \
+            titles = CASE WHEN $2 = ANY(titles) THEN titles ELSE array_append(titles, $2) END, \
+            games = CASE WHEN $3 = ANY(games) THEN games ELSE array_append(games, $3) END \
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(title)
+    .bind(game)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark the session as ended and return its final stats.
+pub async fn finish(db: &PgPool, id: i64) -> Result<StreamSession, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "UPDATE stream_sessions SET ended_at = now() WHERE id = $1 RETURNING {SESSION_COLUMNS}"
+    ))
+    .bind(id)
+    .fetch_one(db)
+    .await
+}
+
+/// Store the VOD link once Twitch has published it.
+pub async fn set_vod_url(
+    db: &PgPool,
+    id: i64,
+    vod_url: &str,
+) -> Result<StreamSession, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "UPDATE stream_sessions SET vod_url = $2 WHERE id = $1 RETURNING {SESSION_COLUMNS}"
+    ))
+    .bind(id)
+    .bind(vod_url)
+    .fetch_one(db)
+    .await
+}
@@ -0,0 +1,303 @@
+use crate::config::TwitchConfig;
+use crate::utils::{embeds, templates};
+use serenity::all::{CreateEmbed, GuildId, Mentionable};
+use sqlx::PgPool;
+
+/// Which stream notification a template renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum TemplateKind {
+    #[name = "Go live"]
+    GoLive,
+    #[name = "Stream update"]
+    Update,
+    #[name = "Stream ended"]
+    Ended,
+}
+
+impl TemplateKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TemplateKind::GoLive => "go_live",
+            TemplateKind::Update => "update",
+            TemplateKind::Ended => "ended",
+        }
+    }
+}
+
+/// A per-guild template for a stream notification message.
+///
+/// Every text field may contain placeholders, see [`StreamVars`].
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StreamTemplate {
+    pub content: String,
+    pub title: String,
+    pub description: String,
+    pub image_url: String,
+    pub thumbnail_url: String,
+    pub show_fields: bool,
+}
+
+impl StreamTemplate {
+    /// The built-in template used when a guild hasn't customised one.
+    pub fn default_for(kind: TemplateKind) -> Self {
+        match kind {
+            TemplateKind::GoLive => Self {
+                content: "{role}".into(),
+                title: "LIVE: {title}".into(),
+                description: String::new(),
+                image_url: "{thumbnail}".into(),
+                thumbnail_url: String::new(),
+                show_fields: true,
+            },
+            TemplateKind::Update => Self {
+                content: String::new(),
+                title: "LIVE: {title}".into(),
+                description: String::new(),
+                image_url: "{thumbnail}".into(),
+                thumbnail_url: String::new(),
+                show_fields: true,
+            },
+            TemplateKind::Ended => Self {
+                content: String::new(),
+                title: "STREAM ENDED".into(),
+                description: "Thanks for watching! See you next time.".into(),
+                image_url: String::new(),
+                thumbnail_url: String::new(),
+                show_fields: true,
+            },
+        }
+    }
+
+    /// Load the guild's template for `kind`, falling back to the default.
+    pub async fn load(
+        db: &PgPool,
+        guild_id: Option<GuildId>,
+        kind: TemplateKind,
+    ) -> Result<Self, sqlx::Error> {
+        let Some(guild_id) = guild_id else {
+            return Ok(Self::default_for(kind));
+        };
+
+        let row = sqlx::query_as::<_, Self>(
+            "SELECT content, title, description, image_url, thumbnail_url, show_fields \
+             FROM stream_templates WHERE guild_id = $1 AND kind = $2",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(kind.as_str())
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.unwrap_or_else(|| Self::default_for(kind)))
+    }
+
+    /// Insert or replace the guild's template for `kind`.
+    pub async fn save(
+        &self,
+        db: &PgPool,
+        guild_id: GuildId,
+        kind: TemplateKind,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO stream_templates \
+                (guild_id, kind, content, title, description, image_url, thumbnail_url, show_fields) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (guild_id, kind) DO UPDATE SET \
+                content = EXCLUDED.content, title = EXCLUDED.title, \
+                description = EXCLUDED.description, image_url = EXCLUDED.image_url, \
+                thumbnail_url = EXCLUDED.thumbnail_url, show_fields = EXCLUDED.show_fields",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(kind.as_str())
+        .bind(&self.content)
+        .bind(&self.title)
+        .bind(&self.description)
+        .bind(&self.image_url)
+        .bind(&self.thumbnail_url)
+        .bind(self.show_fields)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove the guild's customised template so the default applies again.
+    pub async fn reset(
+        db: &PgPool,
+        guild_id: GuildId,
+        kind: TemplateKind,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM stream_templates WHERE guild_id = $1 AND kind = $2")
+            .bind(guild_id.get() as i64)
+            .bind(kind.as_str())
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Render the template into message content and a Twitch-themed embed.
+    ///
+    /// When `show_fields` is set, live notifications get Game/Viewers fields
+    /// and the ended notification gets the session summary.
+    pub fn render(&self, kind: TemplateKind, vars: &StreamVars) -> RenderedTemplate {
+        let fill = |text: &str| templates::render(text, |key| vars.get(key));
+
+        let mut embed = embeds::twitch_embed()
+            .title(fill(&self.title))
+            .url(&vars.url);
+
+        let description = fill(&self.description);
+        if !description.is_empty() {
+            embed = embed.description(description);
+        }
+
+        let image = fill(&self.image_url);
+        if image.starts_with("http") {
+            embed = embed.image(image);
+        }
+
+        let thumbnail = fill(&self.thumbnail_url);
+        if thumbnail.starts_with("http") {
+            embed = embed.thumbnail(thumbnail);
+        }
+
+        if self.show_fields {
+            embed = match kind {
+                TemplateKind::GoLive | TemplateKind::Update => embed
+                    .field("Game", &vars.game, true)
+                    .field("Viewers", &vars.viewers, true),
+                TemplateKind::Ended => summary_fields(embed, vars),
+            };
+        }
+
+        RenderedTemplate {
+            content: fill(&self.content),
+            embed,
+        }
+    }
+}
+
+fn summary_fields(mut embed: CreateEmbed, vars: &StreamVars) -> CreateEmbed {
+    // Without a session only the uptime is known, and Discord rejects empty field values.
+    let duration = if vars.duration.is_empty() {
+        &vars.uptime
+    } else {
+        &vars.duration
+    };
+    let stats = [
+        ("Duration", duration),
+        ("Peak Viewers", &vars.peak_viewers),
+        ("Average Viewers", &vars.avg_viewers),
+    ];
+    for (name, value) in stats {
+        if !value.is_empty() {
+            embed = embed.field(name, value, true);
+        }
+    }
+
+    if !vars.titles.is_empty() {
+        embed = embed.field("Titles", truncate_field(&vars.titles), false);
+    }
+    if !vars.games.is_empty() {
+        embed = embed.field("Games", truncate_field(&vars.games), false);
+    }
+
+    let vod = if vars.vod.is_empty() {
+        "Processing — the link will appear here once Twitch publishes it.".to_string()
+    } else {
+        format!("[Watch the VOD]({})", vars.vod)
+    };
+    embed.field("VOD", vod, false)
+}
+
+/// Discord rejects embed field values over 1024 characters.
+fn truncate_field(value: &str) -> String {
+    const MAX: usize = 1024;
+    if value.chars().count() <= MAX {
+        return value.to_string();
+    }
+    let mut out: String = value.chars().take(MAX - 1).collect();
+    out.push('…');
+    out
+}
+
+/// A rendered stream template, ready to send or edit into a message.
+pub struct RenderedTemplate {
+    pub content: String,
+    pub embed: CreateEmbed,
+}
+
+/// Values substituted into stream templates.
+///
+/// Placeholders: `{title}`, `{game}`, `{viewers}`, `{uptime}`, `{url}`,
+/// `{box_art}`, `{thumbnail}`, `{role}`, plus the session summary
+/// `{duration}`, `{peak_viewers}`, `{avg_viewers}`, `{titles}`, `{games}`
+/// and `{vod}` (only filled in for the ended notification).
+#[derive(Debug, Clone, Default)]
+pub struct StreamVars {
+    pub title: String,
+    pub game: String,
+    pub viewers: String,
+    pub uptime: String,
+    pub url: String,
+    pub box_art: String,
+    pub thumbnail: String,
+    pub role: String,
+    pub duration: String,
+    pub peak_viewers: String,
+    pub avg_viewers: String,
+    pub titles: String,
+    pub games: String,
+    pub vod: String,
+}
+
+impl StreamVars {
+    /// Sample values used by `/twitch preview`.
+    pub fn sample(config: Option<&TwitchConfig>) -> Self {
+        let url = config
+            .map(TwitchConfig::channel_url)
+            .unwrap_or_else(|| "https://twitch.tv/0xDC143C".into());
+        let role = config
+            .and_then(|c| c.live_role_id)
+            .map(|r| r.mention().to_string())
+            .unwrap_or_else(|| "@Live".into());
+
+        Self {
+            title: "Building a Discord bot in Rust".into(),
+            game: "Software and Game Development".into(),
+            viewers: "42".into(),
+            uptime: "1h 23m".into(),
+            url,
+            box_art: "https://static-cdn.jtvnw.net/ttv-boxart/1469308723-285x380.jpg".into(),
+            thumbnail: "https://static-cdn.jtvnw.net/ttv-static/404_preview-440x248.jpg".into(),
+            role,
+            duration: "3h 12m".into(),
+            peak_viewers: "87".into(),
+            avg_viewers: "54".into(),
+            titles: "• Building a Discord bot in Rust\n• Q&A and code review".into(),
+            games: "• Software and Game Development\n• Just Chatting".into(),
+            vod: "https://www.twitch.tv/videos/1234567890".into(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "title" => &self.title,
+            "game" => &self.game,
+            "viewers" => &self.viewers,
+            "uptime" => &self.uptime,
+            "url" => &self.url,
+            "box_art" => &self.box_art,
+            "thumbnail" => &self.thumbnail,
+            "role" => &self.role,
+            "duration" => &self.duration,
+            "peak_viewers" => &self.peak_viewers,
+            "avg_viewers" => &self.avg_viewers,
+            "titles" => &self.titles,
+            "games" => &self.games,
+            "vod" => &self.vod,
+            _ => return None,
+        };
+        Some(value.clone())
+    }
+}
@@ -0,0 +1,427 @@
+use crate::config::TwitchConfig;
+use crate::utils::embeds;
+use chrono::{DateTime, Utc};
+use serenity::all::{ChannelId, CreateMessage, Http};
+use sqlx::PgPool;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use twitch_api::twitch_oauth2::tokens::errors::{
+    RefreshTokenError, RetrieveTokenError, ValidationError,
+};
+use twitch_api::twitch_oauth2::{
+    AccessToken, AppAccessToken, ClientId, ClientSecret, RefreshToken, RequestParseError, Scope,
+    TwitchToken, UserToken,
+};
+use twitch_api::HelixClient;
+
+/// Refresh this long before a token expires.
+const REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// Twitch asks apps to validate their tokens at least hourly.
+const VALIDATE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Minimum wait between refresh attempts.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Broadcaster scopes used by the activity alerts, redemptions and chat bridge.
+const USER_SCOPES: &[Scope] = &[
+    Scope::ModeratorReadFollowers,
+    Scope::ChannelReadSubscriptions,
+    Scope::BitsRead,
+    Scope::ChannelManageRedemptions,
+    Scope::ChatRead,
+    Scope::ChatEdit,
+];
+
+type Helix = HelixClient<'static, reqwest::Client>;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum TokenKind {
+    App,
+    User,
+}
+
+impl TokenKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TokenKind::App => "app",
+            TokenKind::User => "user",
+        }
+    }
+}
+
+/// A token type that can be stored in the `twitch_tokens` table.
+pub(super) trait PersistedToken: TwitchToken + Send + Sync + 'static {
+    const KIND: TokenKind;
+
+    fn stored_refresh_token(&self) -> Option<&RefreshToken>;
+
+    /// Replace the token with a fresh one.
+    ///
+    /// The error carries whether Twitch rejected the token for good.
+    fn renew<'a>(
+        &'a mut self,
+        helix: &'a Helix,
+        client_secret: &'a ClientSecret,
+    ) -> impl Future<Output = Result<(), (String, bool)>> + Send + 'a;
+}
+
+impl PersistedToken for AppAccessToken {
+    const KIND: TokenKind = TokenKind::App;
+
+    fn stored_refresh_token(&self) -> Option<&RefreshToken> {
+        self.refresh_token.as_ref()
+    }
+
+    /// Client-credentials tokens have no refresh token, so fetch a new one.
+    async fn renew(
+        &mut self,
+        helix: &Helix,
+        client_secret: &ClientSecret,
+    ) -> Result<(), (String, bool)> {
+        *self = AppAccessToken::get_app_access_token(
+            helix,
+            self.client_id().clone(),
+            client_secret.clone(),
+            vec![],
+        )
+        .await
+        .map_err(|e| (e.to_string(), false))?;
+        Ok(())
+    }
+}
+
+impl PersistedToken for UserToken {
+    const KIND: TokenKind = TokenKind::User;
+
+    fn stored_refresh_token(&self) -> Option<&RefreshToken> {
+        self.refresh_token.as_ref()
+    }
+
+    async fn renew(
+        &mut self,
+        helix: &Helix,
+        _client_secret: &ClientSecret,
+    ) -> Result<(), (String, bool)> {
+        self.refresh_token(helix)
+            .await
+            .map_err(|e| (e.to_string(), is_revoked(&e)))
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StoredToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: DateTime<Utc>,
+}
+
+async fn load(db: &PgPool, kind: TokenKind) -> Result<Option<StoredToken>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT access_token, refresh_token, expires_at FROM twitch_tokens WHERE kind = $1",
+    )
+    .bind(kind.as_str())
+    .fetch_optional(db)
+    .await
+}
+
+async fn save<T: PersistedToken>(db: &PgPool, token: &T) {
+    let expires_at = Utc::now()
+        + chrono::Duration::from_std(token.expires_in())
+            .unwrap_or_else(|_| chrono::Duration::zero());
+    let scopes: Vec<String> = token.scopes().iter().map(|s| s.to_string()).collect();
+
+    let result = sqlx::query(
+        "INSERT INTO twitch_tokens \
+            (kind, access_token, refresh_token, expires_at, login, user_id, scopes, updated_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, now()) \
+         ON CONFLICT (kind) DO UPDATE SET \
+            access_token = EXCLUDED.access_token, refresh_token = EXCLUDED.refresh_token, \
+            expires_at = EXCLUDED.expires_at, login = EXCLUDED.login, \
+            user_id = EXCLUDED.user_id, scopes = EXCLUDED.scopes, updated_at = now()",
+    )
+    .bind(T::KIND.as_str())
+    .bind(token.token().secret())
+    .bind(token.stored_refresh_token().map(|t| t.secret()))
+    .bind(expires_at)
+    .bind(token.login().map(|l| l.as_str()))
+    .bind(token.user_id().map(|u| u.as_str()))
+    .bind(&scopes)
+    .execute(db)
+    .await;
+
+    if let Err(e) = result {
+        error!(kind = T::KIND.as_str(), error = %e, "Failed to store Twitch token");
+    }
+}
+
+async fn forget(db: &PgPool, kind: TokenKind) {
+    if let Err(e) = sqlx::query("DELETE FROM twitch_tokens WHERE kind = $1")
+        .bind(kind.as_str())
+        .execute(db)
+        .await
+    {
+        error!(kind = kind.as_str(), error = %e, "Failed to remove stored Twitch token");
+    }
+}
+
+/// Whether Twitch rejected the refresh itself, as opposed to a network hiccup.
+fn is_revoked<RE: std::error::Error + Send + Sync + 'static>(e: &RefreshTokenError<RE>) -> bool {
+    match e {
+        RefreshTokenError::NoRefreshToken => true,
+        RefreshTokenError::RequestParseError(RequestParseError::TwitchError(response)) => {
+            matches!(response.status.as_u16(), 400 | 401 | 403)
+        }
+        _ => false,
+    }
+}
+
+// ─── Startup ─────────────────────────────────────────────────────────
+
+/// Reuse the stored app token while it's still valid, otherwise fetch a new one.
+pub(super) async fn app_token(
+    helix: &Helix,
+    db: &PgPool,
+    config: &TwitchConfig,
+) -> Result<AppAccessToken, String> {
+    let client_secret = ClientSecret::new(config.client_secret.clone());
+
+    match load(db, TokenKind::App).await {
+        Ok(Some(stored)) if stored.expires_at > Utc::now() + REFRESH_MARGIN => {
+            match AppAccessToken::from_existing(
+                helix,
+                AccessToken::new(stored.access_token),
+                None,
+                client_secret.clone(),
+            )
+            .await
+            {
+                Ok(token) if token.client_id().as_str() == config.client_id => {
+                    info!(expires_in = ?token.expires_in(), "Reusing stored Twitch app access token");
+                    return Ok(token);
+                }
+                Ok(_) => warn!("Stored Twitch app token belongs to another client, replacing it"),
+                Err(e) => warn!(error = %e, "Stored Twitch app token is invalid, replacing it"),
+            }
+        }
+        Ok(_) => {}
+        Err(e) => warn!(error = %e, "Failed to load stored Twitch app token"),
+    }
+
+    let token = AppAccessToken::get_app_access_token(
+        helix,
+        ClientId::new(config.client_id.clone()),
+        client_secret,
+        vec![],
+    )
+    .await
+    .map_err(|e| format!("Failed to get Twitch app access token: {e}"))?;
+
+    info!(expires_in = ?token.expires_in(), "Twitch app access token acquired");
+    save(db, &token).await;
+    Ok(token)
+}
+
+/// Load the broadcaster user token, preferring the stored one.
+///
+/// Twitch rotates refresh tokens, so after the first refresh the tokens in
+/// the environment are stale and only the stored pair still works. The
+/// environment pair is used to seed storage (or to recover after re-authorising).
+pub(super) async fn user_token(
+    helix: &Helix,
+    db: &PgPool,
+    config: &TwitchConfig,
+    alert: &RevocationAlert,
+) -> Option<UserToken> {
+    let mut candidates = Vec::new();
+    match load(db, TokenKind::User).await {
+        Ok(Some(stored)) => candidates.push(("stored", stored.access_token, stored.refresh_token)),
+        Ok(None) => {}
+        Err(e) => warn!(error = %e, "Failed to load stored Twitch user token"),
+    }
+    if let Some(ref access_token) = config.user_access_token {
+        candidates.push((
+            "configured",
+            access_token.clone(),
+            config.user_refresh_token.clone(),
+        ));
+    }
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let mut revoked = false;
+    for (source, access_token, refresh_token) in candidates {
+        match validate_user_token(helix, config, access_token, refresh_token).await {
+            Ok(token) => {
+                info!(source, login = %token.login, "Twitch broadcaster user token loaded");
+                check_scopes(&token);
+                save(db, &token).await;
+                return Some(token);
+            }
+            Err((e, token_revoked)) => {
+                warn!(source, error = %e, "Twitch user token unusable");
+                revoked |= token_revoked;
+                // A network error or Twitch outage says nothing about the token itself.
+                if source == "stored" && token_revoked {
+                    forget(db, TokenKind::User).await;
+                }
+            }
+        }
+    }
+
+    if revoked {
+        alert
+            .send(
+                "The broadcaster's Twitch authorization was revoked or has expired. Follow, \
+                 sub, cheer and raid alerts, channel-point rewards and the chat bridge are \
+                 disabled until the broadcaster re-authorizes the bot and \
+                 `TWITCH_USER_ACCESS_TOKEN` / `TWITCH_USER_REFRESH_TOKEN` are updated.",
+            )
+            .await;
+    }
+    None
+}
+
+/// Validate an access token, refreshing it if it has expired.
+///
+/// The error carries whether Twitch rejected the token outright.
+async fn validate_user_token(
+    helix: &Helix,
+    config: &TwitchConfig,
+    access_token: String,
+    refresh_token: Option<String>,
+) -> Result<UserToken, (String, bool)> {
+    let access_token = AccessToken::new(access_token);
+    let client_secret = ClientSecret::new(config.client_secret.clone());
+
+    match refresh_token {
+        Some(refresh_token) => UserToken::from_existing_or_refresh_token(
+            helix,
+            access_token,
+            RefreshToken::new(refresh_token),
+            ClientId::new(config.client_id.clone()),
+            client_secret,
+        )
+        .await
+        .map_err(|e| {
+            let revoked =
+                matches!(e, RetrieveTokenError::RefreshTokenError(ref r) if is_revoked(r));
+            (e.to_string(), revoked)
+        }),
+        None => UserToken::from_existing(helix, access_token, None, client_secret)
+            .await
+            .map_err(|e| {
+                let revoked = matches!(e, ValidationError::NotAuthorized);
+                (e.to_string(), revoked)
+            }),
+    }
+}
+
+/// Warn about scopes the broadcaster token is missing.
+fn check_scopes(token: &UserToken) {
+    let missing: Vec<String> = USER_SCOPES
+        .iter()
+        .filter(|scope| !token.scopes().contains(scope))
+        .map(|scope| scope.to_string())
+        .collect();
+
+    if !missing.is_empty() {
+        warn!(
+            missing = %missing.join(", "),
+            "Twitch user token is missing scopes; the matching features stay disabled"
+        );
+    }
+}
+
+// ─── Refresh ─────────────────────────────────────────────────────────
+
+/// Where to report a token that can no longer be refreshed.
+#[derive(Clone)]
+pub(super) struct RevocationAlert {
+    pub http: Arc<Http>,
+    pub channel_id: Option<ChannelId>,
+}
+
+impl RevocationAlert {
+    async fn send(&self, description: &str) {
+        let Some(channel_id) = self.channel_id else {
+            return;
+        };
+
+        let embed = embeds::warning_embed()
+            .title("Twitch Authorization Needed")
+            .description(description);
+        if let Err(e) = channel_id
+            .send_message(&self.http, CreateMessage::new().embed(embed))
+            .await
+        {
+            error!(error = %e, "Failed to post Twitch token alert");
+        }
+    }
+}
+
+/// Keep a token fresh: validate it hourly and refresh it shortly before it
+/// expires (or as soon as Twitch stops accepting it), storing each new token.
+///
+/// Stops with a log-channel alert if Twitch rejects the refresh.
+pub(super) fn spawn_refresh<T: PersistedToken>(
+    helix: Helix,
+    token: Arc<RwLock<T>>,
+    client_secret: ClientSecret,
+    db: PgPool,
+    alert: RevocationAlert,
+) {
+    let kind = T::KIND.as_str();
+
+    tokio::spawn(async move {
+        loop {
+            let wait = {
+                let t = token.read().await;
+                t.expires_in()
+                    .saturating_sub(REFRESH_MARGIN)
+                    .min(VALIDATE_INTERVAL)
+                    .max(RETRY_INTERVAL)
+            };
+            tokio::time::sleep(wait).await;
+
+            let needs_refresh = {
+                let t = token.read().await;
+                t.expires_in() <= REFRESH_MARGIN
+                    || matches!(
+                        t.validate_token(&helix).await,
+                        Err(ValidationError::NotAuthorized)
+                    )
+            };
+            if !needs_refresh {
+                continue;
+            }
+
+            let mut t = token.write().await;
+            match t.renew(&helix, &client_secret).await {
+                Ok(()) => {
+                    info!(kind, expires_in = ?t.expires_in(), "Twitch access token refreshed");
+                    save(&db, &*t).await;
+                }
+                Err((e, true)) => {
+                    error!(kind, error = %e, "Twitch refresh token was rejected, giving up");
+                    forget(&db, T::KIND).await;
+                    alert
+                        .send(&format!(
+                            "Twitch rejected the {kind} token refresh (`{e}`). Features that \
+                             depend on it will stop working until the bot is re-authorized \
+                             and restarted."
+                        ))
+                        .await;
+                    return;
+                }
+                Err((e, false)) => {
+                    error!(kind, error = %e, "Failed to refresh Twitch token, retrying in 60s");
+                }
+            }
+        }
+    });
+}
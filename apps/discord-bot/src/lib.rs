@@ -1,10 +1,22 @@
+pub mod boosts;
 pub mod commands;
 pub mod config;
 pub mod db;
 pub mod error;
 pub mod events;
+pub mod http;
 pub mod integrations;
+pub mod members;
+pub mod metrics;
+pub mod persistence;
+pub mod raid;
+pub mod scheduler;
+pub mod screening;
+pub mod stats;
+pub mod tickets;
 pub mod utils;
+pub mod verification;
+pub mod welcome;
 
 use sqlx::PgPool;
 
@@ -13,6 +25,14 @@ pub struct Data {
     pub db: PgPool,
     pub config: config::Config,
     pub start_time: std::time::Instant,
+    pub chat_bridge: Option<integrations::twitch::chat::ChatBridge>,
+    pub twitch_api: integrations::twitch::TwitchApi,
+    pub github_api: integrations::github::api::GithubApi,
+    pub autolinker: integrations::github::autolink::AutoLinker,
+    pub scheduler: scheduler::Scheduler,
+    pub raid_monitor: raid::RaidMonitor,
+    /// `None` when the card font or background couldn't be loaded.
+    pub welcome_cards: Option<welcome::card::WelcomeCards>,
 }
 
 /// Poise context alias used throughout the bot.
@@ -1,7 +1,9 @@
 use discord_bot::commands;
 use discord_bot::config::Config;
 use discord_bot::events;
+use discord_bot::http;
 use discord_bot::integrations;
+use discord_bot::metrics;
 use discord_bot::Data;
 use poise::serenity_prelude as serenity;
 use tracing::{error, info, warn};
@@ -39,7 +41,7 @@ async fn main() {
         }
     };
 
-    let intents = serenity::GatewayIntents::GUILDS
+    let mut intents = serenity::GatewayIntents::GUILDS
         | serenity::GatewayIntents::GUILD_MEMBERS
         | serenity::GatewayIntents::GUILD_MESSAGES
         | serenity::GatewayIntents::GUILD_MESSAGE_REACTIONS
@@ -47,6 +49,40 @@ async fn main() {
         | serenity::GatewayIntents::DIRECT_MESSAGES
         | serenity::GatewayIntents::MESSAGE_CONTENT;
 
+    // Presences are a privileged intent, so only ask for them when they're used.
+    if config.streaming_role_id.is_some() {
+        intents |= serenity::GatewayIntents::GUILD_PRESENCES;
+        info!("Streaming role enabled, requesting presence intent");
+    }
+
+    // Twitch chat ↔ #live-chat bridge, shared between the Twitch task and the message handler
+    let chat_bridge = config
+        .twitch
+        .as_ref()
+        .and_then(|t| t.live_chat_channel_id)
+        .map(integrations::twitch::chat::ChatBridge::new);
+    let twitch_api = integrations::twitch::TwitchApi::default();
+    let github_api = integrations::github::api::GithubApi::new(config.github.token.as_deref());
+    let scheduler = discord_bot::scheduler::Scheduler::new(db.clone());
+    let welcome_cards = match discord_bot::welcome::card::WelcomeCards::load(&config.welcome_card) {
+        Ok(cards) => Some(cards),
+        Err(e) => {
+            warn!(error = %e, "Welcome cards are unavailable, welcome messages will use embeds");
+            None
+        }
+    };
+    let shutdown_scheduler = scheduler.clone();
+
+    // Captured before `config` moves into the framework setup
+    let http_port = config.http_port;
+    let http_db = db.clone();
+    let http_twitch = config.twitch.is_some().then(|| twitch_api.clone());
+    let webhook = config
+        .github
+        .webhook_secret
+        .clone()
+        .map(|secret| (config.github.clone(), secret));
+
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
             commands: vec![
@@ -56,10 +92,38 @@ async fn main() {
                 commands::general::schedule(),
                 commands::general::server(),
                 commands::general::help(),
+                commands::twitch::twitch(),
+                commands::link::link(),
+                commands::link::unlink(),
+                commands::link::whois(),
+                commands::members::userinfo(),
+                commands::clips::clip(),
+                commands::github::repo(),
+                commands::github::issues(),
+                commands::github::commits(),
+                commands::github::pr(),
+                commands::github::defaultrepo(),
+                commands::github::autolink(),
+                commands::jobs::jobs(),
+                commands::welcome::welcome(),
+                commands::verification::verification(),
+                commands::raid::raid(),
+                commands::screening::screening(),
+                commands::persistence::persistence(),
+                commands::boosts::boosts(),
+                commands::boosts::boostrole(),
+                commands::stats::statchannel(),
+                commands::tickets::ticket(),
             ],
             event_handler: |ctx, event, _framework, data| {
                 Box::pin(async move {
+                    metrics::record_gateway_event(event.snake_case_name());
                     events::member::handle_event(ctx, event, data).await;
+                    events::chat_bridge::handle_event(ctx, event, data).await;
+                    events::interactions::handle_event(ctx, event, data).await;
+                    events::presence::handle_event(ctx, event, data).await;
+                    events::autolink::handle_event(ctx, event, data).await;
+                    events::tickets::handle_event(ctx, event, data).await;
                     Ok(())
                 })
             },
@@ -67,6 +131,7 @@ async fn main() {
                 Box::pin(async move {
                     match error {
                         poise::FrameworkError::Command { error, ctx, .. } => {
+                            metrics::record_command(&ctx.command().qualified_name, false, None);
                             let embed = discord_bot::utils::embeds::error_embed()
                                 .title("Error")
                                 .description(error.user_message());
@@ -87,6 +152,24 @@ async fn main() {
                     }
                 })
             },
+            pre_command: |ctx| {
+                Box::pin(async move {
+                    ctx.set_invocation_data(std::time::Instant::now()).await;
+                })
+            },
+            post_command: |ctx| {
+                Box::pin(async move {
+                    let started = ctx
+                        .invocation_data::<std::time::Instant>()
+                        .await
+                        .map(|s| *s);
+                    metrics::record_command(
+                        &ctx.command().qualified_name,
+                        true,
+                        started.map(|s| s.elapsed()),
+                    );
+                })
+            },
             ..Default::default()
         })
         .setup(move |ctx, ready, framework| {
@@ -110,13 +193,40 @@ async fn main() {
                 // Set bot status
                 ctx.set_activity(Some(serenity::ActivityData::watching("the crimson tide")));
 
+                // Jobs registered later (e.g. by the Twitch integration) are picked up as they're added
+                discord_bot::verification::register_timeout_job(&scheduler, config.log_channel_id);
+                discord_bot::screening::register_daily_summary(&scheduler, config.log_channel_id)
+                    .await;
+                discord_bot::tickets::register_inactivity_job(&scheduler, config.log_channel_id)
+                    .await;
+                discord_bot::stats::register_stat_channels(
+                    &scheduler,
+                    discord_bot::stats::StatSources::new(ctx.cache.clone(), twitch_api.clone()),
+                )
+                .await;
+                scheduler.start(ctx.http.clone()).await;
+
                 // Start Twitch EventSub if configured
                 if let Some(ref twitch_config) = config.twitch {
                     info!("Starting Twitch EventSub integration...");
                     let twitch_ctx = ctx.clone();
                     let twitch_cfg = twitch_config.clone();
+                    let twitch_db = db.clone();
+                    let twitch_chat = chat_bridge.clone();
+                    let api = twitch_api.clone();
+                    let log_channel_id = config.log_channel_id;
+                    let twitch_scheduler = scheduler.clone();
                     tokio::spawn(async move {
-                        integrations::twitch::start_eventsub(twitch_ctx, twitch_cfg).await;
+                        integrations::twitch::start_eventsub(
+                            twitch_ctx,
+                            twitch_cfg,
+                            twitch_db,
+                            twitch_chat,
+                            api,
+                            log_channel_id,
+                            twitch_scheduler,
+                        )
+                        .await;
                     });
                 } else {
                     info!("Twitch integration not configured, skipping");
@@ -126,6 +236,13 @@ async fn main() {
                     db,
                     config,
                     start_time: std::time::Instant::now(),
+                    chat_bridge,
+                    twitch_api,
+                    github_api,
+                    autolinker: integrations::github::autolink::AutoLinker::default(),
+                    scheduler,
+                    raid_monitor: discord_bot::raid::RaidMonitor::default(),
+                    welcome_cards,
                 })
             })
         })
@@ -136,11 +253,29 @@ async fn main() {
         .await
         .expect("Failed to create Discord client");
 
+    // Health checks, metrics and the GitHub webhook
+    let mut routes = axum::Router::new();
+    if let Some((github_config, secret)) = webhook {
+        routes = routes.merge(integrations::github::webhook::router(
+            client.http.clone(),
+            github_config,
+            secret,
+        ));
+        info!("GitHub webhook receiver enabled");
+    }
+    let http_state = http::HttpState {
+        db: http_db,
+        shard_manager: client.shard_manager.clone(),
+        twitch_api: http_twitch,
+    };
+    tokio::spawn(http::serve(http_port, http_state, routes));
+
     // Graceful shutdown on SIGINT/SIGTERM
     let shard_manager = client.shard_manager.clone();
     tokio::spawn(async move {
         shutdown_signal().await;
         info!("Shutdown signal received, stopping bot...");
+        shutdown_scheduler.shutdown().await;
         shard_manager.shutdown_all().await;
     });
 
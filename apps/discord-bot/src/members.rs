@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use serenity::all::{GuildId, UserId};
+use sqlx::PgPool;
+
+/// Join and leave events shown by `/userinfo`.
+const MAX_HISTORY: i64 = 10;
+
+/// Whether a history entry is a join or a leave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberEvent {
+    Join,
+    Leave,
+}
+
+impl MemberEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemberEvent::Join => "join",
+            MemberEvent::Leave => "leave",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "join" => Some(MemberEvent::Join),
+            "leave" => Some(MemberEvent::Leave),
+            _ => None,
+        }
+    }
+}
+
+/// A member's row in `members`, as far as the bot has seen them.
+#[derive(Debug, sqlx::FromRow)]
+pub struct MemberRecord {
+    /// Start of their current (or last) stay.
+    pub join_date: Option<DateTime<Utc>>,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub join_count: i32,
+    pub left_at: Option<DateTime<Utc>>,
+    pub message_count: i32,
+}
+
+impl MemberRecord {
+    /// Times they came back after leaving.
+    pub fn rejoins(&self) -> i32 {
+        (self.join_count - 1).max(0)
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct HistoryEntry {
+    pub event: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl HistoryEntry {
+    pub fn event(&self) -> Option<MemberEvent> {
+        MemberEvent::parse(&self.event)
+    }
+}
+
+/// Record a join, counting it as a rejoin if they were here before.
+/// Returns how many times they've joined, including this one.
+pub async fn record_join(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    joined_at: DateTime<Utc>,
+) -> Result<i32, sqlx::Error> {
+    let join_count = sqlx::query_scalar(
+        "INSERT INTO members (guild_id, user_id, join_date, first_seen, join_count) \
+         VALUES ($1, $2, $3, $3, 1) \
+         ON CONFLICT (guild_id, user_id) DO UPDATE SET \
+            join_date = EXCLUDED.join_date, \
+            first_seen = COALESCE(members.first_seen, EXCLUDED.first_seen), \
+            join_count = members.join_count + 1, left_at = NULL \
+         RETURNING join_count",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .bind(joined_at)
+    .fetch_one(db)
+    .await?;
+
+    record_history(db, guild_id, user_id, MemberEvent::Join).await?;
+    Ok(join_count)
+}
+
+pub async fn record_leave(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO members (guild_id, user_id, first_seen, left_at) VALUES ($1, $2, now(), now()) \
+         ON CONFLICT (guild_id, user_id) DO UPDATE SET \
+            first_seen = COALESCE(members.first_seen, EXCLUDED.first_seen), left_at = now()",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .execute(db)
+    .await?;
+
+    record_history(db, guild_id, user_id, MemberEvent::Leave).await
+}
+
+async fn record_history(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    event: MemberEvent,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO member_history (guild_id, user_id, event) VALUES ($1, $2, $3)")
+        .bind(guild_id.get() as i64)
+        .bind(user_id.get() as i64)
+        .bind(event.as_str())
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Count a message towards the member's total.
+pub async fn count_message(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO members (guild_id, user_id, first_seen, message_count) VALUES ($1, $2, now(), 1) \
+         ON CONFLICT (guild_id, user_id) DO UPDATE SET \
+            first_seen = COALESCE(members.first_seen, EXCLUDED.first_seen), \
+            message_count = members.message_count + 1",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn find(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<MemberRecord>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT join_date, first_seen, join_count, left_at, message_count \
+         FROM members WHERE guild_id = $1 AND user_id = $2",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .fetch_optional(db)
+    .await
+}
+
+/// The member's most recent joins and leaves, newest first.
+pub async fn history(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT event, created_at FROM member_history \
+         WHERE guild_id = $1 AND user_id = $2 ORDER BY created_at DESC LIMIT $3",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .bind(MAX_HISTORY)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn warning_count(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM warnings WHERE guild_id = $1 AND user_id = $2")
+        .bind(guild_id.get() as i64)
+        .bind(user_id.get() as i64)
+        .fetch_one(db)
+        .await
+}
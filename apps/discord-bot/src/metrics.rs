@@ -0,0 +1,108 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Prometheus metrics served on `/metrics`.
+///
+/// Kept in a process-wide registry so gateway, command and Twitch code can
+/// record without threading a handle through every call.
+struct Metrics {
+    registry: Registry,
+    commands: IntCounterVec,
+    command_duration: HistogramVec,
+    gateway_events: IntCounterVec,
+    twitch_reconnects: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(|| {
+    let registry =
+        Registry::new_custom(Some("discord_bot".into()), None).expect("metric prefix is valid");
+
+    let commands = IntCounterVec::new(
+        Opts::new(
+            "commands_total",
+            "Slash and prefix commands run, by outcome",
+        ),
+        &["command", "outcome"],
+    )
+    .expect("metric definition is valid");
+    let command_duration = HistogramVec::new(
+        HistogramOpts::new(
+            "command_duration_seconds",
+            "Time from invocation to completion of successful commands",
+        )
+        .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+        &["command"],
+    )
+    .expect("metric definition is valid");
+    let gateway_events = IntCounterVec::new(
+        Opts::new("gateway_events_total", "Discord gateway events received"),
+        &["event"],
+    )
+    .expect("metric definition is valid");
+    let twitch_reconnects = IntCounterVec::new(
+        Opts::new(
+            "twitch_reconnects_total",
+            "Twitch EventSub and chat reconnects",
+        ),
+        &["connection"],
+    )
+    .expect("metric definition is valid");
+
+    for collector in [
+        Box::new(commands.clone()) as Box<dyn prometheus::core::Collector>,
+        Box::new(command_duration.clone()),
+        Box::new(gateway_events.clone()),
+        Box::new(twitch_reconnects.clone()),
+    ] {
+        registry
+            .register(collector)
+            .expect("metrics are registered once");
+    }
+
+    Metrics {
+        registry,
+        commands,
+        command_duration,
+        gateway_events,
+        twitch_reconnects,
+    }
+});
+
+/// Count a finished command and, if it succeeded, how long it took.
+pub fn record_command(command: &str, succeeded: bool, duration: Option<Duration>) {
+    let outcome = if succeeded { "success" } else { "error" };
+    METRICS
+        .commands
+        .with_label_values(&[command, outcome])
+        .inc();
+    if let Some(duration) = duration {
+        METRICS
+            .command_duration
+            .with_label_values(&[command])
+            .observe(duration.as_secs_f64());
+    }
+}
+
+pub fn record_gateway_event(event: &str) {
+    METRICS.gateway_events.with_label_values(&[event]).inc();
+}
+
+/// `connection` is `eventsub` or `chat`.
+pub fn record_twitch_reconnect(connection: &str) {
+    METRICS
+        .twitch_reconnects
+        .with_label_values(&[connection])
+        .inc();
+}
+
+/// All metrics in the Prometheus text format.
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&METRICS.registry.gather(), &mut buffer) {
+        tracing::error!(error = %e, "Failed to encode metrics");
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
@@ -0,0 +1,265 @@
+use crate::Data;
+use chrono::{DateTime, Utc};
+use serenity::all::{Context, EditMember, GuildId, Member, RoleId, UserId};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+/// A guild's role persistence settings.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PersistenceConfig {
+    pub enabled: bool,
+    /// Roles never saved or restored, e.g. temporary or event roles.
+    pub excluded_role_ids: Vec<i64>,
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            excluded_role_ids: Vec::new(),
+        }
+    }
+}
+
+impl PersistenceConfig {
+    /// Load the guild's settings, falling back to the defaults.
+    pub async fn load(db: &PgPool, guild_id: GuildId) -> Result<Self, sqlx::Error> {
+        let row = sqlx::query_as::<_, Self>(
+            "SELECT enabled, excluded_role_ids FROM role_persistence_config WHERE guild_id = $1",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.unwrap_or_default())
+    }
+
+    pub async fn save(&self, db: &PgPool, guild_id: GuildId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO role_persistence_config (guild_id, enabled, excluded_role_ids) \
+             VALUES ($1, $2, $3) \
+             ON CONFLICT (guild_id) DO UPDATE SET \
+                enabled = EXCLUDED.enabled, excluded_role_ids = EXCLUDED.excluded_role_ids",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(self.enabled)
+        .bind(&self.excluded_role_ids)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn excluded_roles(&self) -> impl Iterator<Item = RoleId> + '_ {
+        self.excluded_role_ids
+            .iter()
+            .map(|id| RoleId::new(*id as u64))
+    }
+
+    fn persists(&self, guild_id: GuildId, role_id: RoleId) -> bool {
+        role_id != guild_id.everyone_role()
+            && !self.excluded_role_ids.contains(&(role_id.get() as i64))
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PersistedMember {
+    role_ids: Vec<i64>,
+    nickname: Option<String>,
+    muted_until: Option<DateTime<Utc>>,
+}
+
+/// Save a departing member's roles, nickname and running timeout.
+///
+/// `member` is only available when the member was cached; without it there's
+/// nothing to save.
+pub async fn handle_leave(
+    data: &Data,
+    guild_id: GuildId,
+    user_id: UserId,
+    member: Option<&Member>,
+) {
+    let Some(member) = member else {
+        warn!(guild_id = %guild_id, user_id = %user_id, "Departing member wasn't cached, their roles can't be saved");
+        return;
+    };
+    if member.user.bot {
+        return;
+    }
+
+    let config = match PersistenceConfig::load(&data.db, guild_id).await {
+        Ok(config) if config.enabled => config,
+        Ok(_) => return,
+        Err(e) => {
+            error!(error = %e, "Failed to load role persistence settings");
+            return;
+        }
+    };
+
+    let role_ids: Vec<i64> = member
+        .roles
+        .iter()
+        .filter(|role_id| config.persists(guild_id, **role_id))
+        .map(|role_id| role_id.get() as i64)
+        .collect();
+    let muted_until = member
+        .communication_disabled_until
+        .and_then(|until| DateTime::from_timestamp(until.unix_timestamp(), 0))
+        .filter(|until| *until > Utc::now());
+
+    if role_ids.is_empty() && member.nick.is_none() && muted_until.is_none() {
+        return;
+    }
+
+    let saved = sqlx::query(
+        "INSERT INTO persisted_members (guild_id, user_id, role_ids, nickname, muted_until) \
+         VALUES ($1, $2, $3, $4, $5) \
+         ON CONFLICT (guild_id, user_id) DO UPDATE SET \
+            role_ids = EXCLUDED.role_ids, nickname = EXCLUDED.nickname, \
+            muted_until = EXCLUDED.muted_until, left_at = now()",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .bind(&role_ids)
+    .bind(&member.nick)
+    .bind(muted_until)
+    .execute(&data.db)
+    .await;
+
+    match saved {
+        Ok(_) => {
+            info!(user = %member.user.name, roles = role_ids.len(), "Saved roles of departing member")
+        }
+        Err(e) => error!(error = %e, "Failed to save roles of departing member"),
+    }
+}
+
+/// Give a returning member back what they had when they left.
+///
+/// Returns `true` if one of the auto-roles verification grants was restored,
+/// meaning they were let in before and don't need to verify again. Other
+/// restored roles don't count, so a saved cosmetic role can't skip the gate.
+pub async fn restore(ctx: &Context, member: &Member, data: &Data) -> bool {
+    let guild_id = member.guild_id;
+
+    let config = match PersistenceConfig::load(&data.db, guild_id).await {
+        Ok(config) if config.enabled => config,
+        Ok(_) => return false,
+        Err(e) => {
+            error!(error = %e, "Failed to load role persistence settings");
+            return false;
+        }
+    };
+
+    let saved: Option<PersistedMember> = match sqlx::query_as(
+        "DELETE FROM persisted_members WHERE guild_id = $1 AND user_id = $2 \
+         RETURNING role_ids, nickname, muted_until",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .fetch_optional(&data.db)
+    .await
+    {
+        Ok(saved) => saved,
+        Err(e) => {
+            error!(error = %e, "Failed to load saved roles");
+            return false;
+        }
+    };
+    let Some(saved) = saved else {
+        return false;
+    };
+
+    let roles: Vec<RoleId> = saved
+        .role_ids
+        .iter()
+        .map(|id| RoleId::new(*id as u64))
+        .filter(|role_id| config.persists(guild_id, *role_id))
+        .filter(|role_id| assignable(ctx, guild_id, *role_id))
+        .collect();
+
+    let muted_until = saved.muted_until.filter(|until| *until > Utc::now());
+    let mut edit = EditMember::new().audit_log_reason("Restoring returning member");
+    if let Some(ref nickname) = saved.nickname {
+        edit = edit.nickname(nickname);
+    }
+    if let Some(until) = muted_until {
+        edit = edit.disable_communication_until(until.to_rfc3339());
+    }
+
+    let mut with_roles = edit.clone();
+    if !roles.is_empty() {
+        // Keep anything given since they joined, e.g. by another bot.
+        let mut all = member.roles.clone();
+        all.extend(
+            roles
+                .iter()
+                .filter(|role_id| !member.roles.contains(role_id)),
+        );
+        with_roles = with_roles.roles(all);
+    }
+
+    let mut edited = member.clone();
+    if let Err(e) = edited.edit(&ctx.http, with_roles).await {
+        // One bad role fails the whole edit, so fall back to a role at a time.
+        warn!(user = %member.user.name, error = %e, "Failed to restore member, retrying roles one by one");
+        for role_id in &roles {
+            if let Err(e) = member.add_role(&ctx.http, *role_id).await {
+                warn!(role_id = %role_id, error = %e, "Failed to restore role");
+            }
+        }
+        if saved.nickname.is_some() || muted_until.is_some() {
+            if let Err(e) = edited.edit(&ctx.http, edit).await {
+                warn!(user = %member.user.name, error = %e, "Failed to restore nickname or timeout");
+            }
+        }
+    }
+
+    info!(
+        user = %member.user.name,
+        roles = roles.len(),
+        muted = muted_until.is_some(),
+        "Restored returning member"
+    );
+    roles
+        .iter()
+        .any(|role_id| data.config.autorole_ids.contains(role_id))
+}
+
+/// Whether the bot can give out `role_id`: it still exists, isn't managed by
+/// an integration, and sits below the bot's highest role. Assumed so when the
+/// guild isn't cached.
+fn assignable(ctx: &Context, guild_id: GuildId, role_id: RoleId) -> bool {
+    let Some(guild) = ctx.cache.guild(guild_id) else {
+        return true;
+    };
+    let Some(role) = guild.roles.get(&role_id) else {
+        return false;
+    };
+    let bot_position = guild
+        .members
+        .get(&ctx.cache.current_user().id)
+        .and_then(|bot| guild.member_highest_role(bot))
+        .map(|top| top.position);
+
+    !role.managed && bot_position.is_none_or(|position| role.position < position)
+}
+
+/// Forget a former member's saved roles. Returns `false` if none were saved.
+pub async fn forget(db: &PgPool, guild_id: GuildId, user_id: UserId) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM persisted_members WHERE guild_id = $1 AND user_id = $2")
+        .bind(guild_id.get() as i64)
+        .bind(user_id.get() as i64)
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Former members with saved roles.
+pub async fn saved_count(db: &PgPool, guild_id: GuildId) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM persisted_members WHERE guild_id = $1")
+        .bind(guild_id.get() as i64)
+        .fetch_one(db)
+        .await
+}
@@ -0,0 +1,453 @@
+use super::{RaidConfig, RAID_PREFIX};
+use crate::events::member;
+use crate::utils::embeds;
+use crate::Data;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ChannelId, Context, CreateActionRow, CreateAllowedMentions, CreateButton,
+    CreateEmbedFooter, CreateMessage, EditGuild, EditMessage, GuildId, Http, Mentionable,
+    MessageId, PermissionOverwrite, PermissionOverwriteType, Permissions, User, UserId,
+    VerificationLevel,
+};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use tracing::{error, info, warn};
+
+/// What `@everyone` loses in locked channels.
+const LOCKED: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::CREATE_PUBLIC_THREADS)
+    .union(Permissions::CREATE_PRIVATE_THREADS)
+    .union(Permissions::ADD_REACTIONS);
+
+/// Raid accounts listed in the alert.
+const MAX_LISTED: usize = 10;
+
+/// A channel's `@everyone` overwrite before it was locked; `None`s mean there wasn't one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LockedChannel {
+    channel_id: u64,
+    allow: Option<u64>,
+    deny: Option<u64>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct LockdownRow {
+    previous_verification_level: Option<i16>,
+    locked_channels: Json<Vec<LockedChannel>>,
+    user_ids: Vec<i64>,
+    held_user_ids: Vec<i64>,
+    alert_channel_id: Option<i64>,
+    alert_message_id: Option<i64>,
+}
+
+/// An active lockdown, for `/raid status`.
+#[derive(Debug, sqlx::FromRow)]
+pub struct LockdownStatus {
+    pub reason: String,
+    pub started_at: DateTime<Utc>,
+    /// Raid joins plus members who joined during the lockdown.
+    pub raid_members: i32,
+    pub held_members: i32,
+}
+
+pub async fn status(db: &PgPool, guild_id: GuildId) -> Result<Option<LockdownStatus>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT reason, started_at, cardinality(user_ids) AS raid_members, \
+            cardinality(held_user_ids) AS held_members \
+         FROM raid_lockdowns WHERE guild_id = $1",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_optional(db)
+    .await
+}
+
+/// Hold a member who joined during a lockdown. Returns whether one is active.
+pub async fn hold(db: &PgPool, guild_id: GuildId, user_id: UserId) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE raid_lockdowns SET user_ids = array_append(user_ids, $2), \
+            held_user_ids = array_append(held_user_ids, $2) \
+         WHERE guild_id = $1",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Lock the guild down: raise the verification level, lock the configured
+/// channels, hold new members and alert moderators.
+///
+/// Returns `false` if a lockdown was already active, in which case
+/// `user_ids` are just added to it.
+pub async fn start(
+    http: &Http,
+    data: &Data,
+    guild_id: GuildId,
+    config: &RaidConfig,
+    reason: &str,
+    user_ids: &[UserId],
+) -> Result<bool, String> {
+    let ids: Vec<i64> = user_ids.iter().map(|id| id.get() as i64).collect();
+    let inserted = sqlx::query(
+        "INSERT INTO raid_lockdowns (guild_id, reason, user_ids) VALUES ($1, $2, $3) \
+         ON CONFLICT (guild_id) DO NOTHING",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(reason)
+    .bind(&ids)
+    .execute(&data.db)
+    .await
+    .map_err(|e| format!("Failed to record lockdown: {e}"))?
+    .rows_affected()
+        > 0;
+
+    if !inserted {
+        sqlx::query(
+            "UPDATE raid_lockdowns SET user_ids = array_cat(user_ids, $2) WHERE guild_id = $1",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(&ids)
+        .execute(&data.db)
+        .await
+        .map_err(|e| format!("Failed to record raid members: {e}"))?;
+        return Ok(false);
+    }
+
+    let mut actions =
+        vec!["New members are held without roles until the lockdown ends".to_string()];
+
+    let previous_level = match raise_verification_level(http, guild_id).await {
+        Ok(Some(previous)) => {
+            actions.push("Raised the server verification level to Highest".into());
+            Some(i16::from(u8::from(previous)))
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!(error = %e, "Failed to raise verification level");
+            actions.push("⚠️ Couldn't raise the verification level".into());
+            None
+        }
+    };
+
+    let mut locked = Vec::new();
+    for channel_id in config.lock_channels() {
+        match lock_channel(http, guild_id, channel_id).await {
+            Ok(channel) => locked.push(channel),
+            Err(e) => {
+                warn!(channel_id = %channel_id, error = %e, "Failed to lock channel");
+                actions.push(format!("⚠️ Couldn't lock {}", channel_id.mention()));
+            }
+        }
+    }
+    if !locked.is_empty() {
+        let mentions: Vec<String> = locked
+            .iter()
+            .map(|c| ChannelId::new(c.channel_id).mention().to_string())
+            .collect();
+        actions.push(format!("Locked {}", mentions.join(", ")));
+    }
+
+    sqlx::query(
+        "UPDATE raid_lockdowns SET previous_verification_level = $2, locked_channels = $3 \
+         WHERE guild_id = $1",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(previous_level)
+    .bind(Json(&locked))
+    .execute(&data.db)
+    .await
+    .map_err(|e| format!("Failed to record lockdown changes: {e}"))?;
+
+    if let Some(channel_id) = config.alert_channel(data.config.log_channel_id) {
+        send_alert(
+            http, &data.db, guild_id, channel_id, config, reason, user_ids, &actions,
+        )
+        .await;
+    }
+
+    info!(guild_id = %guild_id, reason, "Lockdown started");
+    Ok(true)
+}
+
+/// End the lockdown and undo its changes.
+///
+/// With `ban`, everyone who joined in the raid or during the lockdown is
+/// banned; otherwise held members are let in as if they had just joined.
+/// Returns a summary of what was done.
+pub async fn end(
+    ctx: &Context,
+    data: &Data,
+    guild_id: GuildId,
+    moderator: &User,
+    ban: bool,
+) -> Result<String, String> {
+    let row: Option<LockdownRow> = sqlx::query_as(
+        "DELETE FROM raid_lockdowns WHERE guild_id = $1 \
+         RETURNING previous_verification_level, locked_channels, user_ids, held_user_ids, \
+            alert_channel_id, alert_message_id",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_optional(&data.db)
+    .await
+    .map_err(|e| format!("Failed to end lockdown: {e}"))?;
+    let Some(row) = row else {
+        return Err("There's no lockdown to end.".into());
+    };
+
+    let http = &ctx.http;
+    let mut summary = Vec::new();
+
+    if ban {
+        let reason = format!("Raid (lockdown ended by {})", moderator.name);
+        let (mut banned, mut failed) = (0, 0);
+        let unique: HashSet<i64> = row.user_ids.iter().copied().collect();
+        for id in unique {
+            let user_id = UserId::new(id as u64);
+            match guild_id.ban_with_reason(http, user_id, 1, &reason).await {
+                Ok(()) => banned += 1,
+                Err(e) => {
+                    warn!(user_id = %user_id, error = %e, "Failed to ban raid member");
+                    failed += 1;
+                }
+            }
+        }
+        summary.push(format!("Banned **{banned}** raid accounts"));
+        if failed > 0 {
+            summary.push(format!("⚠️ {failed} couldn't be banned"));
+        }
+    }
+
+    if let Some(level) = row.previous_verification_level {
+        let level = VerificationLevel::from(level as u8);
+        match guild_id
+            .edit(
+                http,
+                EditGuild::new()
+                    .verification_level(level)
+                    .audit_log_reason("Lockdown ended"),
+            )
+            .await
+        {
+            Ok(_) => summary.push("Restored the server verification level".into()),
+            Err(e) => {
+                warn!(error = %e, "Failed to restore verification level");
+                summary.push("⚠️ Couldn't restore the verification level".into());
+            }
+        }
+    }
+
+    let mut unlocked = 0;
+    for channel in &row.locked_channels.0 {
+        match unlock_channel(http, guild_id, channel).await {
+            Ok(()) => unlocked += 1,
+            Err(e) => {
+                warn!(channel_id = channel.channel_id, error = %e, "Failed to unlock channel");
+                summary.push(format!(
+                    "⚠️ Couldn't unlock {}",
+                    ChannelId::new(channel.channel_id).mention()
+                ));
+            }
+        }
+    }
+    if unlocked > 0 {
+        summary.push(format!("Unlocked {unlocked} channel(s)"));
+    }
+
+    if !ban && !row.held_user_ids.is_empty() {
+        let mut admitted = 0;
+        for id in &row.held_user_ids {
+            // Members who already left are skipped.
+            if let Ok(held) = guild_id.member(http, UserId::new(*id as u64)).await {
+                member::admit(ctx, &held, data).await;
+                admitted += 1;
+            }
+        }
+        summary.push(format!("Let in **{admitted}** held member(s)"));
+    }
+
+    let summary = if summary.is_empty() {
+        "Nothing needed undoing.".to_string()
+    } else {
+        summary.join("\n")
+    };
+
+    if let (Some(channel_id), Some(message_id)) = (row.alert_channel_id, row.alert_message_id) {
+        let channel_id = ChannelId::new(channel_id as u64);
+        let message_id = MessageId::new(message_id as u64);
+        if let Err(e) = channel_id
+            .edit_message(http, message_id, EditMessage::new().components(vec![]))
+            .await
+        {
+            warn!(error = %e, "Failed to remove lockdown alert buttons");
+        }
+
+        let embed = embeds::success_embed()
+            .title("Lockdown Ended")
+            .description(format!("Ended by {}.\n\n{summary}", moderator.mention()));
+        let message = CreateMessage::new()
+            .embed(embed)
+            .reference_message((channel_id, message_id));
+        if let Err(e) = channel_id.send_message(http, message).await {
+            error!(error = %e, "Failed to post lockdown summary");
+        }
+    }
+
+    info!(guild_id = %guild_id, moderator = %moderator.name, ban, "Lockdown ended");
+    Ok(summary)
+}
+
+/// Raise the verification level to Highest. Returns the previous level if it changed.
+async fn raise_verification_level(
+    http: &Http,
+    guild_id: GuildId,
+) -> Result<Option<VerificationLevel>, serenity::Error> {
+    let previous = guild_id.to_partial_guild(http).await?.verification_level;
+    if previous >= VerificationLevel::Higher {
+        return Ok(None);
+    }
+
+    guild_id
+        .edit(
+            http,
+            EditGuild::new()
+                .verification_level(VerificationLevel::Higher)
+                .audit_log_reason("Raid lockdown"),
+        )
+        .await?;
+    Ok(Some(previous))
+}
+
+async fn lock_channel(
+    http: &Http,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<LockedChannel, String> {
+    let channel = channel_id
+        .to_channel(http)
+        .await
+        .map_err(|e| e.to_string())?
+        .guild()
+        .ok_or("not a server channel")?;
+
+    let everyone = PermissionOverwriteType::Role(guild_id.everyone_role());
+    let existing = channel
+        .permission_overwrites
+        .iter()
+        .find(|overwrite| overwrite.kind == everyone);
+    let (allow, deny) = existing
+        .map(|overwrite| (overwrite.allow, overwrite.deny))
+        .unwrap_or_default();
+
+    channel_id
+        .create_permission(
+            http,
+            PermissionOverwrite {
+                allow: allow - LOCKED,
+                deny: deny | LOCKED,
+                kind: everyone,
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(LockedChannel {
+        channel_id: channel_id.get(),
+        allow: existing.map(|overwrite| overwrite.allow.bits()),
+        deny: existing.map(|overwrite| overwrite.deny.bits()),
+    })
+}
+
+async fn unlock_channel(
+    http: &Http,
+    guild_id: GuildId,
+    channel: &LockedChannel,
+) -> Result<(), serenity::Error> {
+    let channel_id = ChannelId::new(channel.channel_id);
+    let everyone = PermissionOverwriteType::Role(guild_id.everyone_role());
+
+    match (channel.allow, channel.deny) {
+        (Some(allow), Some(deny)) => {
+            channel_id
+                .create_permission(
+                    http,
+                    PermissionOverwrite {
+                        allow: Permissions::from_bits_truncate(allow),
+                        deny: Permissions::from_bits_truncate(deny),
+                        kind: everyone,
+                    },
+                )
+                .await
+        }
+        _ => channel_id.delete_permission(http, everyone).await,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn send_alert(
+    http: &Http,
+    db: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    config: &RaidConfig,
+    reason: &str,
+    user_ids: &[UserId],
+    actions: &[String],
+) {
+    let mut accounts: Vec<String> = user_ids
+        .iter()
+        .take(MAX_LISTED)
+        .map(|id| id.mention().to_string())
+        .collect();
+    if user_ids.len() > MAX_LISTED {
+        accounts.push(format!("…and {} more", user_ids.len() - MAX_LISTED));
+    }
+
+    let embed = embeds::moderation_embed()
+        .title("🚨 Raid Detected — Lockdown Active")
+        .description(reason)
+        .field("Actions", actions.join("\n"), false)
+        .field("Raid Accounts", accounts.join(" "), false)
+        .footer(CreateEmbedFooter::new(
+            "Members who join during the lockdown are added to the list.",
+        ));
+    let buttons = vec![
+        CreateButton::new(format!("{RAID_PREFIX}:end"))
+            .label("End lockdown")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{RAID_PREFIX}:ban"))
+            .label("Ban raid accounts & end")
+            .style(ButtonStyle::Danger),
+    ];
+
+    let mut message = CreateMessage::new()
+        .embed(embed)
+        .components(vec![CreateActionRow::Buttons(buttons)]);
+    if let Some(role_id) = config.alert_role() {
+        message = message
+            .content(role_id.mention().to_string())
+            .allowed_mentions(CreateAllowedMentions::new().roles(vec![role_id]));
+    }
+
+    match channel_id.send_message(http, message).await {
+        Ok(message) => {
+            let saved = sqlx::query(
+                "UPDATE raid_lockdowns SET alert_channel_id = $2, alert_message_id = $3 \
+                 WHERE guild_id = $1",
+            )
+            .bind(guild_id.get() as i64)
+            .bind(channel_id.get() as i64)
+            .bind(message.id.get() as i64)
+            .execute(db)
+            .await;
+            if let Err(e) = saved {
+                warn!(error = %e, "Failed to record lockdown alert");
+            }
+        }
+        Err(e) => error!(error = %e, "Failed to send raid alert"),
+    }
+}
@@ -0,0 +1,312 @@
+pub mod lockdown;
+
+use crate::utils::embeds;
+use crate::Data;
+use serenity::all::{
+    ChannelId, ComponentInteraction, Context, CreateInteractionResponse,
+    CreateInteractionResponseMessage, EditInteractionResponse, GuildId, Member, Permissions,
+    RoleId, User, UserId,
+};
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Custom ID prefix of the alert buttons: `raid:end` and `raid:ban`.
+pub const RAID_PREFIX: &str = "raid";
+
+/// Shortest shared name prefix for two names to count as look-alikes.
+const SIMILAR_PREFIX_LEN: usize = 5;
+
+/// A guild's raid detection settings.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RaidConfig {
+    pub enabled: bool,
+    /// Joins within `window_seconds` that count as a raid.
+    pub join_threshold: i32,
+    pub window_seconds: i32,
+    /// Accounts younger than this are suspicious.
+    pub account_age_days: i32,
+    /// Where alerts go; `None` uses the log channel.
+    pub alert_channel_id: Option<i64>,
+    /// Role pinged by alerts.
+    pub alert_role_id: Option<i64>,
+    /// Channels locked during a lockdown.
+    pub lock_channel_ids: Vec<i64>,
+}
+
+/// Detection is opt-in, like verification and screening.
+impl Default for RaidConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            join_threshold: 10,
+            window_seconds: 30,
+            account_age_days: 7,
+            alert_channel_id: None,
+            alert_role_id: None,
+            lock_channel_ids: Vec::new(),
+        }
+    }
+}
+
+impl RaidConfig {
+    /// Load the guild's settings, falling back to the defaults.
+    pub async fn load(db: &PgPool, guild_id: GuildId) -> Result<Self, sqlx::Error> {
+        let row = sqlx::query_as::<_, Self>(
+            "SELECT enabled, join_threshold, window_seconds, account_age_days, \
+                alert_channel_id, alert_role_id, lock_channel_ids \
+             FROM raid_config WHERE guild_id = $1",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.unwrap_or_default())
+    }
+
+    pub async fn save(&self, db: &PgPool, guild_id: GuildId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO raid_config (guild_id, enabled, join_threshold, window_seconds, \
+                account_age_days, alert_channel_id, alert_role_id, lock_channel_ids) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (guild_id) DO UPDATE SET \
+                enabled = EXCLUDED.enabled, join_threshold = EXCLUDED.join_threshold, \
+                window_seconds = EXCLUDED.window_seconds, \
+                account_age_days = EXCLUDED.account_age_days, \
+                alert_channel_id = EXCLUDED.alert_channel_id, \
+                alert_role_id = EXCLUDED.alert_role_id, \
+                lock_channel_ids = EXCLUDED.lock_channel_ids",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(self.enabled)
+        .bind(self.join_threshold)
+        .bind(self.window_seconds)
+        .bind(self.account_age_days)
+        .bind(self.alert_channel_id)
+        .bind(self.alert_role_id)
+        .bind(&self.lock_channel_ids)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The alert channel, else `fallback`.
+    pub fn alert_channel(&self, fallback: Option<ChannelId>) -> Option<ChannelId> {
+        self.alert_channel_id
+            .map(|id| ChannelId::new(id as u64))
+            .or(fallback)
+    }
+
+    pub fn alert_role(&self) -> Option<RoleId> {
+        self.alert_role_id.map(|id| RoleId::new(id as u64))
+    }
+
+    pub fn lock_channels(&self) -> impl Iterator<Item = ChannelId> + '_ {
+        self.lock_channel_ids
+            .iter()
+            .map(|id| ChannelId::new(*id as u64))
+    }
+}
+
+/// A join seen by the monitor.
+struct RecentJoin {
+    user_id: UserId,
+    at: Instant,
+    young: bool,
+    default_avatar: bool,
+    /// Lowercased letters of the username, for spotting look-alike names.
+    name_stem: String,
+    avatar: Option<String>,
+}
+
+impl RecentJoin {
+    fn new(user: &User, account_age_days: i32) -> Self {
+        let age = chrono::Utc::now().timestamp() - user.created_at().unix_timestamp();
+        Self {
+            user_id: user.id,
+            at: Instant::now(),
+            young: age < i64::from(account_age_days) * 86_400,
+            default_avatar: user.avatar.is_none(),
+            name_stem: user
+                .name
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .flat_map(char::to_lowercase)
+                .collect(),
+            avatar: user.avatar.as_ref().map(ToString::to_string),
+        }
+    }
+
+    fn resembles(&self, other: &RecentJoin) -> bool {
+        let same_avatar = self.avatar.is_some() && self.avatar == other.avatar;
+        let common_prefix = self
+            .name_stem
+            .chars()
+            .zip(other.name_stem.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let similar_name = (self.name_stem.len() >= 3 && self.name_stem == other.name_stem)
+            || common_prefix >= SIMILAR_PREFIX_LEN;
+        same_avatar || similar_name
+    }
+}
+
+/// Joins that tipped a guild over the raid threshold.
+pub struct Raid {
+    pub user_ids: Vec<UserId>,
+    pub reason: String,
+}
+
+/// Recent joins per guild, kept in memory.
+#[derive(Clone, Default)]
+pub struct RaidMonitor {
+    windows: Arc<Mutex<HashMap<GuildId, VecDeque<RecentJoin>>>>,
+}
+
+impl RaidMonitor {
+    /// Record a join, returning the raid if this join completes one.
+    ///
+    /// A raid is `join_threshold` joins within the window, or half as many
+    /// (at least 3) suspicious ones: new accounts, default avatars, or names
+    /// and avatars shared with other recent joins.
+    fn record(&self, guild_id: GuildId, join: RecentJoin, config: &RaidConfig) -> Option<Raid> {
+        let window = Duration::from_secs(config.window_seconds.max(1) as u64);
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let joins = windows.entry(guild_id).or_default();
+
+        joins.retain(|j| j.at.elapsed() < window);
+        joins.push_back(join);
+
+        let threshold = config.join_threshold.max(2) as usize;
+        let suspicious = joins
+            .iter()
+            .filter(|j| {
+                j.young
+                    || j.default_avatar
+                    || joins
+                        .iter()
+                        .any(|other| other.user_id != j.user_id && j.resembles(other))
+            })
+            .count();
+
+        let reason = if joins.len() >= threshold {
+            format!("{} joins in {}s", joins.len(), config.window_seconds)
+        } else if suspicious >= (threshold / 2).max(3) {
+            format!(
+                "{suspicious} suspicious joins in {}s (new accounts, default avatars or look-alike profiles)",
+                config.window_seconds
+            )
+        } else {
+            return None;
+        };
+
+        // Start over so the same joins don't trigger twice.
+        let user_ids = joins.drain(..).map(|j| j.user_id).collect();
+        Some(Raid { user_ids, reason })
+    }
+}
+
+/// Watch a join for raids.
+///
+/// Returns `true` while the guild is locked down: the member is held without
+/// verification, roles or a welcome until a moderator ends the lockdown.
+pub async fn handle_join(ctx: &Context, member: &Member, data: &Data) -> bool {
+    if member.user.bot {
+        return false;
+    }
+    let guild_id = member.guild_id;
+
+    match lockdown::hold(&data.db, guild_id, member.user.id).await {
+        Ok(true) => {
+            info!(user = %member.user.name, "Holding member who joined during a lockdown");
+            return true;
+        }
+        Ok(false) => {}
+        Err(e) => error!(error = %e, "Failed to check for a lockdown"),
+    }
+
+    let config = match RaidConfig::load(&data.db, guild_id).await {
+        Ok(config) if config.enabled => config,
+        Ok(_) => return false,
+        Err(e) => {
+            error!(error = %e, "Failed to load raid settings");
+            return false;
+        }
+    };
+
+    let join = RecentJoin::new(&member.user, config.account_age_days);
+    let Some(raid) = data.raid_monitor.record(guild_id, join, &config) else {
+        return false;
+    };
+
+    warn!(guild_id = %guild_id, reason = %raid.reason, "Raid detected, locking down");
+    if let Err(e) = lockdown::start(
+        &ctx.http,
+        data,
+        guild_id,
+        &config,
+        &raid.reason,
+        &raid.user_ids,
+    )
+    .await
+    {
+        error!(error = %e, "Failed to start raid lockdown");
+    }
+
+    // This member was let in before the lockdown began; only later joins are held.
+    false
+}
+
+/// Handle the alert's "End lockdown" and "Ban raid accounts" buttons.
+pub async fn handle_component(ctx: &Context, component: &ComponentInteraction, data: &Data) {
+    let Some(guild_id) = component.guild_id else {
+        return;
+    };
+    let ban = component.data.custom_id == format!("{RAID_PREFIX}:ban");
+    let required = if ban {
+        Permissions::BAN_MEMBERS
+    } else {
+        Permissions::MANAGE_GUILD
+    };
+
+    let allowed = component
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.contains(required));
+    if !allowed {
+        let embed = embeds::error_embed().description(format!(
+            "You need the **{}** permission to do that.",
+            required.get_permission_names().join(", ")
+        ));
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .embed(embed)
+                .ephemeral(true),
+        );
+        let _ = component.create_response(&ctx.http, response).await;
+        return;
+    }
+
+    // Banning and restoring channels can take a while.
+    if let Err(e) = component.defer_ephemeral(&ctx.http).await {
+        error!(error = %e, "Failed to defer lockdown button");
+        return;
+    }
+
+    let embed = match lockdown::end(ctx, data, guild_id, &component.user, ban).await {
+        Ok(summary) => embeds::success_embed()
+            .title("Lockdown Ended")
+            .description(summary),
+        Err(e) => embeds::error_embed().description(e),
+    };
+    if let Err(e) = component
+        .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+        .await
+    {
+        error!(error = %e, "Failed to respond to lockdown button");
+    }
+}
@@ -0,0 +1,433 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use cron::Schedule;
+use serenity::all::{GuildId, Http};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{watch, Mutex, Notify};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{error, info, warn};
+
+/// Longest the scheduler sleeps between checks for due jobs.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A claimed job is re-run if it hasn't finished within this lease,
+/// e.g. because the bot crashed mid-run.
+const LEASE: ChronoDuration = ChronoDuration::minutes(10);
+
+/// Jobs claimed per pass.
+const BATCH_SIZE: i64 = 10;
+
+/// One-shot jobs are dropped after this many failed attempts.
+const MAX_ATTEMPTS: i32 = 5;
+
+/// A run is cut off after this long, before its lease runs out and another
+/// pass could pick the job up again.
+const JOB_TIMEOUT: Duration = Duration::from_secs(9 * 60);
+
+/// How long shutdown waits for running jobs to finish.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A persisted job, as handed to its handler.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    /// Stable name for recurring jobs; `None` for one-shot jobs.
+    pub key: Option<String>,
+    pub guild_id: Option<i64>,
+    pub payload: serde_json::Value,
+    /// Cron expression for recurring jobs.
+    pub cron: Option<String>,
+    /// When this run was due.
+    pub run_at: DateTime<Utc>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+}
+
+impl Job {
+    pub fn guild_id(&self) -> Option<GuildId> {
+        self.guild_id.map(|id| GuildId::new(id as u64))
+    }
+}
+
+/// What a handler gets to work with.
+#[derive(Clone)]
+pub struct JobContext {
+    pub http: Arc<Http>,
+    pub db: PgPool,
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type Handler = Arc<dyn Fn(JobContext, Job) -> JobFuture + Send + Sync>;
+
+/// Runs cron-style and one-shot jobs stored in the `scheduled_jobs` table.
+///
+/// Jobs are claimed with a lease before running and only cleared or
+/// rescheduled once their handler returns, so every job runs at least once
+/// even across crashes and restarts. Handlers should therefore be safe to
+/// repeat. Jobs whose kind has no registered handler wait until one is.
+#[derive(Clone)]
+pub struct Scheduler {
+    db: PgPool,
+    handlers: Arc<RwLock<HashMap<&'static str, Handler>>>,
+    wake: Arc<Notify>,
+    shutdown: Arc<watch::Sender<bool>>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl Scheduler {
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            db,
+            handlers: Arc::default(),
+            wake: Arc::default(),
+            shutdown: Arc::new(watch::channel(false).0),
+            task: Arc::default(),
+        }
+    }
+
+    /// Register the handler for a job kind. Jobs of that kind become eligible to run.
+    pub fn register<F, Fut>(&self, kind: &'static str, handler: F)
+    where
+        F: Fn(JobContext, Job) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |ctx, job| Box::pin(handler(ctx, job)));
+        self.handlers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(kind, handler);
+        self.wake.notify_one();
+    }
+
+    /// Create or update a recurring job, identified by `key`.
+    ///
+    /// The next run time survives restarts, so a run missed while the bot was
+    /// offline happens as soon as it's back. It's only recomputed when the
+    /// cron expression changes.
+    pub async fn ensure_recurring(
+        &self,
+        key: &str,
+        kind: &'static str,
+        cron: &str,
+        guild_id: Option<GuildId>,
+        payload: serde_json::Value,
+    ) -> Result<(), String> {
+        let next = next_run(cron, Utc::now())?;
+
+        sqlx::query(
+            "INSERT INTO scheduled_jobs (key, kind, guild_id, payload, cron, run_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (key) DO UPDATE SET \
+                kind = EXCLUDED.kind, guild_id = EXCLUDED.guild_id, payload = EXCLUDED.payload, \
+                run_at = CASE WHEN scheduled_jobs.cron IS DISTINCT FROM EXCLUDED.cron \
+                    THEN EXCLUDED.run_at ELSE scheduled_jobs.run_at END, \
+                cron = EXCLUDED.cron",
+        )
+        .bind(key)
+        .bind(kind)
+        .bind(guild_id.map(|g| g.get() as i64))
+        .bind(payload)
+        .bind(cron)
+        .bind(next)
+        .execute(&self.db)
+        .await
+        .map_err(|e| format!("Failed to save recurring job {key}: {e}"))?;
+
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Schedule a job to run once at `run_at`. Returns its ID.
+    pub async fn schedule_once(
+        &self,
+        kind: &'static str,
+        guild_id: Option<GuildId>,
+        payload: serde_json::Value,
+        run_at: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query_scalar(
+            "INSERT INTO scheduled_jobs (kind, guild_id, payload, run_at) \
+             VALUES ($1, $2, $3, $4) RETURNING id",
+        )
+        .bind(kind)
+        .bind(guild_id.map(|g| g.get() as i64))
+        .bind(payload)
+        .bind(run_at)
+        .fetch_one(&self.db)
+        .await?;
+
+        self.wake.notify_one();
+        Ok(id)
+    }
+
+    /// Pending jobs for a guild, soonest first. Bot-wide jobs aren't included.
+    pub async fn list(&self, guild_id: GuildId) -> Result<Vec<Job>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, kind, key, guild_id, payload, cron, run_at, attempts, last_error \
+             FROM scheduled_jobs WHERE guild_id = $1 ORDER BY run_at",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    /// Delete one of the guild's jobs. Returns whether one was removed.
+    ///
+    /// Bot-wide jobs belong to no guild, so they can't be cancelled this way.
+    pub async fn cancel(&self, guild_id: GuildId, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM scheduled_jobs WHERE id = $1 AND guild_id = $2")
+            .bind(id)
+            .bind(guild_id.get() as i64)
+            .execute(&self.db)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Cancel one-shot jobs of a kind whose payload contains `filter`.
+    pub async fn cancel_matching(
+        &self,
+        kind: &str,
+        filter: serde_json::Value,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM scheduled_jobs WHERE kind = $1 AND key IS NULL AND payload @> $2",
+        )
+        .bind(kind)
+        .bind(filter)
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Start running due jobs in the background.
+    pub async fn start(&self, http: Arc<Http>) {
+        let mut task = self.task.lock().await;
+        if task.is_some() {
+            return;
+        }
+
+        let ctx = JobContext {
+            http,
+            db: self.db.clone(),
+        };
+        let scheduler = self.clone();
+        *task = Some(tokio::spawn(async move { scheduler.run(ctx).await }));
+        info!("Job scheduler started");
+    }
+
+    /// Stop claiming jobs and wait for running ones to finish.
+    ///
+    /// Anything cut off by the timeout is retried on the next start once its lease expires.
+    pub async fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+        let Some(task) = self.task.lock().await.take() else {
+            return;
+        };
+
+        match tokio::time::timeout(SHUTDOWN_TIMEOUT, task).await {
+            Ok(_) => info!("Job scheduler stopped"),
+            Err(_) => warn!("Job scheduler didn't stop in time, unfinished jobs will be retried"),
+        }
+    }
+
+    async fn run(&self, ctx: JobContext) {
+        let mut shutdown = self.shutdown.subscribe();
+        // Each run is its own task so a slow job doesn't hold up the others.
+        let mut running = JoinSet::new();
+
+        while !*shutdown.borrow() {
+            while running.try_join_next().is_some() {}
+
+            let kinds = self.kinds();
+            if !kinds.is_empty() {
+                match self.claim_due(&kinds).await {
+                    Ok(jobs) => {
+                        for job in jobs {
+                            let (scheduler, ctx) = (self.clone(), ctx.clone());
+                            running.spawn(async move { scheduler.execute(&ctx, job).await });
+                        }
+                    }
+                    Err(e) => error!(error = %e, "Failed to claim scheduled jobs"),
+                }
+            }
+
+            let wait = match self.next_due(&kinds).await {
+                Ok(Some(next)) => (next - Utc::now())
+                    .to_std()
+                    .unwrap_or_default()
+                    .min(POLL_INTERVAL),
+                Ok(None) => POLL_INTERVAL,
+                Err(e) => {
+                    error!(error = %e, "Failed to check scheduled jobs");
+                    POLL_INTERVAL
+                }
+            };
+
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = self.wake.notified() => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        while running.join_next().await.is_some() {}
+    }
+
+    fn kinds(&self) -> Vec<String> {
+        self.handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .map(|k| k.to_string())
+            .collect()
+    }
+
+    /// Lease due jobs so no other pass (or instance) picks them up meanwhile.
+    async fn claim_due(&self, kinds: &[String]) -> Result<Vec<Job>, sqlx::Error> {
+        sqlx::query_as(
+            "UPDATE scheduled_jobs SET locked_until = now() + $1 \
+             WHERE id IN ( \
+                SELECT id FROM scheduled_jobs \
+                WHERE run_at <= now() AND kind = ANY($2) \
+                  AND (locked_until IS NULL OR locked_until < now()) \
+                ORDER BY run_at LIMIT $3 FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING id, kind, key, guild_id, payload, cron, run_at, attempts, last_error",
+        )
+        .bind(LEASE)
+        .bind(kinds)
+        .bind(BATCH_SIZE)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn next_due(&self, kinds: &[String]) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT min(GREATEST(run_at, COALESCE(locked_until, run_at))) \
+             FROM scheduled_jobs WHERE kind = ANY($1)",
+        )
+        .bind(kinds)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn execute(&self, ctx: &JobContext, job: Job) {
+        let handler = self
+            .handlers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(job.kind.as_str())
+            .cloned();
+        let Some(handler) = handler else {
+            return;
+        };
+
+        let (id, kind) = (job.id, job.kind.clone());
+        let (cron, attempts) = (job.cron.clone(), job.attempts);
+        let result = tokio::time::timeout(JOB_TIMEOUT, handler(ctx.clone(), job))
+            .await
+            .unwrap_or_else(|_| Err(format!("Timed out after {}s", JOB_TIMEOUT.as_secs())));
+
+        let outcome = match (&result, cron) {
+            (Ok(()), None) => self.delete(id).await,
+            (Ok(()), Some(cron)) => self.reschedule(id, &cron, 0, None).await,
+            (Err(e), Some(cron)) => {
+                error!(id, kind, error = %e, "Recurring job failed, waiting for its next run");
+                self.reschedule(id, &cron, attempts + 1, Some(e)).await
+            }
+            (Err(e), None) if attempts + 1 >= MAX_ATTEMPTS => {
+                error!(id, kind, error = %e, "Job failed too many times, giving up");
+                self.delete(id).await
+            }
+            (Err(e), None) => {
+                warn!(id, kind, error = %e, attempt = attempts + 1, "Job failed, retrying");
+                self.retry(id, attempts + 1, e).await
+            }
+        };
+
+        if let Err(e) = outcome {
+            error!(id, kind, error = %e, "Failed to update scheduled job");
+        }
+    }
+
+    async fn delete(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM scheduled_jobs WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn reschedule(
+        &self,
+        id: i64,
+        cron: &str,
+        attempts: i32,
+        error: Option<&String>,
+    ) -> Result<(), sqlx::Error> {
+        let next = match next_run(cron, Utc::now()) {
+            Ok(next) => next,
+            Err(e) => {
+                error!(id, error = %e, "Recurring job has an invalid schedule, removing it");
+                return self.delete(id).await;
+            }
+        };
+
+        sqlx::query(
+            "UPDATE scheduled_jobs SET run_at = $2, locked_until = NULL, attempts = $3, \
+                last_error = $4, last_run_at = now() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(next)
+        .bind(attempts)
+        .bind(error)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Back off exponentially: 1, 2, 4, 8… minutes.
+    async fn retry(&self, id: i64, attempts: i32, error: &str) -> Result<(), sqlx::Error> {
+        let delay = ChronoDuration::minutes(1 << (attempts - 1).clamp(0, 6));
+
+        sqlx::query(
+            "UPDATE scheduled_jobs SET run_at = now() + $2, locked_until = NULL, attempts = $3, \
+                last_error = $4, last_run_at = now() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(delay)
+        .bind(attempts)
+        .bind(error)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+}
+
+/// The first time after `after` matching a cron expression.
+///
+/// Accepts standard five-field expressions (`min hour day month weekday`)
+/// as well as the six/seven-field form with seconds and years.
+pub fn next_run(cron: &str, after: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let expression = if cron.split_whitespace().count() == 5 {
+        format!("0 {cron}")
+    } else {
+        cron.to_string()
+    };
+
+    Schedule::from_str(&expression)
+        .map_err(|e| format!("Invalid cron expression '{cron}': {e}"))?
+        .after(&after)
+        .next()
+        .ok_or_else(|| format!("Cron expression '{cron}' never fires again"))
+}
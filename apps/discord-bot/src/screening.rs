@@ -0,0 +1,470 @@
+use crate::scheduler::Scheduler;
+use crate::utils::embeds;
+use crate::Data;
+use chrono::{DateTime, Duration, Utc};
+use serenity::all::{
+    ChannelId, Context, CreateEmbed, CreateEmbedFooter, CreateMessage, GuildId, Http, Member,
+    Mentionable, RoleId, User, UserId,
+};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::{error, info, warn};
+
+/// Scheduler job that posts each guild's screened accounts, daily at 09:00 UTC.
+const SUMMARY_JOB: &str = "screening_summary";
+const SUMMARY_CRON: &str = "0 9 * * *";
+
+/// Screened accounts listed per summary.
+const MAX_LISTED: usize = 15;
+
+/// How long screened accounts are kept.
+const RETENTION_DAYS: i64 = 30;
+
+/// What happens to a member caught by screening. Flagged members are let in
+/// with an alert; quarantined ones get only the quarantine role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum ScreeningAction {
+    Flag,
+    Quarantine,
+    Kick,
+}
+
+impl ScreeningAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScreeningAction::Flag => "flag",
+            ScreeningAction::Quarantine => "quarantine",
+            ScreeningAction::Kick => "kick",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "flag" => Some(ScreeningAction::Flag),
+            "quarantine" => Some(ScreeningAction::Quarantine),
+            "kick" => Some(ScreeningAction::Kick),
+            _ => None,
+        }
+    }
+
+    fn past_tense(self) -> &'static str {
+        match self {
+            ScreeningAction::Flag => "Flagged",
+            ScreeningAction::Quarantine => "Quarantined",
+            ScreeningAction::Kick => "Kicked",
+        }
+    }
+}
+
+/// A guild's join screening settings.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ScreeningConfig {
+    pub enabled: bool,
+    /// Accounts younger than this are screened; 0 turns the check off.
+    pub min_account_age_days: i32,
+    /// Also screen accounts without an avatar.
+    pub default_avatar: bool,
+    pub action: String,
+    pub quarantine_role_id: Option<i64>,
+    /// Where the daily summary goes; `None` uses the log channel.
+    pub summary_channel_id: Option<i64>,
+}
+
+impl Default for ScreeningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_account_age_days: 7,
+            default_avatar: false,
+            action: ScreeningAction::Flag.as_str().to_string(),
+            quarantine_role_id: None,
+            summary_channel_id: None,
+        }
+    }
+}
+
+impl ScreeningConfig {
+    /// Load the guild's settings, falling back to the defaults (screening off).
+    pub async fn load(db: &PgPool, guild_id: GuildId) -> Result<Self, sqlx::Error> {
+        let row = sqlx::query_as::<_, Self>(
+            "SELECT enabled, min_account_age_days, default_avatar, action, \
+                quarantine_role_id, summary_channel_id \
+             FROM screening_config WHERE guild_id = $1",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.unwrap_or_default())
+    }
+
+    pub async fn save(&self, db: &PgPool, guild_id: GuildId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO screening_config (guild_id, enabled, min_account_age_days, \
+                default_avatar, action, quarantine_role_id, summary_channel_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (guild_id) DO UPDATE SET \
+                enabled = EXCLUDED.enabled, \
+                min_account_age_days = EXCLUDED.min_account_age_days, \
+                default_avatar = EXCLUDED.default_avatar, action = EXCLUDED.action, \
+                quarantine_role_id = EXCLUDED.quarantine_role_id, \
+                summary_channel_id = EXCLUDED.summary_channel_id",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(self.enabled)
+        .bind(self.min_account_age_days)
+        .bind(self.default_avatar)
+        .bind(&self.action)
+        .bind(self.quarantine_role_id)
+        .bind(self.summary_channel_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn action(&self) -> ScreeningAction {
+        ScreeningAction::parse(&self.action).unwrap_or(ScreeningAction::Flag)
+    }
+
+    pub fn quarantine_role(&self) -> Option<RoleId> {
+        self.quarantine_role_id.map(|id| RoleId::new(id as u64))
+    }
+
+    /// The summary channel, else `fallback`.
+    pub fn summary_channel(&self, fallback: Option<ChannelId>) -> Option<ChannelId> {
+        self.summary_channel_id
+            .map(|id| ChannelId::new(id as u64))
+            .or(fallback)
+    }
+
+    /// Why `user` fails screening, if it does.
+    fn reasons(&self, user: &User) -> Vec<String> {
+        let mut reasons = Vec::new();
+
+        let age_days = (Utc::now().timestamp() - user.created_at().unix_timestamp()) / 86_400;
+        if self.min_account_age_days > 0 && age_days < i64::from(self.min_account_age_days) {
+            reasons.push(match age_days {
+                0 => "Account created today".to_string(),
+                1 => "Account is 1 day old".to_string(),
+                days => format!("Account is {days} days old"),
+            });
+        }
+        if self.default_avatar && user.avatar.is_none() {
+            reasons.push("No avatar".to_string());
+        }
+
+        reasons
+    }
+}
+
+/// Add a member to the allowlist. Returns `false` if they were already on it.
+pub async fn allow(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    added_by: UserId,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO screening_allowlist (guild_id, user_id, added_by) VALUES ($1, $2, $3) \
+         ON CONFLICT (guild_id, user_id) DO NOTHING",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .bind(added_by.get() as i64)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove a member from the allowlist. Returns `false` if they weren't on it.
+pub async fn unallow(db: &PgPool, guild_id: GuildId, user_id: UserId) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM screening_allowlist WHERE guild_id = $1 AND user_id = $2")
+            .bind(guild_id.get() as i64)
+            .bind(user_id.get() as i64)
+            .execute(db)
+            .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// An allowlisted member.
+#[derive(Debug, sqlx::FromRow)]
+pub struct AllowedMember {
+    pub user_id: i64,
+    pub added_by: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+pub async fn allowlist(db: &PgPool, guild_id: GuildId) -> Result<Vec<AllowedMember>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT user_id, added_by, created_at FROM screening_allowlist \
+         WHERE guild_id = $1 ORDER BY created_at DESC",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(db)
+    .await
+}
+
+async fn is_allowed(db: &PgPool, guild_id: GuildId, user_id: UserId) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM screening_allowlist WHERE guild_id = $1 AND user_id = $2)",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .fetch_one(db)
+    .await
+}
+
+/// Accounts screened in the guild over the last day.
+pub async fn screened_today(db: &PgPool, guild_id: GuildId) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM screened_accounts \
+         WHERE guild_id = $1 AND screened_at > now() - INTERVAL '1 day'",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_one(db)
+    .await
+}
+
+/// Screen a new member against the guild's policy.
+///
+/// Returns `true` if they were quarantined or kicked, so they shouldn't get
+/// verification, auto-roles or a welcome.
+pub async fn screen(ctx: &Context, member: &Member, data: &Data) -> bool {
+    if member.user.bot {
+        return false;
+    }
+    let guild_id = member.guild_id;
+
+    let config = match ScreeningConfig::load(&data.db, guild_id).await {
+        Ok(config) if config.enabled => config,
+        Ok(_) => return false,
+        Err(e) => {
+            error!(error = %e, "Failed to load screening settings");
+            return false;
+        }
+    };
+
+    let reasons = config.reasons(&member.user);
+    if reasons.is_empty() {
+        return false;
+    }
+    match is_allowed(&data.db, guild_id, member.user.id).await {
+        Ok(true) => return false,
+        Ok(false) => {}
+        Err(e) => error!(error = %e, "Failed to check screening allowlist"),
+    }
+
+    let action = apply(ctx, member, &config, &reasons).await;
+
+    let recorded = sqlx::query(
+        "INSERT INTO screened_accounts (guild_id, user_id, user_name, reasons, action) \
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .bind(&member.user.name)
+    .bind(reasons.join(", "))
+    .bind(action.as_str())
+    .execute(&data.db)
+    .await;
+    if let Err(e) = recorded {
+        error!(error = %e, "Failed to record screened account");
+    }
+
+    if let Some(log_channel) = data.config.log_channel_id {
+        let mut embed = embeds::moderation_embed()
+            .title(format!("Account {}", action.past_tense()))
+            .field(
+                "User",
+                format!("{} ({})", member.mention(), member.user.name),
+                false,
+            )
+            .field("Reasons", reasons.join("\n"), true)
+            .field(
+                "Account Created",
+                format!("<t:{}:R>", member.user.created_at().unix_timestamp()),
+                true,
+            );
+        if action != config.action() {
+            embed = embed.footer(CreateEmbedFooter::new(format!(
+                "Couldn't {} them, so they were only flagged.",
+                config.action().as_str()
+            )));
+        } else if action != ScreeningAction::Kick {
+            embed = embed.footer(CreateEmbedFooter::new("Let them in with /screening allow."));
+        }
+
+        let message = CreateMessage::new().embed(embed);
+        if let Err(e) = log_channel.send_message(&ctx.http, message).await {
+            error!(error = %e, "Failed to send screening alert");
+        }
+    }
+
+    info!(user = %member.user.name, action = action.as_str(), reasons = ?reasons, "Screened new member");
+    action != ScreeningAction::Flag
+}
+
+/// Carry out the guild's action, returning what was actually done.
+async fn apply(
+    ctx: &Context,
+    member: &Member,
+    config: &ScreeningConfig,
+    reasons: &[String],
+) -> ScreeningAction {
+    match config.action() {
+        ScreeningAction::Flag => ScreeningAction::Flag,
+        ScreeningAction::Quarantine => {
+            let Some(role_id) = config.quarantine_role() else {
+                warn!("Screening is set to quarantine but has no quarantine role");
+                return ScreeningAction::Flag;
+            };
+            match member.add_role(&ctx.http, role_id).await {
+                Ok(()) => ScreeningAction::Quarantine,
+                Err(e) => {
+                    warn!(user = %member.user.name, error = %e, "Failed to quarantine member");
+                    ScreeningAction::Flag
+                }
+            }
+        }
+        ScreeningAction::Kick => {
+            let server = member
+                .guild_id
+                .name(&ctx.cache)
+                .unwrap_or_else(|| "the server".to_string());
+            let notice = CreateMessage::new().content(format!(
+                "You were removed from **{server}** by its new-account screening ({}). \
+                 Please try again later, or ask a moderator to let you in.",
+                reasons.join(", ").to_lowercase()
+            ));
+            // Members with closed DMs just don't get the notice.
+            let _ = member.user.direct_message(&ctx.http, notice).await;
+
+            let reason = format!("Join screening: {}", reasons.join(", "));
+            match member.kick_with_reason(&ctx.http, &reason).await {
+                Ok(()) => ScreeningAction::Kick,
+                Err(e) => {
+                    warn!(user = %member.user.name, error = %e, "Failed to kick screened member");
+                    ScreeningAction::Flag
+                }
+            }
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ScreenedAccount {
+    guild_id: i64,
+    user_id: i64,
+    reasons: String,
+    action: String,
+}
+
+/// Post each guild's screened accounts from the last day.
+///
+/// One bot-wide job covers every guild, so it has no guild and doesn't show
+/// up in `/jobs`.
+pub async fn register_daily_summary(scheduler: &Scheduler, log_channel_id: Option<ChannelId>) {
+    scheduler.register(SUMMARY_JOB, move |ctx, job| async move {
+        post_summaries(&ctx.http, &ctx.db, log_channel_id, job.run_at)
+            .await
+            .map_err(|e| format!("Failed to post screening summaries: {e}"))
+    });
+
+    if let Err(e) = scheduler
+        .ensure_recurring(
+            SUMMARY_JOB,
+            SUMMARY_JOB,
+            SUMMARY_CRON,
+            None,
+            serde_json::Value::Null,
+        )
+        .await
+    {
+        error!(error = %e, "Failed to schedule the screening summary");
+    }
+}
+
+async fn post_summaries(
+    http: &Http,
+    db: &PgPool,
+    log_channel_id: Option<ChannelId>,
+    until: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let rows: Vec<ScreenedAccount> = sqlx::query_as(
+        "SELECT guild_id, user_id, reasons, action FROM screened_accounts \
+         WHERE screened_at > $1 AND screened_at <= $2 ORDER BY screened_at",
+    )
+    .bind(until - Duration::days(1))
+    .bind(until)
+    .fetch_all(db)
+    .await?;
+
+    let mut by_guild: HashMap<i64, Vec<ScreenedAccount>> = HashMap::new();
+    for row in rows {
+        by_guild.entry(row.guild_id).or_default().push(row);
+    }
+
+    for (guild_id, accounts) in by_guild {
+        let guild_id = GuildId::new(guild_id as u64);
+        let config = match ScreeningConfig::load(db, guild_id).await {
+            Ok(config) => config,
+            Err(e) => {
+                error!(guild_id = %guild_id, error = %e, "Failed to load screening settings");
+                continue;
+            }
+        };
+        let Some(channel_id) = config.summary_channel(log_channel_id) else {
+            continue;
+        };
+
+        let message = CreateMessage::new().embed(summary_embed(&accounts));
+        if let Err(e) = channel_id.send_message(http, message).await {
+            warn!(guild_id = %guild_id, error = %e, "Failed to send screening summary");
+        }
+    }
+
+    sqlx::query("DELETE FROM screened_accounts WHERE screened_at < $1")
+        .bind(until - Duration::days(RETENTION_DAYS))
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+fn summary_embed(accounts: &[ScreenedAccount]) -> CreateEmbed {
+    let count = |action: ScreeningAction| {
+        accounts
+            .iter()
+            .filter(|a| a.action == action.as_str())
+            .count()
+    };
+
+    let mut lines: Vec<String> = accounts
+        .iter()
+        .take(MAX_LISTED)
+        .map(|a| {
+            let action = ScreeningAction::parse(&a.action)
+                .map(ScreeningAction::past_tense)
+                .unwrap_or("Screened");
+            format!("<@{}> — {action}: {}", a.user_id, a.reasons)
+        })
+        .collect();
+    if accounts.len() > MAX_LISTED {
+        lines.push(format!("…and {} more", accounts.len() - MAX_LISTED));
+    }
+
+    embeds::moderation_embed()
+        .title("Daily Screening Summary")
+        .description(lines.join("\n"))
+        .field("Flagged", count(ScreeningAction::Flag).to_string(), true)
+        .field(
+            "Quarantined",
+            count(ScreeningAction::Quarantine).to_string(),
+            true,
+        )
+        .field("Kicked", count(ScreeningAction::Kick).to_string(), true)
+}
@@ -0,0 +1,279 @@
+use crate::integrations::twitch::TwitchApi;
+use crate::scheduler::Scheduler;
+use crate::utils::templates;
+use serenity::all::{Cache, ChannelId, EditChannel, GuildId, Http, HttpError};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Scheduler job that refreshes stat channel names, every 10 minutes.
+///
+/// Discord allows two renames per channel every 10 minutes, and names are
+/// only changed when the stat has, so this stays well inside the limit.
+const UPDATE_JOB: &str = "stat_channels";
+const UPDATE_CRON: &str = "*/10 * * * *";
+
+/// Discord's channel name limit.
+const MAX_NAME_LENGTH: usize = 100;
+
+/// What a stat channel shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum StatKind {
+    Members,
+    #[name = "Live on Twitch"]
+    Live,
+    #[name = "Twitch followers"]
+    Followers,
+}
+
+impl StatKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatKind::Members => "members",
+            StatKind::Live => "live",
+            StatKind::Followers => "followers",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "members" => Some(StatKind::Members),
+            "live" => Some(StatKind::Live),
+            "followers" => Some(StatKind::Followers),
+            _ => None,
+        }
+    }
+
+    pub fn default_template(self) -> &'static str {
+        match self {
+            StatKind::Members => "Members: {value}",
+            StatKind::Live => "Live: {value}",
+            StatKind::Followers => "Followers: {value}",
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct StatChannel {
+    pub channel_id: i64,
+    pub guild_id: i64,
+    pub kind: String,
+    pub template: String,
+    pub last_name: Option<String>,
+}
+
+impl StatChannel {
+    pub fn channel(&self) -> ChannelId {
+        ChannelId::new(self.channel_id as u64)
+    }
+
+    pub fn kind(&self) -> Option<StatKind> {
+        StatKind::parse(&self.kind)
+    }
+}
+
+pub async fn list(db: &PgPool, guild_id: GuildId) -> Result<Vec<StatChannel>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT channel_id, guild_id, kind, template, last_name FROM stat_channels \
+         WHERE guild_id = $1 ORDER BY channel_id",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn add(
+    db: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    kind: StatKind,
+    template: &str,
+    name: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO stat_channels (channel_id, guild_id, kind, template, last_name, updated_at) \
+         VALUES ($1, $2, $3, $4, $5, now())",
+    )
+    .bind(channel_id.get() as i64)
+    .bind(guild_id.get() as i64)
+    .bind(kind.as_str())
+    .bind(template)
+    .bind(name)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Stop updating a channel. Returns `false` if it wasn't a stat channel.
+pub async fn remove(
+    db: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM stat_channels WHERE guild_id = $1 AND channel_id = $2")
+        .bind(guild_id.get() as i64)
+        .bind(channel_id.get() as i64)
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Fill `template` in with `value`, trimmed to fit a channel name.
+pub fn render(template: &str, value: &str) -> String {
+    let name = templates::render(template, |key| (key == "value").then(|| value.to_string()));
+    name.chars().take(MAX_NAME_LENGTH).collect()
+}
+
+/// Where stat values come from: member counts from the gateway cache, the
+/// rest from Twitch Helix.
+#[derive(Clone)]
+pub struct StatSources {
+    cache: Arc<Cache>,
+    twitch: TwitchApi,
+}
+
+impl StatSources {
+    pub fn new(cache: Arc<Cache>, twitch: TwitchApi) -> Self {
+        Self { cache, twitch }
+    }
+
+    /// The current value of `kind` in `guild_id`, formatted for a channel name.
+    pub async fn value(&self, guild_id: GuildId, kind: StatKind) -> Result<String, String> {
+        match kind {
+            StatKind::Members => self
+                .cache
+                .guild(guild_id)
+                .map(|guild| format_count(guild.member_count))
+                .ok_or_else(|| "The server isn't cached yet".to_string()),
+            StatKind::Live => Ok(if self.twitch.is_live().await? {
+                "Yes"
+            } else {
+                "No"
+            }
+            .to_string()),
+            StatKind::Followers => Ok(format_count(
+                self.twitch.follower_count().await?.max(0) as u64
+            )),
+        }
+    }
+}
+
+/// Refresh every stat channel on a schedule.
+pub async fn register_stat_channels(scheduler: &Scheduler, sources: StatSources) {
+    scheduler.register(UPDATE_JOB, move |ctx, _job| {
+        let sources = sources.clone();
+        async move {
+            update_all(&ctx.http, &ctx.db, &sources)
+                .await
+                .map_err(|e| format!("Failed to update stat channels: {e}"))
+        }
+    });
+
+    if let Err(e) = scheduler
+        .ensure_recurring(
+            UPDATE_JOB,
+            UPDATE_JOB,
+            UPDATE_CRON,
+            None,
+            serde_json::Value::Null,
+        )
+        .await
+    {
+        error!(error = %e, "Failed to schedule stat channel updates");
+    }
+}
+
+async fn update_all(http: &Http, db: &PgPool, sources: &StatSources) -> Result<(), sqlx::Error> {
+    let channels: Vec<StatChannel> =
+        sqlx::query_as("SELECT channel_id, guild_id, kind, template, last_name FROM stat_channels")
+            .fetch_all(db)
+            .await?;
+
+    // Twitch stats are the same in every guild, so each is fetched at most once a run.
+    let mut twitch_values: Vec<(StatKind, Result<String, String>)> = Vec::new();
+
+    for channel in channels {
+        let Some(kind) = channel.kind() else {
+            continue;
+        };
+        let guild_id = GuildId::new(channel.guild_id as u64);
+
+        let value = match kind {
+            StatKind::Members => sources.value(guild_id, kind).await,
+            _ => match twitch_values.iter().find(|(k, _)| *k == kind) {
+                Some((_, value)) => value.clone(),
+                None => {
+                    let value = sources.value(guild_id, kind).await;
+                    twitch_values.push((kind, value.clone()));
+                    value
+                }
+            },
+        };
+        let value = match value {
+            Ok(value) => value,
+            Err(e) => {
+                warn!(channel_id = channel.channel_id, kind = kind.as_str(), error = %e, "Failed to get stat");
+                continue;
+            }
+        };
+
+        let name = render(&channel.template, &value);
+        if channel.last_name.as_deref() == Some(name.as_str()) {
+            continue;
+        }
+
+        match channel
+            .channel()
+            .edit(http, EditChannel::new().name(&name))
+            .await
+        {
+            Ok(_) => {
+                sqlx::query(
+                    "UPDATE stat_channels SET last_name = $2, updated_at = now() \
+                     WHERE channel_id = $1",
+                )
+                .bind(channel.channel_id)
+                .bind(&name)
+                .execute(db)
+                .await?;
+            }
+            Err(e) if is_unknown_channel(&e) => {
+                info!(
+                    channel_id = channel.channel_id,
+                    "Stat channel was deleted, forgetting it"
+                );
+                if let Err(e) = remove(db, guild_id, channel.channel()).await {
+                    error!(channel_id = channel.channel_id, error = %e, "Failed to forget deleted stat channel");
+                }
+            }
+            Err(e) => {
+                warn!(channel_id = channel.channel_id, error = %e, "Failed to rename stat channel");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_unknown_channel(error: &serenity::Error) -> bool {
+    matches!(
+        error,
+        serenity::Error::Http(HttpError::UnsuccessfulRequest(response))
+            if response.status_code.as_u16() == 404
+    )
+}
+
+/// `1234567` as `1,234,567`.
+fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
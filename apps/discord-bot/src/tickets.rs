@@ -0,0 +1,650 @@
+use crate::scheduler::Scheduler;
+use crate::utils::embeds;
+use crate::Data;
+use chrono::{DateTime, Utc};
+use serenity::all::{
+    AutoArchiveDuration, ButtonStyle, ChannelId, ChannelType, ComponentInteraction, Context,
+    CreateActionRow, CreateAllowedMentions, CreateAttachment, CreateButton, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, CreateThread,
+    EditInteractionResponse, EditThread, GetMessages, GuildId, Http, Member, Mentionable, Message,
+    MessageId, Permissions, RoleId, UserId,
+};
+use sqlx::PgPool;
+use std::fmt::Write as _;
+use tracing::{error, info, warn};
+
+/// Custom ID prefix of the ticket buttons: `ticket:open`, `ticket:claim` and `ticket:close`.
+pub const TICKET_PREFIX: &str = "ticket";
+const OPEN_BUTTON: &str = "ticket:open";
+const CLAIM_BUTTON: &str = "ticket:claim";
+const CLOSE_BUTTON: &str = "ticket:close";
+
+pub const DEFAULT_INACTIVITY_HOURS: i32 = 48;
+
+/// Scheduler job that closes inactive tickets, every 15 minutes.
+const INACTIVITY_JOB: &str = "ticket_inactivity";
+const INACTIVITY_CRON: &str = "*/15 * * * *";
+
+/// Messages read back for a transcript; older ones are left out.
+const MAX_TRANSCRIPT_MESSAGES: usize = 5000;
+
+/// A guild's ticket settings.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TicketConfig {
+    pub channel_id: i64,
+    pub staff_role_id: i64,
+    pub log_channel_id: Option<i64>,
+    pub inactivity_hours: i32,
+}
+
+impl TicketConfig {
+    pub async fn load(db: &PgPool, guild_id: GuildId) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT channel_id, staff_role_id, log_channel_id, inactivity_hours \
+             FROM ticket_config WHERE guild_id = $1",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_optional(db)
+        .await
+    }
+
+    pub async fn save(&self, db: &PgPool, guild_id: GuildId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO ticket_config (guild_id, channel_id, staff_role_id, log_channel_id, \
+                inactivity_hours) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (guild_id) DO UPDATE SET \
+                channel_id = EXCLUDED.channel_id, staff_role_id = EXCLUDED.staff_role_id, \
+                log_channel_id = EXCLUDED.log_channel_id, \
+                inactivity_hours = EXCLUDED.inactivity_hours",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(self.channel_id)
+        .bind(self.staff_role_id)
+        .bind(self.log_channel_id)
+        .bind(self.inactivity_hours)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn channel(&self) -> ChannelId {
+        ChannelId::new(self.channel_id as u64)
+    }
+
+    pub fn staff_role(&self) -> RoleId {
+        RoleId::new(self.staff_role_id as u64)
+    }
+
+    /// The transcript channel, else `fallback`.
+    pub fn log_channel(&self, fallback: Option<ChannelId>) -> Option<ChannelId> {
+        self.log_channel_id
+            .map(|id| ChannelId::new(id as u64))
+            .or(fallback)
+    }
+
+    /// Staff are members with the staff role, plus anyone who can manage the server.
+    pub fn is_staff(&self, member: &Member) -> bool {
+        member.roles.contains(&self.staff_role())
+            || member
+                .permissions
+                .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD))
+    }
+}
+
+/// An open or closed ticket.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Ticket {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub user_id: i64,
+    pub claimed_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Ticket {
+    pub fn channel(&self) -> ChannelId {
+        ChannelId::new(self.channel_id as u64)
+    }
+
+    pub fn user(&self) -> UserId {
+        UserId::new(self.user_id as u64)
+    }
+
+    pub fn claimed_by(&self) -> Option<UserId> {
+        self.claimed_by.map(|id| UserId::new(id as u64))
+    }
+}
+
+/// The open ticket in a thread, if it is one.
+pub async fn find_open(db: &PgPool, channel_id: ChannelId) -> Result<Option<Ticket>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, guild_id, channel_id, user_id, claimed_by, created_at FROM tickets \
+         WHERE channel_id = $1 AND status = 'open'",
+    )
+    .bind(channel_id.get() as i64)
+    .fetch_optional(db)
+    .await
+}
+
+/// A guild's open tickets, oldest first.
+pub async fn list_open(db: &PgPool, guild_id: GuildId) -> Result<Vec<Ticket>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, guild_id, channel_id, user_id, claimed_by, created_at FROM tickets \
+         WHERE guild_id = $1 AND status = 'open' ORDER BY created_at",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(db)
+    .await
+}
+
+/// The panel message members open tickets from.
+pub fn panel_message(description: &str) -> CreateMessage {
+    let embed = embeds::crimson_embed()
+        .title("🎫 Support")
+        .description(description);
+    let button = CreateButton::new(OPEN_BUTTON)
+        .label("Open a ticket")
+        .emoji('🎫')
+        .style(ButtonStyle::Primary);
+    CreateMessage::new()
+        .embed(embed)
+        .components(vec![CreateActionRow::Buttons(vec![button])])
+}
+
+/// Handle the panel button and the claim and close buttons in a ticket.
+pub async fn handle_component(ctx: &Context, component: &ComponentInteraction, data: &Data) {
+    let (Some(guild_id), Some(member)) = (component.guild_id, component.member.as_ref()) else {
+        return;
+    };
+    let config = match TicketConfig::load(&data.db, guild_id).await {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            respond(
+                ctx,
+                component,
+                embeds::error_embed().description("Tickets aren't set up on this server."),
+            )
+            .await;
+            return;
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to load ticket settings");
+            return;
+        }
+    };
+
+    // Creating a thread or reading back a transcript can take a while.
+    if let Err(e) = component.defer_ephemeral(&ctx.http).await {
+        error!(error = %e, "Failed to defer ticket button");
+        return;
+    }
+
+    let result = match component.data.custom_id.as_str() {
+        OPEN_BUTTON => open(&ctx.http, &data.db, guild_id, member, &config).await,
+        CLAIM_BUTTON | CLOSE_BUTTON => match find_open(&data.db, component.channel_id).await {
+            Ok(Some(ticket)) if component.data.custom_id == CLAIM_BUTTON => {
+                if config.is_staff(member) {
+                    claim(&ctx.http, &data.db, &ticket, member.user.id).await
+                } else {
+                    Err("Only staff can claim tickets.".to_string())
+                }
+            }
+            Ok(Some(ticket)) => {
+                if config.is_staff(member) || ticket.user() == member.user.id {
+                    close(
+                        &ctx.http,
+                        &data.db,
+                        &ticket,
+                        Some(member.user.id),
+                        None,
+                        config.log_channel(data.config.log_channel_id),
+                    )
+                    .await
+                } else {
+                    Err("Only staff or the member who opened it can close this ticket.".to_string())
+                }
+            }
+            Ok(None) => Err("This ticket is already closed.".to_string()),
+            Err(e) => {
+                error!(error = %e, "Failed to load ticket");
+                Err("Something went wrong. Please try again.".to_string())
+            }
+        },
+        _ => return,
+    };
+
+    let embed = match result {
+        Ok(message) => embeds::success_embed().description(message),
+        Err(message) => embeds::error_embed().description(message),
+    };
+    if let Err(e) = component
+        .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+        .await
+    {
+        error!(error = %e, "Failed to respond to ticket button");
+    }
+}
+
+/// Open a ticket for `member`: a private thread under the panel that only
+/// they and staff can see.
+async fn open(
+    http: &Http,
+    db: &PgPool,
+    guild_id: GuildId,
+    member: &Member,
+    config: &TicketConfig,
+) -> Result<String, String> {
+    let existing: Option<i64> = sqlx::query_scalar(
+        "SELECT channel_id FROM tickets WHERE guild_id = $1 AND user_id = $2 AND status = 'open'",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to check for an open ticket");
+        "Something went wrong. Please try again.".to_string()
+    })?;
+    if let Some(channel_id) = existing {
+        return Err(format!(
+            "You already have an open ticket: {}",
+            ChannelId::new(channel_id as u64).mention()
+        ));
+    }
+
+    let thread = config
+        .channel()
+        .create_thread(
+            http,
+            CreateThread::new(format!("ticket-{}", member.user.name))
+                .kind(ChannelType::PrivateThread)
+                .invitable(false)
+                .auto_archive_duration(AutoArchiveDuration::OneWeek)
+                .audit_log_reason("Support ticket"),
+        )
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to create ticket thread");
+            "Couldn't open a ticket. Ask staff to check the bot can create private threads."
+                .to_string()
+        })?;
+
+    // The check above can race a double click; the unique index settles it.
+    let id: i64 = match sqlx::query_scalar(
+        "INSERT INTO tickets (guild_id, channel_id, user_id) VALUES ($1, $2, $3) \
+         ON CONFLICT (guild_id, user_id) WHERE status = 'open' DO NOTHING RETURNING id",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(thread.id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .fetch_optional(db)
+    .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            let _ = thread.id.delete(http).await;
+            return Err("You already have an open ticket.".to_string());
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to record ticket");
+            let _ = thread.id.delete(http).await;
+            return Err("Something went wrong. Please try again.".to_string());
+        }
+    };
+
+    if let Err(e) = thread.id.add_thread_member(http, member.user.id).await {
+        warn!(error = %e, "Failed to add member to ticket thread");
+    }
+
+    // Mentioning the staff role adds its members to the private thread.
+    let embed = embeds::crimson_embed()
+        .title(format!("Ticket #{id}"))
+        .description(format!(
+        "Thanks for reaching out, {}! Tell us what you need and staff will be with you soon.\n\n\
+             Either of you can press **Close** when you're done.",
+        member.mention()
+    ));
+    let buttons = vec![
+        CreateButton::new(CLAIM_BUTTON)
+            .label("Claim")
+            .emoji('🙋')
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(CLOSE_BUTTON)
+            .label("Close")
+            .emoji('🔒')
+            .style(ButtonStyle::Danger),
+    ];
+    let message = CreateMessage::new()
+        .content(format!(
+            "{} {}",
+            member.mention(),
+            config.staff_role().mention()
+        ))
+        .embed(embed)
+        .components(vec![CreateActionRow::Buttons(buttons)])
+        .allowed_mentions(
+            CreateAllowedMentions::new()
+                .users(vec![member.user.id])
+                .roles(vec![config.staff_role()]),
+        );
+    if let Err(e) = thread.id.send_message(http, message).await {
+        warn!(error = %e, "Failed to post ticket opening message");
+    }
+
+    info!(user = %member.user.name, ticket = id, "Ticket opened");
+    Ok(format!("Your ticket is open: {}", thread.mention()))
+}
+
+/// Assign a ticket to a staff member, unless someone already has it.
+pub async fn claim(
+    http: &Http,
+    db: &PgPool,
+    ticket: &Ticket,
+    staff: UserId,
+) -> Result<String, String> {
+    if let Some(claimed_by) = ticket.claimed_by() {
+        return Err(format!(
+            "{} already claimed this ticket.",
+            claimed_by.mention()
+        ));
+    }
+
+    let claimed =
+        sqlx::query("UPDATE tickets SET claimed_by = $2 WHERE id = $1 AND claimed_by IS NULL")
+            .bind(ticket.id)
+            .bind(staff.get() as i64)
+            .execute(db)
+            .await
+            .map_err(|e| {
+                error!(error = %e, "Failed to claim ticket");
+                "Something went wrong. Please try again.".to_string()
+            })?;
+    if claimed.rows_affected() == 0 {
+        return Err("Someone else just claimed this ticket.".to_string());
+    }
+
+    let embed = embeds::crimson_embed().description(format!(
+        "🙋 {} claimed this ticket and will help you from here.",
+        staff.mention()
+    ));
+    if let Err(e) = ticket
+        .channel()
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await
+    {
+        warn!(error = %e, "Failed to announce ticket claim");
+    }
+
+    Ok(format!("You claimed ticket #{}.", ticket.id))
+}
+
+/// Close a ticket: upload its transcript to `log_channel`, then archive and
+/// lock the thread. `closed_by` is `None` when it closed for inactivity.
+pub async fn close(
+    http: &Http,
+    db: &PgPool,
+    ticket: &Ticket,
+    closed_by: Option<UserId>,
+    reason: Option<&str>,
+    log_channel: Option<ChannelId>,
+) -> Result<String, String> {
+    // Marking it closed first means a second close press finds nothing to do.
+    let closed_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "UPDATE tickets SET status = 'closed', closed_at = now(), closed_by = $2, close_reason = $3 \
+         WHERE id = $1 AND status = 'open' RETURNING closed_at",
+    )
+    .bind(ticket.id)
+    .bind(closed_by.map(|id| id.get() as i64))
+    .bind(reason)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| {
+        error!(error = %e, "Failed to close ticket");
+        "Something went wrong. Please try again.".to_string()
+    })?;
+    let Some(closed_at) = closed_at else {
+        return Err("This ticket is already closed.".to_string());
+    };
+
+    let closer = match closed_by {
+        Some(user_id) => user_id.mention().to_string(),
+        None => "inactivity".to_string(),
+    };
+    let mut notice = format!("🔒 Ticket closed by {closer}.");
+    if let Some(reason) = reason {
+        let _ = write!(notice, "\n**Reason:** {reason}");
+    }
+    if let Err(e) = ticket
+        .channel()
+        .send_message(
+            http,
+            CreateMessage::new().embed(embeds::warning_embed().description(&notice)),
+        )
+        .await
+    {
+        warn!(error = %e, "Failed to post ticket close notice");
+    }
+
+    if let Some(log_channel) = log_channel {
+        match transcript(http, ticket, closed_by, reason, closed_at).await {
+            Ok(text) => {
+                let mut embed = embeds::moderation_embed()
+                    .title(format!("Ticket #{} Closed", ticket.id))
+                    .field("Opened By", ticket.user().mention().to_string(), true)
+                    .field("Closed By", closer.clone(), true)
+                    .field(
+                        "Claimed By",
+                        ticket
+                            .claimed_by()
+                            .map(|id| id.mention().to_string())
+                            .unwrap_or_else(|| "Nobody".to_string()),
+                        true,
+                    )
+                    .field(
+                        "Opened",
+                        format!("<t:{}:f>", ticket.created_at.timestamp()),
+                        true,
+                    );
+                if let Some(reason) = reason {
+                    embed = embed.field("Reason", reason, false);
+                }
+                let message = CreateMessage::new()
+                    .embed(embed)
+                    .add_file(CreateAttachment::bytes(
+                        text.into_bytes(),
+                        format!("ticket-{}.txt", ticket.id),
+                    ));
+                match log_channel.send_message(http, message).await {
+                    Ok(message) => {
+                        if let Err(e) = sqlx::query(
+                            "UPDATE tickets SET transcript_message_id = $2 WHERE id = $1",
+                        )
+                        .bind(ticket.id)
+                        .bind(message.id.get() as i64)
+                        .execute(db)
+                        .await
+                        {
+                            error!(error = %e, "Failed to record ticket transcript");
+                        }
+                    }
+                    Err(e) => error!(error = %e, "Failed to upload ticket transcript"),
+                }
+            }
+            Err(e) => error!(ticket = ticket.id, error = %e, "Failed to read ticket transcript"),
+        }
+    }
+
+    if let Err(e) = ticket
+        .channel()
+        .edit_thread(http, EditThread::new().archived(true).locked(true))
+        .await
+    {
+        warn!(error = %e, "Failed to archive ticket thread");
+    }
+
+    info!(ticket = ticket.id, closed_by = ?closed_by, "Ticket closed");
+    Ok(format!("Closed ticket #{}.", ticket.id))
+}
+
+/// Count a message towards a ticket's activity, if it's in one.
+pub async fn touch(db: &PgPool, channel_id: ChannelId) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE tickets SET last_activity_at = now() WHERE channel_id = $1 AND status = 'open'",
+    )
+    .bind(channel_id.get() as i64)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark a ticket closed when its thread is deleted, since there's nothing
+/// left to transcribe.
+pub async fn handle_thread_delete(db: &PgPool, channel_id: ChannelId) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE tickets SET status = 'closed', closed_at = now(), close_reason = 'Thread deleted' \
+         WHERE channel_id = $1 AND status = 'open'",
+    )
+    .bind(channel_id.get() as i64)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Close tickets nobody has written in for the guild's inactivity limit.
+pub async fn register_inactivity_job(scheduler: &Scheduler, log_channel_id: Option<ChannelId>) {
+    scheduler.register(INACTIVITY_JOB, move |ctx, _job| async move {
+        let stale: Vec<Ticket> = sqlx::query_as(
+            "SELECT t.id, t.guild_id, t.channel_id, t.user_id, t.claimed_by, t.created_at \
+             FROM tickets t JOIN ticket_config c ON c.guild_id = t.guild_id \
+             WHERE t.status = 'open' AND c.inactivity_hours > 0 \
+                AND t.last_activity_at < now() - make_interval(hours => c.inactivity_hours)",
+        )
+        .fetch_all(&ctx.db)
+        .await
+        .map_err(|e| format!("Failed to load inactive tickets: {e}"))?;
+
+        for ticket in stale {
+            let guild_id = GuildId::new(ticket.guild_id as u64);
+            let log_channel = match TicketConfig::load(&ctx.db, guild_id).await {
+                Ok(config) => config.and_then(|c| c.log_channel(log_channel_id)),
+                Err(e) => {
+                    warn!(error = %e, "Failed to load ticket settings");
+                    log_channel_id
+                }
+            };
+            if let Err(e) = close(
+                &ctx.http,
+                &ctx.db,
+                &ticket,
+                None,
+                Some("No activity"),
+                log_channel,
+            )
+            .await
+            {
+                warn!(ticket = ticket.id, error = %e, "Failed to close inactive ticket");
+            }
+        }
+        Ok(())
+    });
+
+    if let Err(e) = scheduler
+        .ensure_recurring(
+            INACTIVITY_JOB,
+            INACTIVITY_JOB,
+            INACTIVITY_CRON,
+            None,
+            serde_json::Value::Null,
+        )
+        .await
+    {
+        error!(error = %e, "Failed to schedule ticket auto-close");
+    }
+}
+
+/// A plain-text log of the ticket thread, oldest message first.
+async fn transcript(
+    http: &Http,
+    ticket: &Ticket,
+    closed_by: Option<UserId>,
+    reason: Option<&str>,
+    closed_at: DateTime<Utc>,
+) -> Result<String, serenity::Error> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut before: Option<MessageId> = None;
+    while messages.len() < MAX_TRANSCRIPT_MESSAGES {
+        let mut request = GetMessages::new().limit(100);
+        if let Some(before) = before {
+            request = request.before(before);
+        }
+        let page = ticket.channel().messages(http, request).await?;
+        let Some(oldest) = page.last() else {
+            break;
+        };
+        before = Some(oldest.id);
+        messages.extend(page);
+    }
+    messages.reverse();
+
+    let mut text = format!(
+        "Ticket #{}\nOpened by {} on {}\nClosed by {} on {}\n",
+        ticket.id,
+        ticket.user_id,
+        ticket.created_at.format("%Y-%m-%d %H:%M UTC"),
+        closed_by
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| "inactivity".to_string()),
+        closed_at.format("%Y-%m-%d %H:%M UTC"),
+    );
+    if let Some(reason) = reason {
+        let _ = writeln!(text, "Reason: {reason}");
+    }
+    if messages.len() >= MAX_TRANSCRIPT_MESSAGES {
+        let _ = writeln!(
+            text,
+            "Only the last {MAX_TRANSCRIPT_MESSAGES} messages are included."
+        );
+    }
+    text.push('\n');
+
+    for message in &messages {
+        let _ = writeln!(
+            text,
+            "[{}] {} ({}): {}",
+            message.timestamp.format("%Y-%m-%d %H:%M"),
+            message.author.name,
+            message.author.id,
+            message.content
+        );
+        for embed in &message.embeds {
+            let parts: Vec<&str> = [embed.title.as_deref(), embed.description.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect();
+            if !parts.is_empty() {
+                let _ = writeln!(text, "    [embed] {}", parts.join(" — "));
+            }
+        }
+        for attachment in &message.attachments {
+            let _ = writeln!(text, "    [attachment] {}", attachment.url);
+        }
+    }
+
+    Ok(text)
+}
+
+async fn respond(ctx: &Context, component: &ComponentInteraction, embed: CreateEmbed) {
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(true),
+    );
+    if let Err(e) = component.create_response(&ctx.http, response).await {
+        error!(error = %e, "Failed to respond to ticket button");
+    }
+}
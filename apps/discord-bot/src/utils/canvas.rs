@@ -0,0 +1,70 @@
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+
+/// Draw `text` starting at `x` on the given baseline, clipped to the image.
+pub fn draw_text(
+    image: &mut RgbaImage,
+    font: &FontArc,
+    text: &str,
+    size: f32,
+    x: f32,
+    baseline: f32,
+    color: Rgba<u8>,
+) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut caret = x;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        previous = Some(id);
+
+        let glyph = id.with_scale_and_position(size, point(caret, baseline));
+        caret += scaled.h_advance(id);
+
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+                blend(image.get_pixel_mut(px as u32, py as u32), color, coverage);
+            }
+        });
+    }
+}
+
+pub fn text_width(font: &FontArc, text: &str, size: f32) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Alpha-blend `color` over `pixel`, scaled by `coverage`.
+pub fn blend(pixel: &mut Rgba<u8>, color: Rgba<u8>, coverage: f32) {
+    let alpha = coverage * color[3] as f32 / 255.0;
+    for i in 0..3 {
+        pixel[i] = (pixel[i] as f32 * (1.0 - alpha) + color[i] as f32 * alpha) as u8;
+    }
+    pixel[3] = pixel[3].max((alpha * 255.0) as u8);
+}
+
+/// An opaque colour from `0xRRGGBB`, as used by [`Colors`](super::embeds::Colors).
+pub fn rgba(color: u32) -> Rgba<u8> {
+    Rgba([(color >> 16) as u8, (color >> 8) as u8, color as u8, 255])
+}
@@ -1,2 +1,4 @@
+pub mod canvas;
 pub mod embeds;
 pub mod permissions;
+pub mod templates;
@@ -1,4 +1,4 @@
-use serenity::all::{Member, Permissions};
+use serenity::all::{Guild, Member, Permissions, Role, RoleId, UserId};
 
 /// Check if a member has moderator-level permissions.
 pub fn is_moderator(member: &Member) -> bool {
@@ -11,3 +11,49 @@ pub fn is_admin(member: &Member) -> bool {
     let perms = member.permissions.unwrap_or(Permissions::empty());
     perms.administrator()
 }
+
+/// Why `role` can't be handed out on `member`'s behalf, if it can't.
+///
+/// Managed roles belong to integrations, and neither the member nor the bot
+/// may grant a role at or above their own highest role. The server owner is
+/// only held to the bot's limit.
+pub fn role_assign_error(
+    guild: &Guild,
+    member: &Member,
+    bot_id: UserId,
+    role: &Role,
+) -> Option<String> {
+    if role.managed {
+        return Some(format!(
+            "**{}** is managed by an integration and can't be assigned.",
+            role.name
+        ));
+    }
+
+    let top_position = |roles: &[RoleId]| {
+        roles
+            .iter()
+            .filter_map(|id| guild.roles.get(id))
+            .map(|r| r.position)
+            .max()
+            .unwrap_or(0)
+    };
+    if member.user.id != guild.owner_id && role.position >= top_position(&member.roles) {
+        return Some(format!(
+            "**{}** is at or above your highest role.",
+            role.name
+        ));
+    }
+    let bot_roles = guild
+        .members
+        .get(&bot_id)
+        .map(|bot| bot.roles.as_slice())
+        .unwrap_or_default();
+    if role.position >= top_position(bot_roles) {
+        return Some(format!(
+            "**{}** is at or above the bot's highest role.",
+            role.name
+        ));
+    }
+    None
+}
@@ -0,0 +1,92 @@
+use crate::utils::canvas::{blend, draw_text, rgba, text_width};
+use crate::utils::embeds::Colors;
+use ab_glyph::FontArc;
+use image::{ImageFormat, Rgba, RgbaImage};
+use rand::Rng;
+use std::io::Cursor;
+
+/// Characters used in codes, leaving out look-alikes such as 0/O and 1/I.
+const ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+pub const CODE_LENGTH: usize = 6;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 110;
+
+/// A fresh random code.
+pub fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Draw `code` with jittered letters and noise lines as a PNG.
+pub fn render(font: &FontArc, code: &str) -> Result<Vec<u8>, String> {
+    let mut rng = rand::thread_rng();
+    let mut image = RgbaImage::from_pixel(WIDTH, HEIGHT, Rgba([24, 6, 10, 255]));
+
+    // Faint lines behind the text
+    for _ in 0..6 {
+        let color = Rgba([120, 40, 55, 255]);
+        let (x0, y0) = (rng.gen_range(0.0..40.0), rng.gen_range(0.0..HEIGHT as f32));
+        let (x1, y1) = (
+            rng.gen_range(WIDTH as f32 - 40.0..WIDTH as f32),
+            rng.gen_range(0.0..HEIGHT as f32),
+        );
+        draw_line(&mut image, (x0, y0), (x1, y1), color);
+    }
+
+    let slot = (WIDTH as f32 - 40.0) / code.len() as f32;
+    for (i, c) in code.chars().enumerate() {
+        let size = rng.gen_range(44.0..58.0);
+        let letter = c.to_string();
+        let x = 20.0 + slot * i as f32 + (slot - text_width(font, &letter, size)) / 2.0;
+        let baseline = rng.gen_range(68.0..88.0);
+        let shade = rng.gen_range(200..=255);
+        draw_text(
+            &mut image,
+            font,
+            &letter,
+            size,
+            x + rng.gen_range(-4.0..4.0),
+            baseline,
+            Rgba([shade, shade, shade, 255]),
+        );
+    }
+
+    // Lines across the text make it harder to read automatically.
+    for _ in 0..3 {
+        let (y0, y1) = (
+            rng.gen_range(30.0..HEIGHT as f32 - 20.0),
+            rng.gen_range(30.0..HEIGHT as f32 - 20.0),
+        );
+        draw_line(
+            &mut image,
+            (0.0, y0),
+            (WIDTH as f32, y1),
+            rgba(Colors::CRIMSON),
+        );
+    }
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode captcha: {e}"))?;
+    Ok(png)
+}
+
+fn draw_line(image: &mut RgbaImage, from: (f32, f32), to: (f32, f32), color: Rgba<u8>) {
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil() as u32;
+    for step in 0..=steps {
+        let t = step as f32 / steps.max(1) as f32;
+        let x = from.0 + (to.0 - from.0) * t;
+        let y = from.1 + (to.1 - from.1) * t;
+        for dy in 0..2 {
+            let (px, py) = (x as u32, y as u32 + dy);
+            if px < image.width() && py < image.height() {
+                blend(image.get_pixel_mut(px, py), color, 0.8);
+            }
+        }
+    }
+}
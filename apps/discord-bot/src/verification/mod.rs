@@ -0,0 +1,593 @@
+pub mod captcha;
+
+use crate::scheduler::Scheduler;
+use crate::utils::embeds;
+use crate::Data;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serenity::all::{
+    ActionRowComponent, ButtonStyle, ChannelId, ComponentInteraction, Context, CreateActionRow,
+    CreateAttachment, CreateButton, CreateEmbed, CreateInputText, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, CreateModal, GuildId, Http, InputTextStyle,
+    Member, Mentionable, MessageId, ModalInteraction, User, UserId,
+};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+/// Custom ID of the gate message button: `verify:<user id>`.
+pub const VERIFY_PREFIX: &str = "verify";
+/// Custom ID of the "Enter code" button under a captcha: `verify_code:<user id>`.
+pub const CODE_PREFIX: &str = "verify_code";
+/// Custom ID of the captcha answer modal: `verify_answer:<user id>`.
+pub const ANSWER_PREFIX: &str = "verify_answer";
+
+pub const DEFAULT_TIMEOUT_MINUTES: i32 = 10;
+
+/// Scheduler job that kicks a member who didn't verify in time.
+const TIMEOUT_JOB: &str = "verification_timeout";
+
+const CAPTCHA_FILE_NAME: &str = "captcha.png";
+const ANSWER_INPUT: &str = "code";
+
+/// What a new member has to do to get in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum VerificationMode {
+    Button,
+    Captcha,
+}
+
+impl VerificationMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VerificationMode::Button => "button",
+            VerificationMode::Captcha => "captcha",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "button" => Some(VerificationMode::Button),
+            "captcha" => Some(VerificationMode::Captcha),
+            _ => None,
+        }
+    }
+}
+
+/// A guild's verification gate.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct VerificationConfig {
+    pub enabled: bool,
+    pub channel_id: i64,
+    pub mode: String,
+    pub timeout_minutes: i32,
+}
+
+impl VerificationConfig {
+    pub async fn load(db: &PgPool, guild_id: GuildId) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT enabled, channel_id, mode, timeout_minutes \
+             FROM verification_config WHERE guild_id = $1",
+        )
+        .bind(guild_id.get() as i64)
+        .fetch_optional(db)
+        .await
+    }
+
+    pub async fn save(&self, db: &PgPool, guild_id: GuildId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO verification_config (guild_id, enabled, channel_id, mode, timeout_minutes) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (guild_id) DO UPDATE SET \
+                enabled = EXCLUDED.enabled, channel_id = EXCLUDED.channel_id, \
+                mode = EXCLUDED.mode, timeout_minutes = EXCLUDED.timeout_minutes",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(self.enabled)
+        .bind(self.channel_id)
+        .bind(&self.mode)
+        .bind(self.timeout_minutes)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Turn the gate off, keeping its settings. Returns whether it was on.
+    pub async fn disable(db: &PgPool, guild_id: GuildId) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE verification_config SET enabled = FALSE WHERE guild_id = $1 AND enabled",
+        )
+        .bind(guild_id.get() as i64)
+        .execute(db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub fn mode(&self) -> VerificationMode {
+        VerificationMode::parse(&self.mode).unwrap_or(VerificationMode::Button)
+    }
+
+    pub fn channel(&self) -> ChannelId {
+        ChannelId::new(self.channel_id as u64)
+    }
+}
+
+/// A member waiting at the gate.
+#[derive(Debug, sqlx::FromRow)]
+struct Pending {
+    channel_id: i64,
+    message_id: Option<i64>,
+    /// The captcha answer, once one has been shown.
+    challenge: Option<String>,
+}
+
+/// How many members are waiting at a guild's gate.
+pub async fn pending_count(db: &PgPool, guild_id: GuildId) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM pending_verifications WHERE guild_id = $1")
+        .bind(guild_id.get() as i64)
+        .fetch_one(db)
+        .await
+}
+
+/// Hold a new member at the gate if the guild requires verification.
+///
+/// Returns `false` when there's no gate, or it couldn't be set up, in which
+/// case the member should get the auto-roles straight away.
+pub async fn start(ctx: &Context, member: &Member, data: &Data) -> bool {
+    if member.user.bot {
+        return false;
+    }
+
+    let config = match VerificationConfig::load(&data.db, member.guild_id).await {
+        Ok(Some(config)) if config.enabled => config,
+        Ok(_) => return false,
+        Err(e) => {
+            error!(error = %e, "Failed to load verification settings");
+            return false;
+        }
+    };
+
+    let expires_at = Utc::now() + ChronoDuration::minutes(i64::from(config.timeout_minutes));
+    let action = match config.mode() {
+        VerificationMode::Button => "press **Verify**",
+        VerificationMode::Captcha => "press **Verify** and enter the code you're shown",
+    };
+    let embed = embeds::crimson_embed()
+        .title("Verification Required")
+        .description(format!(
+            "Welcome, {}! To get access to the server, {action} before <t:{}:t>. \
+             Members who don't verify in time are removed.",
+            member.mention(),
+            expires_at.timestamp()
+        ));
+    let button = CreateButton::new(format!("{VERIFY_PREFIX}:{}", member.user.id))
+        .label("Verify")
+        .emoji('✅')
+        .style(ButtonStyle::Success);
+    let message = CreateMessage::new()
+        .content(member.mention().to_string())
+        .embed(embed)
+        .components(vec![CreateActionRow::Buttons(vec![button])]);
+
+    let message = match config.channel().send_message(&ctx.http, message).await {
+        Ok(message) => message,
+        Err(e) => {
+            error!(error = %e, "Failed to post verification gate message, skipping verification");
+            return false;
+        }
+    };
+
+    let saved = sqlx::query(
+        "INSERT INTO pending_verifications (guild_id, user_id, channel_id, message_id, expires_at) \
+         VALUES ($1, $2, $3, $4, $5) \
+         ON CONFLICT (guild_id, user_id) DO UPDATE SET \
+            channel_id = EXCLUDED.channel_id, message_id = EXCLUDED.message_id, \
+            challenge = NULL, expires_at = EXCLUDED.expires_at",
+    )
+    .bind(member.guild_id.get() as i64)
+    .bind(member.user.id.get() as i64)
+    .bind(config.channel_id)
+    .bind(message.id.get() as i64)
+    .bind(expires_at)
+    .execute(&data.db)
+    .await;
+
+    let scheduled = match saved {
+        Ok(_) => data
+            .scheduler
+            .schedule_once(
+                TIMEOUT_JOB,
+                Some(member.guild_id),
+                timeout_payload(member.guild_id, member.user.id),
+                expires_at,
+            )
+            .await
+            .map(|_| ()),
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = scheduled {
+        error!(error = %e, "Failed to record pending verification, skipping verification");
+        let _ = message.delete(&ctx.http).await;
+        return false;
+    }
+
+    info!(user = %member.user.name, expires_at = %expires_at, "Member is waiting for verification");
+    true
+}
+
+/// Clear the gate for a member who left before verifying.
+pub async fn handle_leave(ctx: &Context, guild_id: GuildId, user: &User, data: &Data) {
+    let pending = match take_pending(&data.db, guild_id, user.id, None).await {
+        Ok(Some(pending)) => pending,
+        Ok(None) => return,
+        Err(e) => {
+            error!(error = %e, "Failed to clear pending verification");
+            return;
+        }
+    };
+
+    clean_up(&ctx.http, &data.scheduler, guild_id, user.id, &pending).await;
+    log(
+        &ctx.http,
+        data.config.log_channel_id,
+        embeds::warning_embed()
+            .title("Verification Abandoned")
+            .description(format!(
+                "{} ({}) left before verifying.",
+                user.mention(),
+                user.name
+            )),
+    )
+    .await;
+}
+
+/// Run the timeout job, which kicks members still at the gate.
+pub fn register_timeout_job(scheduler: &Scheduler, log_channel_id: Option<ChannelId>) {
+    scheduler.register(TIMEOUT_JOB, move |ctx, job| async move {
+        let id = |key: &str| job.payload.get(key).and_then(serde_json::Value::as_u64);
+        let (Some(guild_id), Some(user_id)) = (id("guild_id"), id("user_id")) else {
+            return Err("Verification timeout job has no member".to_string());
+        };
+        let (guild_id, user_id) = (GuildId::new(guild_id), UserId::new(user_id));
+
+        // Only a still-expired row counts: the member may have verified, or rejoined since.
+        let pending = take_pending(&ctx.db, guild_id, user_id, Some(Utc::now()))
+            .await
+            .map_err(|e| format!("Failed to load pending verification: {e}"))?;
+        let Some(pending) = pending else {
+            return Ok(());
+        };
+
+        let outcome = match guild_id
+            .kick_with_reason(&ctx.http, user_id, "Did not complete verification in time")
+            .await
+        {
+            Ok(()) => "was removed for not verifying in time.",
+            Err(e) => {
+                warn!(user_id = %user_id, error = %e, "Failed to kick unverified member");
+                "didn't verify in time, but couldn't be removed. Check the bot's Kick Members permission."
+            }
+        };
+
+        delete_gate_message(&ctx.http, &pending).await;
+        log(
+            &ctx.http,
+            log_channel_id,
+            embeds::moderation_embed()
+                .title("Verification Timed Out")
+                .description(format!("{} {outcome}", user_id.mention())),
+        )
+        .await;
+        info!(user_id = %user_id, "Unverified member timed out");
+        Ok(())
+    });
+}
+
+/// Handle the gate button and the "Enter code" button.
+pub async fn handle_component(ctx: &Context, component: &ComponentInteraction, data: &Data) {
+    let (Some(prefix), Some(guild_id)) = (
+        target(&component.data.custom_id, component.user.id),
+        component.guild_id,
+    ) else {
+        let response = reply(embeds::error_embed().description("This button isn't for you."));
+        let _ = component.create_response(&ctx.http, response).await;
+        return;
+    };
+
+    let response = match prefix {
+        CODE_PREFIX => answer_modal(component.user.id),
+        _ => match verify_pressed(ctx, guild_id, &component.user, data).await {
+            Ok(response) => response,
+            Err(e) => {
+                error!(error = %e, "Failed to handle verification");
+                reply(embeds::error_embed().description("Something went wrong. Please try again."))
+            }
+        },
+    };
+
+    if let Err(e) = component.create_response(&ctx.http, response).await {
+        error!(error = %e, "Failed to respond to verification button");
+    }
+}
+
+/// Check a submitted captcha answer.
+pub async fn handle_modal(ctx: &Context, modal: &ModalInteraction, data: &Data) {
+    let (Some(_), Some(guild_id)) = (target(&modal.data.custom_id, modal.user.id), modal.guild_id)
+    else {
+        return;
+    };
+
+    let answer = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == ANSWER_INPUT => {
+                input.value.clone()
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let response = match check_answer(ctx, guild_id, &modal.user, &answer, data).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!(error = %e, "Failed to check captcha answer");
+            reply(embeds::error_embed().description("Something went wrong. Please try again."))
+        }
+    };
+
+    if let Err(e) = modal.create_response(&ctx.http, response).await {
+        error!(error = %e, "Failed to respond to captcha answer");
+    }
+}
+
+/// The prefix of a `<prefix>:<user id>` custom ID, if it belongs to `user_id`.
+fn target(custom_id: &str, user_id: UserId) -> Option<&str> {
+    let (prefix, target) = custom_id.split_once(':')?;
+    (target.parse::<u64>().ok()? == user_id.get()).then_some(prefix)
+}
+
+async fn verify_pressed(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    data: &Data,
+) -> Result<CreateInteractionResponse, sqlx::Error> {
+    if load_pending(&data.db, guild_id, user.id).await?.is_none() {
+        return Ok(not_pending());
+    }
+    let mode = VerificationConfig::load(&data.db, guild_id)
+        .await?
+        .map(|config| config.mode())
+        .unwrap_or(VerificationMode::Button);
+
+    if mode == VerificationMode::Button {
+        return complete(ctx, guild_id, user, data).await;
+    }
+
+    // A code shown as text can be read by anyone, bots included, so without
+    // an image the gate falls back to the button.
+    let code = captcha::generate_code();
+    let png = match data.welcome_cards.as_ref() {
+        Some(cards) => captcha::render(cards.font(), &code),
+        None => Err("no captcha font is loaded".to_string()),
+    };
+    let png = match png {
+        Ok(png) => png,
+        Err(e) => {
+            warn!(error = %e, "Can't show a captcha, verifying with the button instead");
+            return complete(ctx, guild_id, user, data).await;
+        }
+    };
+
+    sqlx::query(
+        "UPDATE pending_verifications SET challenge = $3 WHERE guild_id = $1 AND user_id = $2",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user.id.get() as i64)
+    .bind(&code)
+    .execute(&data.db)
+    .await?;
+
+    let embed = embeds::crimson_embed()
+        .title("Enter the Code")
+        .description("Type the characters below using **Enter code**.")
+        .attachment(CAPTCHA_FILE_NAME);
+    let message = CreateInteractionResponseMessage::new()
+        .ephemeral(true)
+        .add_file(CreateAttachment::bytes(png, CAPTCHA_FILE_NAME));
+
+    // Pressing Verify again replaces the code.
+    let button = CreateButton::new(format!("{CODE_PREFIX}:{}", user.id))
+        .label("Enter code")
+        .style(ButtonStyle::Primary);
+    Ok(CreateInteractionResponse::Message(
+        message
+            .embed(embed)
+            .components(vec![CreateActionRow::Buttons(vec![button])]),
+    ))
+}
+
+async fn check_answer(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    answer: &str,
+    data: &Data,
+) -> Result<CreateInteractionResponse, sqlx::Error> {
+    let Some(pending) = load_pending(&data.db, guild_id, user.id).await? else {
+        return Ok(not_pending());
+    };
+
+    let answer: String = answer
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase();
+    if pending.challenge.as_deref() == Some(answer.as_str()) {
+        return complete(ctx, guild_id, user, data).await;
+    }
+
+    // Each code gets one try.
+    sqlx::query(
+        "UPDATE pending_verifications SET challenge = NULL WHERE guild_id = $1 AND user_id = $2",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user.id.get() as i64)
+    .execute(&data.db)
+    .await?;
+
+    Ok(reply(embeds::error_embed().description(
+        "That code didn't match. Press **Verify** for a new one.",
+    )))
+}
+
+/// Let the member in: grant the auto-roles and take down the gate.
+async fn complete(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    data: &Data,
+) -> Result<CreateInteractionResponse, sqlx::Error> {
+    let Some(pending) = take_pending(&data.db, guild_id, user.id, None).await? else {
+        return Ok(not_pending());
+    };
+
+    for role_id in &data.config.autorole_ids {
+        if let Err(e) = ctx
+            .http
+            .add_member_role(guild_id, user.id, *role_id, Some("Passed verification"))
+            .await
+        {
+            error!(user = %user.name, role_id = %role_id, error = %e, "Failed to assign auto-role");
+        }
+    }
+
+    clean_up(&ctx.http, &data.scheduler, guild_id, user.id, &pending).await;
+    log(
+        &ctx.http,
+        data.config.log_channel_id,
+        embeds::success_embed()
+            .title("Member Verified")
+            .description(format!(
+                "{} ({}) passed verification.",
+                user.mention(),
+                user.name
+            )),
+    )
+    .await;
+    info!(user = %user.name, "Member verified");
+
+    Ok(reply(
+        embeds::success_embed().description("You're verified. Welcome in!"),
+    ))
+}
+
+async fn load_pending(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<Pending>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT channel_id, message_id, challenge FROM pending_verifications \
+         WHERE guild_id = $1 AND user_id = $2 AND expires_at > now()",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .fetch_optional(db)
+    .await
+}
+
+/// Remove a member's pending row; with `expired_by`, only if it expired by then.
+async fn take_pending(
+    db: &PgPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    expired_by: Option<DateTime<Utc>>,
+) -> Result<Option<Pending>, sqlx::Error> {
+    sqlx::query_as(
+        "DELETE FROM pending_verifications \
+         WHERE guild_id = $1 AND user_id = $2 AND ($3::TIMESTAMPTZ IS NULL OR expires_at <= $3) \
+         RETURNING channel_id, message_id, challenge",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(user_id.get() as i64)
+    .bind(expired_by)
+    .fetch_optional(db)
+    .await
+}
+
+fn timeout_payload(guild_id: GuildId, user_id: UserId) -> serde_json::Value {
+    serde_json::json!({ "guild_id": guild_id.get(), "user_id": user_id.get() })
+}
+
+async fn clean_up(
+    http: &Http,
+    scheduler: &Scheduler,
+    guild_id: GuildId,
+    user_id: UserId,
+    pending: &Pending,
+) {
+    if let Err(e) = scheduler
+        .cancel_matching(TIMEOUT_JOB, timeout_payload(guild_id, user_id))
+        .await
+    {
+        warn!(error = %e, "Failed to cancel verification timeout");
+    }
+    delete_gate_message(http, pending).await;
+}
+
+async fn delete_gate_message(http: &Http, pending: &Pending) {
+    let Some(message_id) = pending.message_id else {
+        return;
+    };
+    let channel_id = ChannelId::new(pending.channel_id as u64);
+    if let Err(e) = channel_id
+        .delete_message(http, MessageId::new(message_id as u64))
+        .await
+    {
+        warn!(error = %e, "Failed to delete verification gate message");
+    }
+}
+
+async fn log(http: &Http, channel_id: Option<ChannelId>, embed: CreateEmbed) {
+    let Some(channel_id) = channel_id else {
+        return;
+    };
+    if let Err(e) = channel_id
+        .send_message(http, CreateMessage::new().embed(embed))
+        .await
+    {
+        error!(error = %e, "Failed to send verification log");
+    }
+}
+
+fn answer_modal(user_id: UserId) -> CreateInteractionResponse {
+    let input = CreateInputText::new(InputTextStyle::Short, "Code", ANSWER_INPUT)
+        .min_length(captcha::CODE_LENGTH as u16)
+        .max_length(captcha::CODE_LENGTH as u16 * 2)
+        .required(true);
+    CreateInteractionResponse::Modal(
+        CreateModal::new(format!("{ANSWER_PREFIX}:{user_id}"), "Verification")
+            .components(vec![CreateActionRow::InputText(input)]),
+    )
+}
+
+fn not_pending() -> CreateInteractionResponse {
+    reply(
+        embeds::warning_embed().description(
+            "There's nothing to verify: you're already verified, or your time ran out.",
+        ),
+    )
+}
+
+fn reply(embed: CreateEmbed) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(true),
+    )
+}
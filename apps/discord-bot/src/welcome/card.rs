@@ -0,0 +1,304 @@
+use super::templates::WelcomeVars;
+use crate::config::WelcomeCardConfig;
+use crate::utils::canvas::{blend, draw_text, rgba, text_width};
+use crate::utils::embeds::Colors;
+use ab_glyph::FontArc;
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, Rgba, RgbaImage};
+use serenity::all::{CreateAttachment, User};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// File name of the card attachment, for `attachment://` embed URLs.
+pub const CARD_FILE_NAME: &str = "welcome.png";
+
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 340;
+
+const AVATAR_SIZE: u32 = 220;
+const AVATAR_RING: u32 = 8;
+const AVATAR_X: i64 = 60;
+const TEXT_X: f32 = 340.0;
+const TEXT_MAX_WIDTH: f32 = WIDTH as f32 - TEXT_X - 50.0;
+
+/// Avatars kept before the least recently used is dropped.
+const MAX_CACHED_AVATARS: usize = 128;
+
+const AVATAR_TIMEOUT: Duration = Duration::from_secs(5);
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const MUTED: Rgba<u8> = Rgba([200, 200, 205, 255]);
+
+/// What's written on a card.
+struct CardInfo {
+    avatar_url: String,
+    name: String,
+    server: String,
+    member_count: String,
+}
+
+struct CachedAvatar {
+    image: Arc<RgbaImage>,
+    used: Instant,
+}
+
+/// Renders PNG welcome cards.
+///
+/// Avatars are cached by URL, which changes whenever a user changes their
+/// avatar, so cached copies never go stale.
+#[derive(Clone)]
+pub struct WelcomeCards {
+    font: FontArc,
+    background: Option<Arc<RgbaImage>>,
+    http: reqwest::Client,
+    avatars: Arc<Mutex<HashMap<String, CachedAvatar>>>,
+}
+
+impl WelcomeCards {
+    /// Load the font and background image named in the config.
+    pub fn load(config: &WelcomeCardConfig) -> Result<Self, String> {
+        let font_data = std::fs::read(&config.font)
+            .map_err(|e| format!("Failed to read card font {}: {e}", config.font))?;
+        let font = FontArc::try_from_vec(font_data)
+            .map_err(|e| format!("Invalid card font {}: {e}", config.font))?;
+
+        let background = match config.background {
+            Some(ref path) => {
+                let image = image::open(path)
+                    .map_err(|e| format!("Failed to load card background {path}: {e}"))?;
+                let image = image.resize_to_fill(WIDTH, HEIGHT, FilterType::Triangle);
+                Some(Arc::new(image.to_rgba8()))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            font,
+            background,
+            http: reqwest::Client::new(),
+            avatars: Arc::default(),
+        })
+    }
+
+    /// The card font, also used for verification captchas.
+    pub fn font(&self) -> &FontArc {
+        &self.font
+    }
+
+    /// Render `user`'s card as a message attachment.
+    pub async fn attachment(
+        &self,
+        user: &User,
+        vars: &WelcomeVars,
+    ) -> Result<CreateAttachment, String> {
+        // Animated avatars are GIFs, which the card can't decode; use a still frame.
+        let info = CardInfo {
+            avatar_url: user
+                .static_avatar_url()
+                .unwrap_or_else(|| user.default_avatar_url()),
+            name: user.display_name().to_string(),
+            server: vars.server.clone(),
+            member_count: vars.member_count.clone(),
+        };
+        let png = self.render(info).await?;
+        Ok(CreateAttachment::bytes(png, CARD_FILE_NAME))
+    }
+
+    /// Render a card as PNG bytes.
+    async fn render(&self, info: CardInfo) -> Result<Vec<u8>, String> {
+        let avatar = self.avatar(&info.avatar_url).await?;
+        let font = self.font.clone();
+        let background = self.background.clone();
+
+        tokio::task::spawn_blocking(move || draw(&font, background.as_deref(), &avatar, &info))
+            .await
+            .map_err(|e| format!("Card rendering panicked: {e}"))?
+    }
+
+    async fn avatar(&self, url: &str) -> Result<Arc<RgbaImage>, String> {
+        // Request a size close to what's drawn rather than Discord's 1024px default.
+        let url = format!("{}?size=256", url.split('?').next().unwrap_or(url));
+
+        if let Some(image) = self.cached(&url) {
+            return Ok(image);
+        }
+
+        let bytes = self
+            .http
+            .get(&url)
+            .timeout(AVATAR_TIMEOUT)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to download avatar: {e}"))?
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download avatar: {e}"))?;
+
+        let image = tokio::task::spawn_blocking(move || {
+            image::load_from_memory(&bytes)
+                .map(|image| {
+                    image
+                        .resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3)
+                        .to_rgba8()
+                })
+                .map_err(|e| format!("Failed to decode avatar: {e}"))
+        })
+        .await
+        .map_err(|e| format!("Avatar decoding panicked: {e}"))??;
+
+        let image = Arc::new(image);
+        self.store(url, Arc::clone(&image));
+        Ok(image)
+    }
+
+    fn cached(&self, url: &str) -> Option<Arc<RgbaImage>> {
+        let mut avatars = self.avatars.lock().unwrap_or_else(|e| e.into_inner());
+        avatars.get_mut(url).map(|entry| {
+            entry.used = Instant::now();
+            Arc::clone(&entry.image)
+        })
+    }
+
+    fn store(&self, url: String, image: Arc<RgbaImage>) {
+        let mut avatars = self.avatars.lock().unwrap_or_else(|e| e.into_inner());
+        if avatars.len() >= MAX_CACHED_AVATARS && !avatars.contains_key(&url) {
+            let oldest = avatars
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                avatars.remove(&oldest);
+            }
+        }
+        avatars.insert(
+            url,
+            CachedAvatar {
+                image,
+                used: Instant::now(),
+            },
+        );
+    }
+}
+
+fn draw(
+    font: &FontArc,
+    background: Option<&RgbaImage>,
+    avatar: &RgbaImage,
+    info: &CardInfo,
+) -> Result<Vec<u8>, String> {
+    let crimson = rgba(Colors::CRIMSON);
+
+    let mut card = match background {
+        Some(background) => {
+            let mut card = background.clone();
+            // Darken so the text stays readable on any image.
+            fill(&mut card, Rgba([10, 2, 5, 150]));
+            card
+        }
+        None => gradient(),
+    };
+
+    // Accent bar along the bottom edge
+    for y in HEIGHT - 8..HEIGHT {
+        for x in 0..WIDTH {
+            card.put_pixel(x, y, crimson);
+        }
+    }
+
+    // Avatar in a crimson ring
+    let outer = (AVATAR_SIZE + AVATAR_RING * 2) as f32 / 2.0;
+    let center_x = AVATAR_X as f32 + AVATAR_SIZE as f32 / 2.0;
+    let center_y = HEIGHT as f32 / 2.0;
+    draw_circle(&mut card, center_x, center_y, outer, crimson);
+
+    let mut round_avatar = avatar.clone();
+    mask_circle(&mut round_avatar);
+    let avatar_y = (HEIGHT - AVATAR_SIZE) as i64 / 2;
+    imageops::overlay(&mut card, &round_avatar, AVATAR_X, avatar_y);
+
+    // Text
+    draw_text(&mut card, font, "WELCOME", 34.0, TEXT_X, 110.0, crimson);
+
+    let name_size = fit_size(font, &info.name, 64.0, 36.0);
+    let name = truncate_to_width(font, &info.name, name_size);
+    draw_text(&mut card, font, &name, name_size, TEXT_X, 190.0, WHITE);
+
+    let subtitle = format!("Member #{} of {}", info.member_count, info.server);
+    let subtitle = truncate_to_width(font, &subtitle, 30.0);
+    draw_text(&mut card, font, &subtitle, 30.0, TEXT_X, 250.0, MUTED);
+
+    let mut png = Vec::new();
+    card.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode card: {e}"))?;
+    Ok(png)
+}
+
+/// The default background: dark crimson fading to near-black.
+fn gradient() -> RgbaImage {
+    let from = [58.0, 7.0, 18.0];
+    let to = [14.0, 3.0, 6.0];
+
+    RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let t = (x as f32 / WIDTH as f32 * 0.7 + y as f32 / HEIGHT as f32 * 0.3).min(1.0);
+        let channel = |i: usize| (from[i] + (to[i] - from[i]) * t) as u8;
+        Rgba([channel(0), channel(1), channel(2), 255])
+    })
+}
+
+fn fill(image: &mut RgbaImage, color: Rgba<u8>) {
+    for pixel in image.pixels_mut() {
+        blend(pixel, color, 1.0);
+    }
+}
+
+fn draw_circle(image: &mut RgbaImage, cx: f32, cy: f32, radius: f32, color: Rgba<u8>) {
+    let (min_x, max_x) = ((cx - radius - 1.0) as u32, (cx + radius + 1.0) as u32);
+    let (min_y, max_y) = ((cy - radius - 1.0) as u32, (cy + radius + 1.0) as u32);
+
+    for y in min_y..=max_y.min(image.height() - 1) {
+        for x in min_x..=max_x.min(image.width() - 1) {
+            let coverage = circle_coverage(x as f32 + 0.5 - cx, y as f32 + 0.5 - cy, radius);
+            if coverage > 0.0 {
+                blend(image.get_pixel_mut(x, y), color, coverage);
+            }
+        }
+    }
+}
+
+/// Make everything outside the inscribed circle transparent, with a soft edge.
+fn mask_circle(image: &mut RgbaImage) {
+    let radius = image.width() as f32 / 2.0;
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let coverage = circle_coverage(x as f32 + 0.5 - radius, y as f32 + 0.5 - radius, radius);
+        pixel[3] = (pixel[3] as f32 * coverage) as u8;
+    }
+}
+
+/// How much of the pixel at offset `(dx, dy)` from the centre lies inside the circle.
+fn circle_coverage(dx: f32, dy: f32, radius: f32) -> f32 {
+    (radius + 0.5 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0)
+}
+
+/// The largest size between `min` and `max` at which `text` fits on one line.
+fn fit_size(font: &FontArc, text: &str, max: f32, min: f32) -> f32 {
+    let mut size = max;
+    while size > min && text_width(font, text, size) > TEXT_MAX_WIDTH {
+        size -= 2.0;
+    }
+    size.max(min)
+}
+
+fn truncate_to_width(font: &FontArc, text: &str, size: f32) -> String {
+    if text_width(font, text, size) <= TEXT_MAX_WIDTH {
+        return text.to_string();
+    }
+
+    let mut out: String = text.to_string();
+    while !out.is_empty() && text_width(font, &format!("{out}…"), size) > TEXT_MAX_WIDTH {
+        out.pop();
+    }
+    format!("{}…", out.trim_end())
+}
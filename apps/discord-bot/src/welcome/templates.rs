@@ -0,0 +1,298 @@
+use crate::utils::{embeds, templates};
+use serenity::all::{
+    ChannelId, ChannelType, Context, CreateAttachment, CreateEmbed, CreateMessage, GuildId,
+    Mentionable, User,
+};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Which member event a template is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum WelcomeKind {
+    Welcome,
+    Leave,
+}
+
+impl WelcomeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            WelcomeKind::Welcome => "welcome",
+            WelcomeKind::Leave => "leave",
+        }
+    }
+}
+
+/// A per-guild welcome or leave message.
+///
+/// `title` and `message` may contain placeholders, see [`WelcomeVars`].
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WelcomeTemplate {
+    pub enabled: bool,
+    /// Where to post; `None` uses the configured welcome channel.
+    pub channel_id: Option<i64>,
+    /// Post as an embed rather than plain text.
+    pub embed: bool,
+    pub title: String,
+    pub message: String,
+    /// Attach a generated image card (welcome messages only).
+    pub card: bool,
+}
+
+impl WelcomeTemplate {
+    /// The built-in template used when a guild hasn't customised one.
+    ///
+    /// Leave messages are off until a guild turns them on.
+    pub fn default_for(kind: WelcomeKind) -> Self {
+        match kind {
+            WelcomeKind::Welcome => Self {
+                enabled: true,
+                channel_id: None,
+                embed: true,
+                title: "Welcome to {server}!".into(),
+                message: "Glad to have you here, {user}! Check out {channel:rules} \
+                          and grab your roles in {channel:roles}."
+                    .into(),
+                card: false,
+            },
+            WelcomeKind::Leave => Self {
+                enabled: false,
+                channel_id: None,
+                embed: false,
+                title: "Goodbye!".into(),
+                message: "**{username}** has left {server}.".into(),
+                card: false,
+            },
+        }
+    }
+
+    /// Load the guild's template for `kind`, falling back to the default.
+    pub async fn load(
+        db: &PgPool,
+        guild_id: GuildId,
+        kind: WelcomeKind,
+    ) -> Result<Self, sqlx::Error> {
+        let row = sqlx::query_as::<_, Self>(
+            "SELECT enabled, channel_id, embed, title, message, card \
+             FROM welcome_templates WHERE guild_id = $1 AND kind = $2",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(kind.as_str())
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row.unwrap_or_else(|| Self::default_for(kind)))
+    }
+
+    /// Insert or replace the guild's template for `kind`.
+    pub async fn save(
+        &self,
+        db: &PgPool,
+        guild_id: GuildId,
+        kind: WelcomeKind,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO welcome_templates \
+                (guild_id, kind, enabled, channel_id, embed, title, message, card) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (guild_id, kind) DO UPDATE SET \
+                enabled = EXCLUDED.enabled, channel_id = EXCLUDED.channel_id, \
+                embed = EXCLUDED.embed, title = EXCLUDED.title, message = EXCLUDED.message, \
+                card = EXCLUDED.card",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(kind.as_str())
+        .bind(self.enabled)
+        .bind(self.channel_id)
+        .bind(self.embed)
+        .bind(&self.title)
+        .bind(&self.message)
+        .bind(self.card)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove the guild's customised template so the default applies again.
+    pub async fn reset(
+        db: &PgPool,
+        guild_id: GuildId,
+        kind: WelcomeKind,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM welcome_templates WHERE guild_id = $1 AND kind = $2")
+            .bind(guild_id.get() as i64)
+            .bind(kind.as_str())
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The channel to post in: the template's own, else `fallback`.
+    pub fn channel(&self, fallback: Option<ChannelId>) -> Option<ChannelId> {
+        self.channel_id
+            .map(|id| ChannelId::new(id as u64))
+            .or(fallback)
+    }
+
+    /// Render the template as plain text or a crimson embed.
+    ///
+    /// With a `card` attachment the embed shows it as its image instead of the avatar thumbnail.
+    pub fn render(
+        &self,
+        kind: WelcomeKind,
+        vars: &WelcomeVars,
+        card: Option<CreateAttachment>,
+    ) -> RenderedWelcome {
+        let fill = |text: &str| templates::render(text, |key| vars.get(key));
+        let message = fill(&self.message);
+
+        if !self.embed {
+            return RenderedWelcome {
+                content: message,
+                embed: None,
+                card,
+            };
+        }
+
+        let mut embed = match card {
+            Some(ref card) => embeds::crimson_embed().attachment(&card.filename),
+            None => embeds::crimson_embed().thumbnail(&vars.avatar_url),
+        };
+        let title = fill(&self.title);
+        if !title.is_empty() {
+            embed = embed.title(title);
+        }
+        if !message.is_empty() {
+            embed = embed.description(message);
+        }
+        // The card already shows the member number.
+        if kind == WelcomeKind::Welcome && card.is_none() {
+            embed = embed.field("Member #", &vars.member_count, true);
+        }
+
+        RenderedWelcome {
+            content: String::new(),
+            embed: Some(embed),
+            card,
+        }
+    }
+}
+
+/// A rendered welcome or leave message.
+pub struct RenderedWelcome {
+    pub content: String,
+    pub embed: Option<CreateEmbed>,
+    pub card: Option<CreateAttachment>,
+}
+
+impl RenderedWelcome {
+    pub fn into_message(self) -> CreateMessage {
+        let mut message = CreateMessage::new();
+        if !self.content.is_empty() {
+            message = message.content(self.content);
+        }
+        if let Some(embed) = self.embed {
+            message = message.embed(embed);
+        }
+        if let Some(card) = self.card {
+            message = message.add_file(card);
+        }
+        message
+    }
+}
+
+/// Values substituted into welcome and leave templates.
+///
+/// Placeholders: `{user}` (a mention), `{username}`, `{server}`,
+/// `{member_count}` and `{channel:name}`, which links the guild's text
+/// channel with that name.
+#[derive(Debug, Clone, Default)]
+pub struct WelcomeVars {
+    pub user: String,
+    pub username: String,
+    pub server: String,
+    pub member_count: String,
+    pub avatar_url: String,
+    /// Lowercased channel names to IDs.
+    pub channels: HashMap<String, ChannelId>,
+}
+
+impl WelcomeVars {
+    /// Gather values for `user` in `guild_id`, from the cache where possible.
+    pub async fn new(ctx: &Context, guild_id: GuildId, user: &User) -> Self {
+        let cached = ctx.cache.guild(guild_id).map(|guild| {
+            let channels = guild
+                .channels
+                .values()
+                .filter(|c| matches!(c.kind, ChannelType::Text | ChannelType::News))
+                .map(|c| (c.name.to_lowercase(), c.id))
+                .collect();
+            (guild.name.clone(), guild.member_count, channels)
+        });
+
+        let (server, member_count, channels) = match cached {
+            Some(cached) => cached,
+            None => fetch_guild(ctx, guild_id).await,
+        };
+
+        Self {
+            user: user.mention().to_string(),
+            username: user.name.clone(),
+            server,
+            member_count: member_count.to_string(),
+            avatar_url: user
+                .avatar_url()
+                .unwrap_or_else(|| user.default_avatar_url()),
+            channels,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        if let Some(name) = key.strip_prefix("channel:") {
+            let name = name.trim().trim_start_matches('#').to_lowercase();
+            // An unknown channel still reads naturally rather than as a broken mention.
+            return Some(match self.channels.get(&name) {
+                Some(id) => id.mention().to_string(),
+                None => format!("#{name}"),
+            });
+        }
+
+        let value = match key {
+            "user" => &self.user,
+            "username" => &self.username,
+            "server" => &self.server,
+            "member_count" => &self.member_count,
+            _ => return None,
+        };
+        Some(value.clone())
+    }
+}
+
+async fn fetch_guild(
+    ctx: &Context,
+    guild_id: GuildId,
+) -> (String, u64, HashMap<String, ChannelId>) {
+    let (server, member_count) = match guild_id.to_partial_guild_with_counts(&ctx.http).await {
+        Ok(guild) => (guild.name, guild.approximate_member_count.unwrap_or(0)),
+        Err(e) => {
+            warn!(guild_id = %guild_id, error = %e, "Failed to fetch guild for welcome message");
+            (String::new(), 0)
+        }
+    };
+
+    let channels = match guild_id.channels(&ctx.http).await {
+        Ok(channels) => channels
+            .into_values()
+            .filter(|c| matches!(c.kind, ChannelType::Text | ChannelType::News))
+            .map(|c| (c.name.to_lowercase(), c.id))
+            .collect(),
+        Err(e) => {
+            warn!(guild_id = %guild_id, error = %e, "Failed to fetch channels for welcome message");
+            HashMap::new()
+        }
+    };
+
+    (server, member_count, channels)
+}